@@ -3,7 +3,7 @@ use quote::quote;
 
 /// See the `fn call` docs at the `lib.rs` of this crate for a high level definition.
 pub fn expand_call(def: CallDef) -> proc_macro2::TokenStream {
-	let CallDef { pallet_struct, methods } = def;
+	let CallDef { pallet_struct, methods, context } = def;
 
 	// This is a vector of all the callable function names.
 	let fn_name = methods.iter().map(|method| &method.name).collect::<Vec<_>>();
@@ -13,52 +13,257 @@ pub fn expand_call(def: CallDef) -> proc_macro2::TokenStream {
 	// first two parameters to these calls.
 	let args_name = methods
 		.iter()
-		.map(|method| method.args.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>())
+		.map(|method| method.args.iter().map(|(name, _, _)| name.clone()).collect::<Vec<_>>())
 		.collect::<Vec<_>>();
 
 	// This is a nested vector of all the types for all the arguments for each of the functions in
 	// `fn_name`. It has the same assumptions as `args_name`.
 	let args_type = methods
 		.iter()
-		.map(|method| method.args.iter().map(|(_, type_)| type_.clone()).collect::<Vec<_>>())
+		.map(|method| method.args.iter().map(|(_, type_, _)| type_.clone()).collect::<Vec<_>>())
 		.collect::<Vec<_>>();
 
-	// This quote block creates an `enum Call` which contains all the calls exposed by our pallet,
-	// and the `Dispatch` trait logic to route a `caller` to access those functions.
-	let dispatch_impl = quote! {
+	// This is a nested vector of the arguments passed to each of the functions in `fn_name`, which
+	// borrow the arguments the function takes by reference.
+	let args_passed = methods
+		.iter()
+		.map(|method| {
+			method
+				.args
+				.iter()
+				.map(|(name, _, by_ref)| if *by_ref { quote! { &#name } } else { quote! { #name } })
+				.collect::<Vec<_>>()
+		})
+		.collect::<Vec<_>>();
+
+	// This is a vector of the context argument for each of the functions in `fn_name`, which is
+	// empty for functions which do not take the context.
+	let context_arg = methods
+		.iter()
+		.map(|method| if method.with_context { quote! { context, } } else { quote! {} })
+		.collect::<Vec<_>>();
+
+	// This is a vector of the second argument of each of the functions in `fn_name`. Functions taking
+	// a `caller` can only be called by signed origins, other functions are given the origin as it is.
+	let origin_arg = methods
+		.iter()
+		.map(|method| {
+			if method.with_origin {
+				quote! { origin }
+			} else if method.caller_by_ref {
+				quote! { &crate::support::Origin::ensure_signed(origin)? }
+			} else {
+				quote! { crate::support::Origin::ensure_signed(origin)? }
+			}
+		})
+		.collect::<Vec<_>>();
+
+	// This is a vector of the weight of each of the functions in `fn_name`. Functions without a
+	// `#[weight(n)]` attribute weigh 1.
+	let weight = methods
+		.iter()
+		.map(|method| match &method.weight {
+			Some(weight) => quote! { #weight },
+			None => quote! { 1 },
+		})
+		.collect::<Vec<_>>();
+
+	// This is a nested vector of the types of all the arguments for each of the functions in
+	// `fn_name`, written out like in the function, for the metadata of the calls.
+	let args_type_name = args_type
+		.iter()
+		.map(|types| types.iter().map(|type_| type_name(type_)).collect::<Vec<_>>())
+		.collect::<Vec<_>>();
+
+	// When serialized, every argument type must be serializable. We spell this out as a bound, since
+	// serde cannot infer bounds for associated types like `T::Balance`.
+	let serde_bound = args_type
+		.iter()
+		.flatten()
+		.map(|type_| format!("{}: serde::Serialize + serde::de::DeserializeOwned", quote!(#type_)))
+		.collect::<Vec<_>>()
+		.join(", ");
+
+	// This is a vector of the index of each of the functions in `fn_name`, which encodes the call
+	// before its arguments.
+	let index = (0..methods.len()).map(|index| index as u8).collect::<Vec<_>>();
+
+	// This quote block creates an `enum Call` which contains all the calls exposed by our pallet.
+	let call_enum = quote! {
 		// The callable functions exposed by this pallet.
 		//
 		// The parsed function names will be `snake_case`, and that will show up in the enum.
+		//
+		// With the `serde` feature, a call is represented by its name in the `call` field, next to
+		// its arguments.
 		#[allow(non_camel_case_types)]
+		#[derive(Debug, Clone, PartialEq)]
+		#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+		#[cfg_attr(feature = "serde", serde(tag = "call", bound = #serde_bound))]
 		pub enum Call<T: Config> {
 			#(
 				#fn_name { #( #args_name: #args_type),* },
 			)*
 		}
 
-		// Dispatch logic at the pallet level, mapping each of the items in the `Call` enum to the
-		// appropriate function call with all arguments, including the `caller`.
-		impl<T: Config> crate::support::Dispatch for #pallet_struct<T> {
-			type Caller = T::AccountId;
-			type Call = Call<T>;
-
-			fn dispatch(&mut self, caller: Self::Caller, call: Self::Call) -> crate::support::DispatchResult {
-				match call {
+		// A call is encoded as the index of its function, followed by its arguments. This requires
+		// every argument type to be encodable.
+		impl<T: Config> crate::support::Encode for Call<T>
+		where
+			#( #( #args_type: crate::support::Encode, )* )*
+		{
+			fn encode_to(&self, dest: &mut Vec<u8>) {
+				match self {
 					#(
 						Call::#fn_name { #( #args_name ),* } => {
-							self.#fn_name(
-								// Note that we assume the first argument of every call is the `caller`.
-								caller,
-								#( #args_name ),*
-							)?;
+							crate::support::Encode::encode_to(&#index, dest);
+							#( crate::support::Encode::encode_to(#args_name, dest); )*
+						},
+					)*
+				}
+			}
+		}
+
+		impl<T: Config> Call<T> {
+			// The name of the callable function this call is routed to, like `transfer`.
+			pub fn name(&self) -> &'static str {
+				match *self {
+					#(
+						Call::#fn_name { .. } => stringify!(#fn_name),
+					)*
+				}
+			}
+
+			// The name and parameters of every callable function, in the order they are declared.
+			pub fn metadata() -> Vec<crate::support::CallMetadata> {
+				vec![
+					#(
+						crate::support::CallMetadata {
+							name: stringify!(#fn_name),
+							params: vec![
+								#(
+									crate::support::ParamMetadata {
+										name: stringify!(#args_name),
+										type_name: #args_type_name,
+									},
+								)*
+							],
 						},
 					)*
+				]
+			}
+
+			// The weight declared for this call, which is what the block is charged for it at most. The
+			// `#[weight(n)]` attribute of a function may use its arguments, like the calls of a batch.
+			#[allow(unused_variables)]
+			pub fn weight(&self) -> crate::support::Weight {
+				match self {
+					#(
+						Call::#fn_name { #( #args_name ),* } => crate::support::Weight(#weight),
+					)*
 				}
-				Ok(())
+			}
+		}
+
+		impl<T: Config> crate::support::GetWeight for Call<T> {
+			fn weight(&self) -> crate::support::Weight {
+				Call::weight(self)
 			}
 		}
 	};
 
+	// The match statement routing each of the items in the `Call` enum to the appropriate function
+	// call with all arguments, including the `caller` or the `origin`, and the `context` if the
+	// function takes it. Calls taking a `caller` fail with `BadOrigin` unless their origin is signed.
+	// The callable functions cannot report the weight they actually used up, so we report the weight
+	// declared for the call.
+	let dispatch_match = |dispatch_trait: proc_macro2::TokenStream| quote! {
+		let actual_weight = <Self as #dispatch_trait>::weight(self, &call);
+		match call {
+			#(
+				Call::#fn_name { #( #args_name ),* } => {
+					self.#fn_name(
+						// Note that we assume the first argument of every call is the `caller` or the
+						// `origin`.
+						#origin_arg,
+						#context_arg
+						#( #args_passed ),*
+					)?;
+				},
+			)*
+		}
+		Ok(crate::support::PostDispatchInfo { actual_weight })
+	};
+
+	// Dispatch logic at the pallet level. Pallets without a context implement `Dispatch`, and pallets
+	// with a context implement `DispatchWith`, which is given that context by the runtime.
+	let dispatch_trait_impl = match context {
+		None => {
+			let dispatch_match = dispatch_match(quote! { crate::support::Dispatch });
+			quote! {
+				impl<T: Config> crate::support::Dispatch for #pallet_struct<T> {
+					type Caller = crate::support::Origin<T::AccountId>;
+					type Call = Call<T>;
+
+					fn dispatch(&mut self, origin: Self::Caller, call: Self::Call) -> crate::support::DispatchResultWithPostInfo {
+						#dispatch_match
+					}
+
+					fn weight(&self, call: &Self::Call) -> crate::support::Weight {
+						call.weight()
+					}
+				}
+			}
+		},
+		Some(context) => {
+			let dispatch_match = dispatch_match(quote! { crate::support::DispatchWith });
+			quote! {
+				impl<T: Config> crate::support::DispatchWith for #pallet_struct<T> {
+					type Caller = crate::support::Origin<T::AccountId>;
+					type Call = Call<T>;
+					type Context = #context;
+
+					fn dispatch_with(
+						&mut self,
+						context: &mut Self::Context,
+						origin: Self::Caller,
+						call: Self::Call,
+					) -> crate::support::DispatchResultWithPostInfo {
+						#dispatch_match
+					}
+
+					fn weight(&self, call: &Self::Call) -> crate::support::Weight {
+						call.weight()
+					}
+				}
+			}
+		},
+	};
+
 	// Return the generated code.
-	dispatch_impl.into()
+	quote! {
+		#call_enum
+		#dispatch_trait_impl
+	}
+}
+
+/// Write out a type like it is written in the source, like `T::Balance` or `Vec<T::RuntimeCall>`.
+///
+/// The tokens of a type are printed with a space between every two of them, so here we only keep the
+/// spaces between two words, like in `&mut T`, and after commas.
+fn type_name(type_: &syn::Type) -> String {
+	let tokens = quote!(#type_).to_string();
+	let chars = tokens.chars().collect::<Vec<_>>();
+	let is_word = |c: char| c.is_alphanumeric() || c == '_';
+	let mut name = String::new();
+	for (index, &c) in chars.iter().enumerate() {
+		if c != ' ' {
+			name.push(c);
+		} else if chars[index - 1] == ',' ||
+			(is_word(chars[index - 1]) && chars.get(index + 1).map_or(false, |&c| is_word(c)))
+		{
+			name.push(' ');
+		}
+	}
+	name
 }
@@ -3,17 +3,20 @@ pub mod parse;
 
 /// See the `fn call` docs at the `lib.rs` of this crate for a high level definition.
 pub fn call(
-	_attr: proc_macro::TokenStream,
+	attr: proc_macro::TokenStream,
 	item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
+	let item_mod = syn::parse_macro_input!(item as syn::Item);
+	let call_attr = syn::parse_macro_input!(attr as parse::CallAttr);
+
 	// The final expanded code will be placed here.
 	// Since our macro only adds new code, our final product will contain all of our old code too,
-	// hence we clone `item`.
-	let mut finished = item.clone();
-	let item_mod = syn::parse_macro_input!(item as syn::Item);
+	// except for the function attributes which are only meant for this macro.
+	let stripped = strip_fn_attrs(item_mod.clone());
+	let mut finished: proc_macro::TokenStream = quote::quote!(#stripped).into();
 
 	// First we parse the call functions implemented for the pallet...
-	let generated: proc_macro::TokenStream = match parse::CallDef::try_from(item_mod.clone()) {
+	let generated: proc_macro::TokenStream = match parse::CallDef::try_from(call_attr, item_mod.clone()) {
 		// ..then we generate our new code.
 		Ok(def) => expand::expand_call(def).into(),
 		Err(e) => e.to_compile_error().into(),
@@ -23,3 +26,18 @@ pub fn call(
 	finished.extend(generated);
 	return finished;
 }
+
+/// The attributes on the callable functions which are only meant for this macro.
+const FN_ATTRS: &[&str] = &["weight"];
+
+/// Remove the attributes only meant for this macro from the functions of the `impl` block.
+fn strip_fn_attrs(mut item: syn::Item) -> syn::Item {
+	if let syn::Item::Impl(item_impl) = &mut item {
+		for item in item_impl.items.iter_mut() {
+			if let syn::ImplItem::Fn(method) = item {
+				method.attrs.retain(|attr| !FN_ATTRS.iter().any(|name| attr.path().is_ident(name)));
+			}
+		}
+	}
+	item
+}
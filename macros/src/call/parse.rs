@@ -5,6 +5,7 @@ use syn::spanned::Spanned;
 mod keyword {
 	syn::custom_keyword!(T);
 	syn::custom_keyword!(AccountId);
+	syn::custom_keyword!(context);
 }
 
 /// This object will collect all the information we need to keep while parsing the callable
@@ -16,6 +17,25 @@ pub struct CallDef {
 	pub pallet_struct: syn::Ident,
 	/// This is a list of the callable functions exposed by this pallet. See `CallVariantDef`.
 	pub methods: Vec<CallVariantDef>,
+	/// This is the type of the context the calls of this pallet are dispatched with, if any. It is
+	/// given through the attribute, like `#[macros::call(context = T::Currency)]`.
+	pub context: Option<syn::Type>,
+}
+
+/// This is the attribute given to the call macro, which optionally names the type of the context.
+pub struct CallAttr {
+	pub context: Option<syn::Type>,
+}
+
+impl syn::parse::Parse for CallAttr {
+	fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+		if input.is_empty() {
+			return Ok(Self { context: None })
+		}
+		input.parse::<keyword::context>()?;
+		input.parse::<syn::Token![=]>()?;
+		Ok(Self { context: Some(input.parse()?) })
+	}
 }
 
 /// This is the metadata we keep about each callable function in our pallet.
@@ -23,12 +43,24 @@ pub struct CallDef {
 pub struct CallVariantDef {
 	/// The function name.
 	pub name: syn::Ident,
-	/// Information on args of the function: `(name, type)`.
-	pub args: Vec<(syn::Ident, Box<syn::Type>)>,
+	/// Information on args of the function: `(name, type, by_ref)`. An argument the function takes by
+	/// reference, like `to: &T::AccountId`, has the type it refers to, and is stored in the `Call` by
+	/// value.
+	pub args: Vec<(syn::Ident, Box<syn::Type>, bool)>,
+	/// Whether the function takes the whole `origin: Origin<T::AccountId>` as its second argument,
+	/// instead of the `caller: T::AccountId` who signed the call.
+	pub with_origin: bool,
+	/// Whether the function takes the `caller` by reference, like `caller: &T::AccountId`.
+	pub caller_by_ref: bool,
+	/// Whether the function takes the context as its third argument, right after the `caller`.
+	pub with_context: bool,
+	/// The weight of the call, given by the `#[weight(n)]` attribute on the function. It is `None`
+	/// for functions without that attribute, whose calls weigh 1.
+	pub weight: Option<syn::Expr>,
 }
 
 impl CallDef {
-	pub fn try_from(item: syn::Item) -> syn::Result<Self> {
+	pub fn try_from(attr: CallAttr, item: syn::Item) -> syn::Result<Self> {
 		// First we check that we are parsing an `impl`.
 		let item_impl = if let syn::Item::Impl(item) = item {
 			item
@@ -59,23 +91,32 @@ impl CallDef {
 					},
 				}
 
-				// The second argument should be the `caller: T::AccountId` argument.
-				match method.sig.inputs.iter().skip(1).next() {
+				// The second argument should be the `caller: T::AccountId` argument, or the
+				// `origin: Origin<T::AccountId>` argument for calls which accept any origin.
+				let (with_origin, caller_by_ref) = match method.sig.inputs.iter().skip(1).next() {
+					Some(syn::FnArg::Typed(arg)) if is_origin_arg(arg) => (true, false),
 					Some(syn::FnArg::Typed(arg)) => {
 						// Here we specifically check that this argument is as we expect for
 						// `caller: T::AccountId`.
-						check_caller_arg(arg)?;
+						(false, check_caller_arg(arg)?)
 					},
 					_ => {
 						let msg = "Invalid call, second argument should be `caller: T::AccountId`";
 						return Err(syn::Error::new(method.sig.span(), msg))
 					},
-				}
+				};
 
 				let fn_name = method.sig.ident.clone();
 
-				// Parsing the rest of the args. Skipping 2 for `self` and `caller`.
-				for arg in method.sig.inputs.iter().skip(2) {
+				// The third argument may be the context, i.e. a `&mut` reference to the context type.
+				let with_context = match (&attr.context, method.sig.inputs.iter().nth(2)) {
+					(Some(context), Some(syn::FnArg::Typed(arg))) => is_context_arg(arg, context),
+					_ => false,
+				};
+
+				// Parsing the rest of the args. Skipping 2 for `self` and `caller`, and the context.
+				let skip = if with_context { 3 } else { 2 };
+				for arg in method.sig.inputs.iter().skip(skip) {
 					// All arguments should be typed.
 					let arg = if let syn::FnArg::Typed(arg) = arg {
 						arg
@@ -91,24 +132,64 @@ impl CallDef {
 						return Err(syn::Error::new(arg.pat.span(), msg))
 					};
 
-					// Store the argument name and the argument type for generating code.
-					args.push((arg_ident, arg.ty.clone()));
+					// Store the argument name and the argument type for generating code. Arguments taken
+					// by shared reference are stored as the type they refer to.
+					match &*arg.ty {
+						syn::Type::Reference(reference) if reference.mutability.is_none() => {
+							args.push((arg_ident, reference.elem.clone(), true));
+						},
+						_ => args.push((arg_ident, arg.ty.clone(), false)),
+					}
 				}
 
+				// The weight of the call may be given as an attribute, like `#[weight(5)]`.
+				let weight = match method.attrs.iter().find(|attr| attr.path().is_ident("weight")) {
+					Some(attr) => Some(attr.parse_args()?),
+					None => None,
+				};
+
 				// Store all the function name and the arg data for the function.
-				methods.push(CallVariantDef { name: fn_name, args });
+				methods.push(CallVariantDef { name: fn_name, args, with_origin, caller_by_ref, with_context, weight });
 			}
 		}
 
 		// Return all callable functions for this pallet.
-		Ok(Self { pallet_struct, methods })
+		Ok(Self { pallet_struct, methods, context: attr.context })
+	}
+}
+
+/// Check whether an arg is a mutable reference to the `context` type, like `&mut T::Currency`.
+fn is_context_arg(arg: &syn::PatType, context: &syn::Type) -> bool {
+	match &*arg.ty {
+		syn::Type::Reference(reference) => {
+			reference.mutability.is_some() &&
+				reference.elem.to_token_stream().to_string() == context.to_token_stream().to_string()
+		},
+		_ => false,
 	}
 }
 
-/// Check caller arg is exactly: `caller: T::AccountId`.
+/// Check whether an arg is the origin of the call, like `origin: Origin<T::AccountId>`.
+///
+/// Only the name of the argument and the last segment of its type are checked, so the type may be
+/// given by its full path, like `crate::support::Origin<T::AccountId>`.
+fn is_origin_arg(arg: &syn::PatType) -> bool {
+	let named_origin = match &*arg.pat {
+		syn::Pat::Ident(ident) => ident.ident == "origin" || ident.ident == "_origin",
+		_ => false,
+	};
+	let typed_origin = match &*arg.ty {
+		syn::Type::Path(tp) => tp.path.segments.last().map_or(false, |segment| segment.ident == "Origin"),
+		_ => false,
+	};
+	named_origin && typed_origin
+}
+
+/// Check caller arg is exactly: `caller: T::AccountId`, or `caller: &T::AccountId`, and return whether
+/// it is taken by reference.
 ///
 /// This is kept strict to keep the code simple.
-pub fn check_caller_arg(arg: &syn::PatType) -> syn::Result<()> {
+pub fn check_caller_arg(arg: &syn::PatType) -> syn::Result<bool> {
 	pub struct CheckDispatchableFirstArg;
 	impl syn::parse::Parse for CheckDispatchableFirstArg {
 		fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
@@ -128,8 +209,12 @@ pub fn check_caller_arg(arg: &syn::PatType) -> syn::Result<()> {
 		}
 	}
 
-	// This checks the type is `T::AccountId` with `CheckDispatchableFirstArg`
-	let ty = &arg.ty;
+	// This checks the type is `T::AccountId` with `CheckDispatchableFirstArg`, behind a shared
+	// reference or not.
+	let (ty, by_ref) = match &*arg.ty {
+		syn::Type::Reference(reference) if reference.mutability.is_none() => (&reference.elem, true),
+		_ => (&arg.ty, false),
+	};
 	syn::parse2::<CheckDispatchableFirstArg>(ty.to_token_stream()).map_err(|e| {
 		let msg = "Invalid type for second parameter: expected `caller: T::AccountId`";
 		let mut err = syn::Error::new(ty.span(), msg);
@@ -137,5 +222,5 @@ pub fn check_caller_arg(arg: &syn::PatType) -> syn::Result<()> {
 		err
 	})?;
 
-	Ok(())
+	Ok(by_ref)
 }
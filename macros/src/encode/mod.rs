@@ -0,0 +1,115 @@
+use quote::quote;
+use syn::spanned::Spanned;
+
+/// See the `fn encode` docs at the `lib.rs` of this crate for a high level definition.
+pub fn encode(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	let input = syn::parse_macro_input!(item as syn::DeriveInput);
+	match expand_encode(input) {
+		Ok(expanded) => expanded.into(),
+		Err(e) => e.to_compile_error().into(),
+	}
+}
+
+/// Generate the `impl` of `support::Encode` for a struct or an enum.
+fn expand_encode(input: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+	let name = &input.ident;
+
+	// Every field which is encoded must have an encodable type. We spell this out as a bound, since
+	// the fields may have types like `T::Balance`, which can be anything.
+	let mut field_types = Vec::new();
+
+	let body = match &input.data {
+		syn::Data::Struct(data) => {
+			let (pattern, encode) = expand_fields(&data.fields, &mut field_types)?;
+			quote! {
+				let #name #pattern = self;
+				#( #encode )*
+			}
+		},
+		// Each variant is encoded as its index, followed by its fields.
+		syn::Data::Enum(data) => {
+			let mut arms = Vec::new();
+			for (index, variant) in data.variants.iter().enumerate() {
+				let variant_name = &variant.ident;
+				let index = index as u8;
+				let (pattern, encode) = expand_fields(&variant.fields, &mut field_types)?;
+				arms.push(quote! {
+					#name::#variant_name #pattern => {
+						crate::support::Encode::encode_to(&#index, dest);
+						#( #encode )*
+					},
+				});
+			}
+			quote! {
+				match self {
+					#( #arms )*
+				}
+			}
+		},
+		syn::Data::Union(_) => {
+			return Err(syn::Error::new(input.span(), "Invalid Encode, unions are not supported"))
+		},
+	};
+
+	let mut generics = input.generics.clone();
+	let where_clause = generics.make_where_clause();
+	for type_ in field_types {
+		where_clause.predicates.push(syn::parse_quote! { #type_: crate::support::Encode });
+	}
+	let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+	Ok(quote! {
+		impl #impl_generics crate::support::Encode for #name #type_generics #where_clause {
+			fn encode_to(&self, dest: &mut Vec<u8>) {
+				#body
+			}
+		}
+	})
+}
+
+/// Generate the pattern binding every field, and the statements encoding each of them in order.
+/// Fields marked `#[encode(skip)]` are bound to `_`, and their types are not added to `field_types`.
+fn expand_fields(
+	fields: &syn::Fields,
+	field_types: &mut Vec<syn::Type>,
+) -> syn::Result<(proc_macro2::TokenStream, Vec<proc_macro2::TokenStream>)> {
+	let mut bindings = Vec::new();
+	let mut encode = Vec::new();
+	for (index, field) in fields.iter().enumerate() {
+		let binding = quote::format_ident!("field_{}", index);
+		let skip = is_skipped(field)?;
+		let pattern = if skip { quote! { _ } } else { quote! { #binding } };
+		bindings.push(match &field.ident {
+			Some(ident) => quote! { #ident: #pattern },
+			None => pattern,
+		});
+		if !skip {
+			encode.push(quote! { crate::support::Encode::encode_to(#binding, dest); });
+			field_types.push(field.ty.clone());
+		}
+	}
+
+	let pattern = match fields {
+		syn::Fields::Named(_) => quote! { { #( #bindings ),* } },
+		syn::Fields::Unnamed(_) => quote! { ( #( #bindings ),* ) },
+		syn::Fields::Unit => quote! {},
+	};
+	Ok((pattern, encode))
+}
+
+/// Check whether a field is marked `#[encode(skip)]`, like the events of a pallet, which are not
+/// part of its state.
+fn is_skipped(field: &syn::Field) -> syn::Result<bool> {
+	let mut skip = false;
+	for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("encode")) {
+		attr.parse_nested_meta(|meta| {
+			if meta.path.is_ident("skip") {
+				skip = true;
+				Ok(())
+			} else {
+				Err(meta.error("Invalid encode attribute, expected `skip`"))
+			}
+		})?;
+	}
+	Ok(skip)
+}
@@ -1,6 +1,29 @@
 mod call;
+mod encode;
 mod runtime;
 
+/// Expand the callable functions of a pallet.
+///
+/// This generates an `enum Call` with a variant for every function in the `impl` block, and
+/// implements `support::Dispatch` to route each variant to its function. The `name()` of a `Call` is
+/// the name of the function it is routed to, like `"create_claim"`, and its `weight()` is given by
+/// a `#[weight(n)]` attribute on that function, or 1 without one. The weight may be computed from the
+/// arguments of the call, like `#[weight(calls.len() as u64)]`. `Call` implements `support::GetWeight`
+/// with that weight. Every function must take
+/// `caller: T::AccountId` as its first argument after `self`, in which case its calls fail with
+/// `BadOrigin` unless their `support::Origin` is signed, or it must take the whole
+/// `origin: Origin<T::AccountId>`, like the privileged calls checking for the root origin. Since the
+/// functions only return a `support::DispatchResult`, every call reports its declared weight as the
+/// weight it actually used. The `caller` and the other arguments may be taken by shared reference,
+/// like `to: &T::AccountId`, in which case the `Call` holds them by value and lends them to the
+/// function, so the function does not have to clone them.
+/// `support::Encode` is implemented for every `Call` whose argument types are encodable, by encoding
+/// the index of its function followed by its arguments. `Call::metadata()` describes every function,
+/// with the names of its arguments and their types as they are written, in a `support::CallMetadata`.
+///
+/// Pallets whose calls need something outside of the pallet, like a currency, can name the type of
+/// that context as `#[macros::call(context = T::Currency)]`. Functions may then take a
+/// `&mut T::Currency` right after the `caller` or the `origin`, and `support::DispatchWith` is implemented instead.
 #[proc_macro_attribute]
 pub fn call(
 	attr: proc_macro::TokenStream,
@@ -13,18 +36,80 @@ pub fn call(
 ///
 /// This generates function implementations on `Runtime`:
 /// - `fn new()` - which generates a new instance of the runtime, by instantiating all the pallets
-///   included in the runtime.
+///   included in the runtime. Fields which are not pallets, i.e. whose type is not named `Pallet`,
+///   are set to their default value.
+/// - `fn validate_block()` - which checks, without modifying any state, that a block has the
+///   `expected_block_number()` of the system pallet, a timestamp later than the previous block, the hash of the previous block as its
+///   parent hash, and an extrinsics root matching `support::compute_extrinsics_root` of its
+///   extrinsics, computed with the `Hashing` of the system config. `execute_block` stores the hash
+///   of every block in the system pallet, hashed with the same hasher, and
+///   `fn parent_hash()` gets the hash the next block must name as its parent.
 /// - `fn execute_block()` - which handles basic logic for executing a block of extrinsics. It does
 ///   basic actions like incrementing the block number and checking the block to be executed has a
-///   valid block number.
+///   valid block number. The block is charged the `actual_weight` each call reports in its
+///   `support::PostDispatchInfo`, which lets more extrinsics fit when calls use up less weight than
+///   they declare. It also calls the `support::Hooks` of every pallet before and after the
+///   extrinsics, and collects the events emitted by every pallet into the system pallet. The accounts
+///   created or removed by pallets marked `#[account_provider]`, which implement
+///   `support::AccountProvider`, are reported to the system pallet along with the events. Before
+///   the call of an extrinsic is dispatched, the pallet marked `#[charge_fees(other_pallet)]`, which
+///   implements `support::ChargeExtrinsicFee`, charges its fee from that other pallet. An extrinsic
+///   whose fee cannot be paid is skipped, as is an extrinsic whose `support::SignedExtrinsic` signature
+///   was not made by its caller, or whose caller is over the rate limit the system pallet keeps with
+///   `ensure_rate_limit` and `record_extrinsic`. Only an extrinsic which is valid otherwise is skipped
+///   for not fitting into the block, so invalid extrinsics never fill it. Failed extrinsics are reported to the `support::Logger` in the `logger` field, or to stderr if there
+///   is no such field.
+/// - `fn execute_block_detailed()` - which executes a block like `execute_block`, but returns a
+///   `support::ExecutionReport` with the number of extrinsics included and skipped, the weight the
+///   block was charged, and the index of every failed extrinsic along with its error.
+/// - `fn state_root()` - which hashes the `support::Encode` encoding of the storage of every pallet,
+///   in the order of the fields, with the `support::Hasher` the system config names as its
+///   `Hashing`. `execute_block` stores the state root after every block in the
+///   system pallet, and `fn verify_state_root()` compares it to an expected state root.
+/// - `fn execute_blocks()` - which executes a chain of blocks in order, stopping at the first invalid
+///   block and returning its index along with the error.
+/// - `fn take_events()` - which takes all the events collected by `execute_block`.
+/// - `fn events()`, `fn next_event_index()`, `fn events_since()` & `fn prune_events()` - which read the collected events
+///   without taking them, by the index every event gets when it is collected, and drop the events
+///   before some index once they have been read. Taking the events keeps the indices going.
+/// - `fn runtime_upgrade()` - which calls `support::Hooks::on_runtime_upgrade` once on every pallet
+///   whose storage the system pallet records as stored with a different version than the
+///   `support::Hooks::STORAGE_VERSION` of the pallet, and then records the current version. The
+///   accounts created or reaped by the migrations are reported to the system pallet. `fn new()`
+///   records the current version of every pallet.
+/// - `fn snapshot()` & `fn restore()` - which capture the storage of every pallet into a
+///   `RuntimeSnapshot`, and roll back to it. This requires the `Runtime` to implement `Clone`.
+/// - `fn dry_run_block()` - which executes a block against a snapshot, returning the result of every
+///   extrinsic, and then rolls back to the snapshot. Failed extrinsics are not reported to the logger.
+/// - `fn metadata()` - which describes the calls of every pallet, not including system, with the
+///   names and the types of their parameters, in a `support::RuntimeMetadata`.
+/// - `fn undo_last_block()` - which undoes the mutations every pallet journaled while
+///   `execute_block` executed the last block. Every pallet, including system, must implement
+///   `support::Journaled`, whose default functions journal nothing.
+/// - `fn dispatch_named()` - which dispatches a call like `support::Dispatch`, and returns the name
+///   of the pallet which handled it.
 ///
 /// This also generates code needed for dispatching calls to the pallets:
 /// - Note: For simplicity, we assume that the system pallet is not callable.
 /// - `enum RuntimeCall` - an "outer"-enum representing the accumulation of all possible calls to
-///   all pallets. The system pallet is not included.
-/// - implements the trait `support::Dispatch` to dispatch calls to the appropriate pallet. Basic
-///   logic like incrementing the nonce of the user is included in the generated code. The system
-///   pallet is not included.
+///   all pallets. The system pallet is not included. Its `pallet_name()` and `name()` name the
+///   pallet and the call within that pallet, which are logged along with the errors of extrinsics.
+///   It implements `support::Encode` by encoding the index of the pallet, followed by its call, and
+///   `support::GetWeight` with the weight of that call.
+/// - `enum RuntimeEvent` - an "outer"-enum representing the accumulation of all possible events
+///   emitted by all pallets. Every pallet must have an `Event` type and a `take_events` function.
+/// - implements the trait `support::Dispatch` to dispatch calls to the appropriate pallet, on behalf
+///   of a `support::Origin`. `execute_block` dispatches every extrinsic with the signed origin of its
+///   caller, and basic logic like incrementing the nonce of the user is included there. The system
+///   pallet is not included. A pallet implementing `support::DispatchWith` is given the pallet
+///   named by its field attribute as context, like `#[dispatch_with(balances)]`. After every call,
+///   and after the `on_initialize` hooks, the calls queued up by pallets marked `#[queued_calls]`,
+///   which implement `support::QueuedCalls`, are dispatched too. After every call, the batches
+///   queued up by pallets marked `#[batch_calls]`, which implement `support::BatchCalls`, are
+///   dispatched according to their `support::BatchMode`. An atomic batch which fails is rolled back
+///   with the `support::Journaled` checkpoints of every pallet, along with the events it emitted. The
+///   weight declared for a call queuing batches is refunded for every call of its batches which used
+///   up less than declared, or which was never dispatched.
 #[proc_macro_attribute]
 pub fn runtime(
 	attr: proc_macro::TokenStream,
@@ -32,3 +117,14 @@ pub fn runtime(
 ) -> proc_macro::TokenStream {
 	runtime::runtime(attr, item)
 }
+
+/// Derive `support::Encode` for a struct or an enum.
+///
+/// The fields are encoded one after the other, in the order they are declared. An enum encodes
+/// the index of its variant first, followed by the fields of that variant. Fields which are not part
+/// of the state, like the events of a pallet, can be left out with `#[encode(skip)]`. Every other
+/// field must have an encodable type.
+#[proc_macro_derive(Encode, attributes(encode))]
+pub fn encode(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	encode::encode(item)
+}
@@ -3,81 +3,635 @@ use quote::quote;
 
 /// See the `fn runtime` docs at the `lib.rs` of this crate for a high level definition.
 pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
-	let RuntimeDef { runtime_struct, pallets } = def;
+	let RuntimeDef { runtime_struct, pallets, contexts, queued, batched, providers, fees, others } = def;
 
 	// This is a vector of all the pallet names, not including system.
 	let pallet_names = pallets.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
+	// This is a vector of the index of each pallet, not including system, which encodes its calls.
+	let pallet_index = (0..pallets.len()).map(|index| index as u8).collect::<Vec<_>>();
 	// This is a vector of all the pallet types, not including system.
 	let pallet_types = pallets.iter().map(|(_, type_)| type_.clone()).collect::<Vec<_>>();
+	// This is a vector of the expressions getting the current version of the storage layout of each
+	// pallet, not including system.
+	let storage_versions = pallet_types
+		.iter()
+		.map(|type_| quote! {
+			<#type_ as crate::support::Hooks<<#runtime_struct as system::Config>::BlockNumber>>::STORAGE_VERSION
+		})
+		.collect::<Vec<_>>();
+	// This is a vector of the expressions dispatching a call to each pallet, and of the expressions
+	// getting the weight of such a call. Pallets with a context are dispatched through
+	// `DispatchWith`, and are given mutable access to the pallet acting as their context.
+	let (pallet_dispatch, pallet_weight): (Vec<_>, Vec<_>) = pallet_names
+		.iter()
+		.zip(&contexts)
+		.map(|(name, context)| match context {
+			Some(context) => (
+				quote! {
+					crate::support::DispatchWith::dispatch_with(&mut self.#name, &mut self.#context, origin, call)
+				},
+				quote! { crate::support::DispatchWith::weight(&self.#name, call) },
+			),
+			None => (
+				quote! { crate::support::Dispatch::dispatch(&mut self.#name, origin, call) },
+				quote! { crate::support::Dispatch::weight(&self.#name, call) },
+			),
+		})
+		.unzip();
+	// The fee of every extrinsic is charged by the pallet marked `#[charge_fees]`, from the pallet
+	// named by that attribute. Without such a pallet, extrinsics are free.
+	let charge_fee = match &fees {
+		Some((pallet, context)) => quote! {
+			crate::support::ChargeExtrinsicFee::charge_extrinsic_fee(&mut self.#pallet, &mut self.#context, who)
+		},
+		None => quote! { Ok(()) },
+	};
+	// Extrinsic errors are reported to the `logger` field if there is one, or to stderr otherwise.
+	let logger = if others.iter().any(|name| name == "logger") {
+		quote! { &self.logger }
+	} else {
+		quote! { &crate::support::StderrLogger }
+	};
 
 	// This quote block implements functions on the `Runtime` struct.
 	let runtime_impl = quote! {
 		impl #runtime_struct {
 			// Create a new instance of the main Runtime, by creating a new instance of each pallet.
 			fn new() -> Self {
-				Self {
+				let mut runtime = Self {
 					// Since system is not included in the list of pallets, we manually add it here.
 					system: <system::Pallet::<Self>>::new(),
 					#(
-						#pallet_names: <#pallet_types>::new()
-					),*
+						#pallet_names: <#pallet_types>::new(),
+					)*
+					// All the other fields start out with their default value.
+					#(
+						#others: Default::default(),
+					)*
+				};
+				// A new runtime stores every pallet with the current layout, so it has nothing to migrate.
+				#(
+					runtime.system.set_storage_version(stringify!(#pallet_names), #storage_versions);
+				)*
+				runtime
+			}
+
+			// Check that a block can be executed on top of the current state, without modifying it. Its
+			// timestamp must be later than the timestamp of the current block.
+			//
+			// Only the block as a whole is validated here. Whether each extrinsic has a valid nonce
+			// and enough funds depends on the extrinsics before it, so that is checked while
+			// executing the block.
+			fn validate_block(&self, block: &types::Block) -> crate::support::DispatchResult {
+				// No block can follow the largest block number.
+				self.system.next_block_number()?;
+				if block.header.block_number != self.system.expected_block_number() {
+					return Err(crate::support::DispatchError::InvalidBlockNumber)
+				}
+				if block.header.timestamp <= self.system.timestamp() {
+					return Err(crate::support::DispatchError::TimestampNotIncreasing)
+				}
+				if block.header.parent_hash != self.parent_hash() {
+					return Err(crate::support::DispatchError::ParentHashMismatch)
 				}
+				if block.header.extrinsics_root != crate::support::compute_extrinsics_root::<<#runtime_struct as system::Config>::Hashing, _>(&block.extrinsics) {
+					return Err(crate::support::DispatchError::InvalidExtrinsicsRoot)
+				}
+				Ok(())
 			}
 
 			// Execute a block of extrinsics. Increments the block number.
-			fn execute_block(&mut self, block: types::Block) -> crate::support::DispatchResult {
-				self.system.inc_block_number();
-				if block.header.block_number != self.system.block_number() {
-					return Err(&"block number does not match what is expected")
+			//
+			// Returns the result of every extrinsic in the block, in order. Extrinsics are executed
+			// until the block weight limit would be exceeded, after which the remaining extrinsics
+			// are skipped. An invalid block is rejected before any state is modified.
+			//
+			// Every mutation made while executing the block is journaled, so that `undo_last_block`
+			// can undo the block later.
+			fn execute_block(
+				&mut self,
+				block: types::Block,
+			) -> Result<Vec<crate::support::DispatchResult>, crate::support::DispatchError> {
+				self.execute_block_detailed(block).map(|report| report.results())
+			}
+
+			// Execute a block of extrinsics like `execute_block`, but report how many extrinsics were
+			// included or skipped, and the weight the block was charged, along with the errors.
+			fn execute_block_detailed(
+				&mut self,
+				block: types::Block,
+			) -> Result<crate::support::ExecutionReport, crate::support::DispatchError> {
+				self.apply_block(block, true)
+			}
+
+			// Execute a block like `execute_block_detailed`, reporting every failed extrinsic to the
+			// logger only if `log_errors` is set. The errors are always part of the report.
+			fn apply_block(
+				&mut self,
+				block: types::Block,
+				log_errors: bool,
+			) -> Result<crate::support::ExecutionReport, crate::support::DispatchError> {
+				self.validate_block(&block)?;
+				crate::support::Journaled::start_journal(&mut self.system);
+				#(
+					crate::support::Journaled::start_journal(&mut self.#pallet_names);
+				)*
+				self.system.inc_block_number()?;
+				self.system.set_timestamp(block.header.timestamp);
+				// Let every pallet know that a new block has started.
+				#(
+					crate::support::Hooks::on_initialize(&mut self.#pallet_names, block.header.block_number);
+				)*
+				// Dispatch the calls queued up while initializing the block, like scheduled calls. They
+				// are not part of any extrinsic, so their results are not reported.
+				let _ = self.dispatch_queued_calls();
+				self.collect_provider_changes();
+				self.collect_events();
+				let max_weight = <#runtime_struct as system::Config>::MAX_BLOCK_WEIGHT;
+				let mut used_weight = crate::support::Weight(0);
+				let mut block_full = false;
+				let mut report = crate::support::ExecutionReport::default();
+				let extrinsic_count = block.extrinsics.len();
+				for (i, extrinsic) in block.extrinsics.into_iter().enumerate() {
+					let signed = extrinsic.verify();
+					let crate::support::SignedExtrinsic { caller, call, nonce, .. } = extrinsic;
+					let (pallet_name, call_name) = (call.pallet_name(), call.name());
+					let weight = self.weight(&call);
+					let total_weight = crate::support::Weight(used_weight.0.saturating_add(weight.0));
+
+					// An extrinsic which was not signed by its caller is skipped.
+					let res = if !signed {
+						Err(crate::support::DispatchError::InvalidSignature)
+					} else if nonce != self.system.nonce(&caller) {
+						// An extrinsic must carry the caller's current nonce, otherwise it is skipped too.
+						// This prevents the same extrinsic from being executed twice.
+						Err(crate::support::DispatchError::InvalidNonce)
+					} else if let Err(e) = self.system.ensure_rate_limit(&caller) {
+						// An extrinsic over the rate limit of its caller is skipped too, before paying
+						// any fee.
+						Err(e)
+					} else if block_full || total_weight > max_weight {
+						// Once a valid extrinsic does not fit into the block anymore, we stop executing.
+						// Whether its fee can be paid is checked first, without keeping the payment, so an
+						// extrinsic which would be skipped anyway never fills the block.
+						let checkpoint = self.checkpoint_journals();
+						let res = self.charge_extrinsic_fee(&caller).and(Err(crate::support::DispatchError::ExhaustsResources));
+						self.rollback_journals(checkpoint);
+						block_full = block_full || res == Err(crate::support::DispatchError::ExhaustsResources);
+						res
+					} else if let Err(e) = self.charge_extrinsic_fee(&caller) {
+						// An extrinsic whose fee cannot be paid is skipped like one with an invalid nonce.
+						Err(e)
+					} else {
+						// The events of paying the fee come before the events of the call.
+						self.collect_provider_changes();
+						self.collect_events();
+						self.system.inc_nonce(&caller);
+						self.system.record_extrinsic(&caller);
+						report.included += 1;
+						// The block is only charged the weight the call actually used up, which is never
+						// more than its declared weight. A failed call is charged its declared weight.
+						let res = self.dispatch(crate::support::Origin::Signed(caller), call);
+						let actual_weight = res.as_ref().map_or(weight, |info| info.actual_weight.min(weight));
+						used_weight = crate::support::Weight(used_weight.0.saturating_add(actual_weight.0));
+						res.map(|_| ())
+					};
+					// Collect the events emitted while executing this extrinsic, so that the events of
+					// the whole block end up in extrinsic order.
+					self.collect_provider_changes();
+					self.collect_events();
+					if let Err(e) = res {
+						if log_errors {
+							crate::support::Logger::log_extrinsic_error(
+								#logger,
+								block.header.block_number,
+								i,
+								&format!("{}::{}", pallet_name, call_name),
+								&e.to_string(),
+							);
+						}
+						report.errors.push((i, e));
+					}
 				}
-				for (i, support::Extrinsic { caller, call }) in block.extrinsics.into_iter().enumerate() {
-					self.system.inc_nonce(&caller);
-					let _res = self.dispatch(caller, call).map_err(|e| {
-						eprintln!(
-							"Extrinsic Error\n\tBlock Number: {}\n\tExtrinsic Number: {}\n\tError: {}",
-							block.header.block_number, i, e
-						)
-					});
+				// Every extrinsic which was not included was skipped.
+				report.skipped = extrinsic_count - report.included;
+				report.total_weight = used_weight;
+				// Let every pallet know that the block has ended.
+				#(
+					crate::support::Hooks::on_finalize(&mut self.#pallet_names, block.header.block_number);
+				)*
+				self.collect_provider_changes();
+				self.collect_events();
+				let block_hash = block.header.hash_with::<<#runtime_struct as system::Config>::Hashing>();
+				self.system.set_block_hash(block.header.block_number, block_hash);
+				let state_root = self.state_root();
+				self.system.set_state_root(state_root);
+				crate::support::Journaled::stop_journal(&mut self.system);
+				#(
+					crate::support::Journaled::stop_journal(&mut self.#pallet_names);
+				)*
+				Ok(report)
+			}
+
+			// Execute a chain of blocks one after the other, each on top of the one before it.
+			//
+			// Stops at the first invalid block, returning its index in `blocks` along with the error.
+			// The blocks before it stay executed. Extrinsics failing within a block do not stop the
+			// chain, since they do not make the block invalid.
+			fn execute_blocks(
+				&mut self,
+				blocks: Vec<types::Block>,
+			) -> Result<(), (usize, crate::support::DispatchError)> {
+				for (index, block) in blocks.into_iter().enumerate() {
+					self.execute_block(block).map_err(|error| (index, error))?;
 				}
 				Ok(())
 			}
+
+			// Undo every mutation journaled while executing the last block, so the storage of every
+			// pallet is back to what it was before that block. Only the last block can be undone,
+			// and the events it emitted are not taken back.
+			fn undo_last_block(&mut self) {
+				crate::support::Journaled::undo_journal(&mut self.system);
+				#(
+					crate::support::Journaled::undo_journal(&mut self.#pallet_names);
+				)*
+			}
+
+			// The hash of the current block, which the next block must name as its parent. Before the
+			// first block, this is zero.
+			fn parent_hash(&self) -> <#runtime_struct as system::Config>::Hash {
+				self.system.block_hash(self.system.block_number()).unwrap_or_default()
+			}
+
+			// Hash the storage of every pallet with the `Hashing` of the system config, in the order of
+			// the fields of the runtime, starting with the system pallet. Runtimes with the same storage
+			// always have the same state root.
+			fn state_root(&self) -> <#runtime_struct as system::Config>::Hash {
+				let mut storage = Vec::new();
+				crate::support::Encode::encode_to(&self.system, &mut storage);
+				#(
+					crate::support::Encode::encode_to(&self.#pallet_names, &mut storage);
+				)*
+				<<#runtime_struct as system::Config>::Hashing as crate::support::Hasher>::hash(&storage)
+			}
+
+			// Check that the state root after executing the last block is the `expected` one.
+			fn verify_state_root(
+				&self,
+				expected: <#runtime_struct as system::Config>::Hash,
+			) -> crate::support::DispatchResult {
+				if self.system.state_root() != Some(expected) {
+					return Err(crate::support::DispatchError::StateRootMismatch)
+				}
+				Ok(())
+			}
+
+			// Dispatch a call like `dispatch` does, returning the name of the pallet which handled it,
+			// like `balances`. This allows tallying the calls dispatched to each pallet.
+			fn dispatch_named(
+				&mut self,
+				origin: crate::support::Origin<<#runtime_struct as system::Config>::AccountId>,
+				runtime_call: RuntimeCall,
+			) -> Result<&'static str, crate::support::DispatchError> {
+				let pallet = runtime_call.pallet_name();
+				crate::support::Dispatch::dispatch(self, origin, runtime_call)?;
+				Ok(pallet)
+			}
+
+			// Charge the account `who` the fee of an extrinsic, before its call is dispatched.
+			fn charge_extrinsic_fee(
+				&mut self,
+				who: &<#runtime_struct as system::Config>::AccountId,
+			) -> crate::support::DispatchResult {
+				#charge_fee
+			}
+
+			// Dispatch the calls queued up by the pallets, signed by their own callers. Every queued
+			// call is dispatched, and the first error is returned.
+			fn dispatch_queued_calls(&mut self) -> crate::support::DispatchResult {
+				let mut result = Ok(());
+				#(
+					for (caller, call) in crate::support::QueuedCalls::take_queued_calls(&mut self.#queued) {
+						let origin = crate::support::Origin::Signed(caller);
+						result = result.and(crate::support::Dispatch::dispatch(self, origin, call).map(|_| ()));
+					}
+				)*
+				result
+			}
+
+			// Dispatch the batches queued up by the pallets, each call on behalf of the origin of its
+			// batch. An atomic batch is rolled back as a whole once one of its calls fails, and its
+			// error is returned. The results of every other batch are reported to the pallet queuing
+			// it instead.
+			//
+			// Returns the weight to refund to the call which queued the batches: the weight every
+			// dispatched call declared but did not use up, and the weight of every call which was not
+			// dispatched because its batch was interrupted.
+			fn dispatch_batches(&mut self) -> Result<crate::support::Weight, crate::support::DispatchError> {
+				let mut refund = crate::support::Weight(0);
+				#(
+					for batch in crate::support::BatchCalls::take_batches(&mut self.#batched) {
+						let crate::support::Batch { origin, calls, mode } = batch;
+						let checkpoint = (mode == crate::support::BatchMode::Atomic).then(|| self.checkpoint_journals());
+						let mut results = Vec::new();
+						let mut calls = calls.into_iter();
+						for call in calls.by_ref() {
+							let weight = crate::support::GetWeight::weight(&call);
+							let res = crate::support::Dispatch::dispatch(self, origin.clone(), call);
+							let unused = res.as_ref().map_or(0, |info| weight.0.saturating_sub(info.actual_weight.0));
+							refund = crate::support::Weight(refund.0.saturating_add(unused));
+							let failed = res.is_err();
+							results.push(res.map(|_| ()));
+							if failed && mode != crate::support::BatchMode::Forced {
+								break;
+							}
+						}
+						match (checkpoint, results.last()) {
+							(Some(checkpoint), Some(&Err(e))) => {
+								self.rollback_journals(checkpoint);
+								return Err(e);
+							},
+							(Some(checkpoint), _) => self.commit_journals(checkpoint),
+							_ => {},
+						}
+						for call in calls {
+							refund = crate::support::Weight(refund.0.saturating_add(crate::support::GetWeight::weight(&call).0));
+						}
+						crate::support::BatchCalls::batch_dispatched(&mut self.#batched, origin, mode, &results);
+					}
+				)*
+				Ok(refund)
+			}
+
+			// Start journaling the mutations of every pallet, including system, so that they can be
+			// rolled back on their own, like the calls of an atomic batch. The events and the provider
+			// changes so far are collected first, so those made after the checkpoint can be dropped.
+			fn checkpoint_journals(&mut self) -> RuntimeCheckpoint {
+				self.collect_provider_changes();
+				self.collect_events();
+				RuntimeCheckpoint {
+					system: crate::support::Journaled::checkpoint_journal(&mut self.system),
+					#(
+						#pallet_names: crate::support::Journaled::checkpoint_journal(&mut self.#pallet_names),
+					)*
+					events: self.system.next_event_index(),
+				}
+			}
+
+			// Keep the mutations every pallet journaled since `checkpoint`.
+			fn commit_journals(&mut self, checkpoint: RuntimeCheckpoint) {
+				crate::support::Journaled::commit_journal(&mut self.system, checkpoint.system);
+				#(
+					crate::support::Journaled::commit_journal(&mut self.#pallet_names, checkpoint.#pallet_names);
+				)*
+			}
+
+			// Undo the mutations every pallet journaled since `checkpoint`, and drop the events and
+			// the provider changes made since.
+			fn rollback_journals(&mut self, checkpoint: RuntimeCheckpoint) {
+				crate::support::Journaled::rollback_journal(&mut self.system, checkpoint.system);
+				#(
+					crate::support::Journaled::rollback_journal(&mut self.#pallet_names, checkpoint.#pallet_names);
+					let _ = self.#pallet_names.take_events();
+				)*
+				#(
+					let _ = crate::support::AccountProvider::take_provider_changes(&mut self.#providers);
+				)*
+				self.system.drop_events_since(checkpoint.events);
+			}
+
+			// Report the accounts created or removed by the pallets providing for accounts to the
+			// system pallet, which keeps track of which accounts exist.
+			fn collect_provider_changes(&mut self) {
+				#(
+					for change in crate::support::AccountProvider::take_provider_changes(&mut self.#providers) {
+						match change {
+							crate::support::ProviderChange::Added(who) => self.system.inc_providers(&who),
+							crate::support::ProviderChange::Removed(who) => self.system.dec_providers(&who),
+						}
+					}
+				)*
+			}
+
+			// Move the events emitted by every pallet into the system pallet.
+			fn collect_events(&mut self) {
+				#(
+					for event in self.#pallet_names.take_events() {
+						self.system.deposit_event(RuntimeEvent::#pallet_names(event));
+					}
+				)*
+			}
+
+			// Take all the events emitted by the pallets since they were last taken, in the order
+			// they happened.
+			fn take_events(&mut self) -> Vec<RuntimeEvent> {
+				self.system.take_events()
+			}
+
+			// The events collected and not yet taken or pruned, across blocks.
+			fn events(&self) -> &[RuntimeEvent] {
+				self.system.events()
+			}
+
+			// The index the next event collected will get, which a consumer reading the events
+			// up to now passes to `events_since` later on.
+			fn next_event_index(&self) -> usize {
+				self.system.next_event_index()
+			}
+
+			// The events collected with an index of at least `index`, which is where a consumer
+			// which has read every event before it picks up.
+			fn events_since(&self, index: usize) -> &[RuntimeEvent] {
+				self.system.events_since(index)
+			}
+
+			// Drop the events collected with an index below `up_to`, once they have been read.
+			fn prune_events(&mut self, up_to: usize) {
+				self.system.prune_events(up_to)
+			}
+
+			// Execute a block as a preview, returning what `execute_block` would, and then restore the
+			// storage of every pallet to what it was before. The events the block emitted are
+			// dropped along with it, and failed extrinsics are only reported in the results, never
+			// to the logger.
+			fn dry_run_block(
+				&mut self,
+				block: &types::Block,
+			) -> Result<Vec<crate::support::DispatchResult>, crate::support::DispatchError> {
+				let snapshot = self.snapshot();
+				let results = self.apply_block(block.clone(), false).map(|report| report.results());
+				self.restore(snapshot);
+				results
+			}
+
+			// Migrate the storage of every pallet stored with a different version than the current
+			// version of its layout, once, and record that it is stored with the current version.
+			// Returns the weight every migration used up together.
+			fn runtime_upgrade(&mut self) -> crate::support::Weight {
+				let mut weight = crate::support::Weight(0);
+				#(
+					if self.system.storage_version(stringify!(#pallet_names)) != #storage_versions {
+						let used = crate::support::Hooks::on_runtime_upgrade(&mut self.#pallet_names);
+						weight = crate::support::Weight(weight.0.saturating_add(used.0));
+						self.system.set_storage_version(stringify!(#pallet_names), #storage_versions);
+					}
+				)*
+				// The accounts a migration created or reaped are reported to the system pallet.
+				self.collect_provider_changes();
+				weight
+			}
+
+			// Describe the calls exposed by every pallet, not including system, in the order the
+			// pallets are declared.
+			fn metadata() -> crate::support::RuntimeMetadata {
+				crate::support::RuntimeMetadata {
+					pallets: vec![
+						#(
+							crate::support::PalletMetadata {
+								name: stringify!(#pallet_names),
+								calls: #pallet_names::Call::<#runtime_struct>::metadata(),
+							},
+						)*
+					],
+				}
+			}
+
+			// Capture the storage of every pallet, so that it can be restored later.
+			fn snapshot(&self) -> RuntimeSnapshot {
+				RuntimeSnapshot(self.clone())
+			}
+
+			// Roll back the storage of every pallet to a previously captured snapshot.
+			fn restore(&mut self, snapshot: RuntimeSnapshot) {
+				*self = snapshot.0;
+			}
+		}
+
+		// The point of the journal of every pallet to roll back to, made by `checkpoint_journals`.
+		#[must_use]
+		struct RuntimeCheckpoint {
+			system: crate::support::JournalCheckpoint,
+			#(
+				#pallet_names: crate::support::JournalCheckpoint,
+			)*
+			events: usize,
 		}
+
+		// A copy of the storage of every pallet in the runtime, as captured by `snapshot`.
+		#[derive(Debug, Clone, PartialEq)]
+		pub struct RuntimeSnapshot(#runtime_struct);
 	};
 
-	// This quote block implements the `RuntimeCall` enum and implements the `Dispatch` trait.
+	// This quote block implements the `RuntimeCall` and `RuntimeEvent` enums and implements the
+	// `Dispatch` trait.
 	let dispatch_impl = quote! {
 		// These are all the calls which are exposed to the world.
 		// Note that it is just an accumulation of the calls exposed by each pallet.
 		//
 		// The parsed function names will be `snake_case`, and that will show up in the enum.
+		//
+		// With the `serde` feature, a call is represented by the name of its pallet in the `pallet`
+		// field, next to the pallet level call.
 		#[allow(non_camel_case_types)]
+		#[derive(Debug, Clone, PartialEq)]
+		#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+		#[cfg_attr(feature = "serde", serde(tag = "pallet"))]
 		pub enum RuntimeCall {
 			#( #pallet_names(#pallet_names::Call<#runtime_struct>) ),*
 		}
 
+		// These are all the events which are emitted by the runtime.
+		// Like `RuntimeCall`, it is just an accumulation of the events emitted by each pallet.
+		#[allow(non_camel_case_types)]
+		#[derive(Debug, Clone, PartialEq)]
+		pub enum RuntimeEvent {
+			#( #pallet_names(#pallet_names::Event<#runtime_struct>) ),*
+		}
+
+		impl RuntimeCall {
+			// The name of the pallet this call is routed to, like `balances`.
+			pub fn pallet_name(&self) -> &'static str {
+				match self {
+					#(
+						RuntimeCall::#pallet_names(_) => stringify!(#pallet_names),
+					)*
+				}
+			}
+
+			// The name of the call within its pallet, like `transfer`.
+			pub fn name(&self) -> &'static str {
+				match self {
+					#(
+						RuntimeCall::#pallet_names(call) => call.name(),
+					)*
+				}
+			}
+		}
+
+		// The weight of a call is the weight declared for the pallet level call.
+		impl crate::support::GetWeight for RuntimeCall {
+			fn weight(&self) -> crate::support::Weight {
+				match self {
+					#(
+						RuntimeCall::#pallet_names(call) => crate::support::GetWeight::weight(call),
+					)*
+				}
+			}
+		}
+
+		// A call is encoded as the index of its pallet, followed by the pallet level call.
+		impl crate::support::Encode for RuntimeCall {
+			fn encode_to(&self, dest: &mut Vec<u8>) {
+				match self {
+					#(
+						RuntimeCall::#pallet_names(call) => {
+							crate::support::Encode::encode_to(&#pallet_index, dest);
+							crate::support::Encode::encode_to(call, dest);
+						},
+					)*
+				}
+			}
+		}
+
 		impl crate::support::Dispatch for #runtime_struct {
-			type Caller = <Runtime as system::Config>::AccountId;
+			type Caller = crate::support::Origin<<Runtime as system::Config>::AccountId>;
 			type Call = RuntimeCall;
-			// Dispatch a call on behalf of a caller. Increments the caller's nonce.
+			// Dispatch a call on behalf of an origin.
 			//
 			// Dispatch allows us to identify which underlying pallet call we want to execute.
-			// Note that we extract the `caller` from the extrinsic, and use that information
-			// to determine who we are executing the call on behalf of.
+			// Note that the `caller` of an extrinsic is its signed origin, which determines who we
+			// are executing the call on behalf of.
 			fn dispatch(
 				&mut self,
-				caller: Self::Caller,
+				origin: Self::Caller,
 				runtime_call: Self::Call,
-			) -> crate::support::DispatchResult {
+			) -> crate::support::DispatchResultWithPostInfo {
 				// This match statement will allow us to correctly route `RuntimeCall`s
 				// to the appropriate pallet level call.
+				let info = match runtime_call {
+					#(
+						RuntimeCall::#pallet_names(call) => #pallet_dispatch?,
+					)*
+				};
+
+				// Dispatch the calls and the batches queued up by the call we just dispatched. Like the
+				// weight declared for the call, its actual weight does not include the queued calls. The
+				// weight declared for a batch does include its calls, so the part of it they did not use
+				// up is refunded.
+				self.dispatch_queued_calls()?;
+				let refund = self.dispatch_batches()?;
+				let actual_weight = crate::support::Weight(info.actual_weight.0.saturating_sub(refund.0));
+				Ok(crate::support::PostDispatchInfo { actual_weight })
+			}
+
+			// The weight of a call is the weight reported by the pallet handling that call.
+			fn weight(&self, runtime_call: &Self::Call) -> crate::support::Weight {
 				match runtime_call {
 					#(
-						RuntimeCall::#pallet_names(call) => {
-							self.#pallet_names.dispatch(caller, call)?;
-						}
-					),*
+						RuntimeCall::#pallet_names(call) => #pallet_weight,
+					)*
 				}
-				Ok(())
 			}
 		}
 	};
@@ -6,11 +6,13 @@ pub fn runtime(
 	_attr: proc_macro::TokenStream,
 	item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
+	let item_mod = syn::parse_macro_input!(item as syn::Item);
+
 	// The final expanded code will be placed here.
 	// Since our macro only adds new code, our final product will contain all of our old code too,
-	// hence we clone `item`.
-	let mut finished = item.clone();
-	let item_mod = syn::parse_macro_input!(item as syn::Item);
+	// except for the field attributes which are only meant for this macro.
+	let stripped = strip_field_attrs(item_mod.clone());
+	let mut finished: proc_macro::TokenStream = quote::quote!(#stripped).into();
 
 	// First we parse the `Runtime` struct...
 	let generated: proc_macro::TokenStream = match parse::RuntimeDef::try_from(item_mod.clone()) {
@@ -23,3 +25,16 @@ pub fn runtime(
 	finished.extend(generated);
 	return finished;
 }
+
+/// The attributes on the fields of the `Runtime` struct which are only meant for this macro.
+const FIELD_ATTRS: &[&str] = &["dispatch_with", "queued_calls", "batch_calls", "account_provider", "charge_fees"];
+
+/// Remove the attributes only meant for this macro from the fields of the `Runtime` struct.
+fn strip_field_attrs(mut item: syn::Item) -> syn::Item {
+	if let syn::Item::Struct(item_struct) = &mut item {
+		for field in item_struct.fields.iter_mut() {
+			field.attrs.retain(|attr| !FIELD_ATTRS.iter().any(|name| attr.path().is_ident(name)));
+		}
+	}
+	item
+}
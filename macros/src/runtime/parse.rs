@@ -8,6 +8,26 @@ pub struct RuntimeDef {
 	/// This is the list of pallets included in the `Runtime` struct. We omit `system` from this
 	/// list, but during parsing we check that system exists.
 	pub pallets: Vec<(syn::Ident, syn::Type)>,
+	/// This is the context each pallet in `pallets` is dispatched with, given by the
+	/// `#[dispatch_with(other_pallet)]` attribute on its field. It is `None` for pallets which are
+	/// dispatched without a context.
+	pub contexts: Vec<Option<syn::Ident>>,
+	/// This is the list of pallets which queue up calls for the runtime to dispatch, marked by the
+	/// `#[queued_calls]` attribute on their field.
+	pub queued: Vec<syn::Ident>,
+	/// This is the list of pallets which queue up batches of calls for the runtime to dispatch,
+	/// marked by the `#[batch_calls]` attribute on their field.
+	pub batched: Vec<syn::Ident>,
+	/// This is the list of pallets which provide for the existence of accounts, marked by the
+	/// `#[account_provider]` attribute on their field.
+	pub providers: Vec<syn::Ident>,
+	/// This is the pallet which charges the fee of every extrinsic, along with the pallet it charges
+	/// the fee from, given by the `#[charge_fees(other_pallet)]` attribute on its field. A runtime has
+	/// at most one such pallet, and extrinsics are free without it.
+	pub fees: Option<(syn::Ident, syn::Ident)>,
+	/// This is the list of fields included in the `Runtime` struct which are not pallets, like a
+	/// logger. Any field whose type is not named `Pallet` ends up here.
+	pub others: Vec<syn::Ident>,
 }
 
 impl RuntimeDef {
@@ -24,16 +44,61 @@ impl RuntimeDef {
 
 		let runtime_struct = item_struct.ident;
 
-		// Here is where we will store a list of all the pallets.
+		// Here is where we will store a list of all the pallets, and all the other fields.
 		let mut pallets = vec![];
+		let mut contexts = vec![];
+		let mut queued = vec![];
+		let mut batched = vec![];
+		let mut providers = vec![];
+		let mut fees = None;
+		let mut others = vec![];
 		// We skip `system`, which we ensure is the first field in `check_system`.
 		for field in item_struct.fields.into_iter().skip(1) {
 			if let Some(ident) = field.ident {
-				pallets.push((ident, field.ty))
+				if is_pallet(&field.ty) {
+					contexts.push(dispatch_with(&field.attrs)?);
+					if field.attrs.iter().any(|attr| attr.path().is_ident("queued_calls")) {
+						queued.push(ident.clone());
+					}
+					if field.attrs.iter().any(|attr| attr.path().is_ident("batch_calls")) {
+						batched.push(ident.clone());
+					}
+					if field.attrs.iter().any(|attr| attr.path().is_ident("account_provider")) {
+						providers.push(ident.clone());
+					}
+					if let Some(attr) = field.attrs.iter().find(|attr| attr.path().is_ident("charge_fees")) {
+						if fees.is_some() {
+							let msg = "only one pallet can be marked with `#[charge_fees]`";
+							return Err(syn::Error::new(attr.span(), msg))
+						}
+						fees = Some((ident.clone(), attr.parse_args()?));
+					}
+					pallets.push((ident, field.ty))
+				} else {
+					others.push(ident)
+				}
 			}
 		}
 
-		Ok(Self { runtime_struct, pallets })
+		Ok(Self { runtime_struct, pallets, contexts, queued, batched, providers, fees, others })
+	}
+}
+
+/// This function extracts the name of the pallet given by a `#[dispatch_with(other_pallet)]`
+/// attribute, if there is one.
+fn dispatch_with(attrs: &[syn::Attribute]) -> syn::Result<Option<syn::Ident>> {
+	match attrs.iter().find(|attr| attr.path().is_ident("dispatch_with")) {
+		Some(attr) => Ok(Some(attr.parse_args()?)),
+		None => Ok(None),
+	}
+}
+
+/// This function checks whether the type of a field is a pallet, i.e. whether it looks like
+/// `some_pallet::Pallet<Self>`.
+fn is_pallet(ty: &syn::Type) -> bool {
+	match ty {
+		syn::Type::Path(tp) => tp.path.segments.last().is_some_and(|segment| segment.ident == "Pallet"),
+		_ => false,
 	}
 }
 
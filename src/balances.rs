@@ -1,43 +1,805 @@
-use::num::traits::{CheckedAdd, CheckedSub, Zero} ;
-use std::collections::BTreeMap ;
+use::num::traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, Saturating, Zero} ;
+use std::collections::{BTreeMap, BTreeSet} ;
+use crate::support::{DispatchError, Journal, LockId, OrderedStorageMap, Origin, ProviderChange, StorageMap} ;
 
 /// The Config trait for the Balances module.
 /// It contains the types AccountId & Balance for handling balance of a user.
 pub trait Config: crate::system::Config {
     /// A type which can represent the balance of an account.
     /// Usually it is a large unsigned integer.
-    type Balance: Zero + CheckedAdd + CheckedSub + Copy ;
+    type Balance: Zero + One + CheckedAdd + CheckedSub + CheckedMul + CheckedDiv + Saturating + Copy + PartialOrd ;
+    /// The minimum balance an account must hold to be kept in storage.
+    /// Accounts falling below this amount are reaped, and their remaining dust is burned.
+    const EXISTENTIAL_DEPOSIT: Self::Balance ;
+    /// The flat fee paid by the sender of every transfer, on top of the transferred amount.
+    /// No fee is paid if this is zero.
+    const TRANSFER_FEE: Self::Balance ;
+    /// The smallest amount which can be transferred, to keep accounts from being spammed with tiny transfers.
+    /// Unlike the existential deposit, it applies to the amount moved rather than the resulting balances.
+    const MINIMUM_TRANSFER: Self::Balance ;
+    /// The account every transfer fee is paid into.
+    fn fee_account() -> Self::AccountId ;
+    /// The map the balances of every account are stored in, like a "BTreeMap".
+    /// The accounts can only be iterated over if this map is ordered.
+    type AccountStore: StorageMap<Self::AccountId, AccountData<Self::Balance>> + core::fmt::Debug + Clone + PartialEq ;
+}
+
+/// The smaller of two balances, which are only partially ordered.
+fn min<Balance: PartialOrd>(a: Balance, b: Balance) -> Balance {
+    if b < a { b } else { a }
+}
+
+/// The balance information stored for each account.
+#[derive(Debug, Clone, Copy, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccountData<Balance> {
+    /// The balance which can be freely used, for example to make transfers.
+    pub free: Balance,
+    /// The balance which has been set aside and cannot be used until it is unreserved.
+    pub reserved: Balance,
+}
+
+impl<Balance: Zero> Default for AccountData<Balance> {
+    fn default() -> Self {
+        Self {
+            free: Balance::zero(),
+            reserved: Balance::zero(),
+        }
+    }
+}
+
+impl<Balance: CheckedAdd + CheckedSub> AccountData<Balance> {
+    /// Increase the free balance by "amount".
+    /// This function fails with "BalanceOverflow" if the free balance would overflow.
+    fn increase_free(&mut self, amount: Balance) -> crate::support::DispatchResult {
+        self.free = self.free.checked_add(&amount).ok_or(DispatchError::BalanceOverflow) ?;
+        Ok(())
+    }
+
+    /// Decrease the free balance by "amount".
+    /// This function fails with "InsufficientFunds" if the free balance is less than "amount".
+    fn decrease_free(&mut self, amount: Balance) -> crate::support::DispatchResult {
+        self.free = self.free.checked_sub(&amount).ok_or(DispatchError::InsufficientFunds) ?;
+        Ok(())
+    }
+}
+
+/// The account data stored by a pallet configured with "T".
+type AccountDataOf<T> = AccountData<<T as Config>::Balance> ;
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<T: Config> {
+    /// Some "amount" was transferred from one account to another.
+    Transferred {
+        from: T::AccountId,
+        to: T::AccountId,
+        amount: T::Balance,
+    },
+    /// Some "amount" was paid by "who" into the fee account, as the fee of a transfer.
+    FeePaid {
+        who: T::AccountId,
+        amount: T::Balance,
+    },
+    /// Some "spender" was allowed to spend up to "amount" on behalf of some "owner".
+    Approved {
+        owner: T::AccountId,
+        spender: T::AccountId,
+        amount: T::Balance,
+    },
+}
+
+/// The operations undoing the mutations of the storage of this pallet, as recorded in its journal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Undo<T: Config> {
+    /// Set the balances of an account back to the given ones, or remove it if it had none.
+    Account(T::AccountId, Option<AccountDataOf<T>>),
+    /// Set the total issuance back to the given amount.
+    TotalIssuance(T::Balance),
+    /// Set the allowance of a spender on behalf of an owner back to the given amount, or remove it if
+    /// there was none.
+    Allowance(T::AccountId, T::AccountId, Option<T::Balance>),
+    /// Set the locks of an account back to the given ones, or remove them if there were none.
+    Locks(T::AccountId, Option<Vec<(LockId, T::Balance)>>),
+    /// Freeze an account again if it was frozen, or thaw it if it was not.
+    Frozen(T::AccountId, bool),
 }
 
 /// This is the Balances module.
 /// It is a simple module that keeps track of how much balance a user has in our state machine.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "T::AccountId: serde::Serialize + serde::de::DeserializeOwned, T::Balance: serde::Serialize + serde::de::DeserializeOwned, T::AccountStore: serde::Serialize + serde::de::DeserializeOwned"))]
+#[cfg_attr(feature = "serde", serde(from = "StoredPallet<T>"))]
 pub struct Pallet<T: Config> {
-    balances: BTreeMap<T::AccountId, T::Balance>,
+    /// A map from an account to its balances, stored in the backend chosen by the runtime.
+    balances: T::AccountStore,
+    /// The total amount of balance issued across all accounts.
+    total_issuance: T::Balance,
+    /// A map from an owner to the spenders it approved, each with the amount it may still transfer
+    /// on behalf of the owner.
+    /// The spenders are keyed by owner first, so an allowance can be looked up by borrowed ids.
+    allowances: BTreeMap<T::AccountId, BTreeMap<T::AccountId, T::Balance>>,
+    /// A map from an account to the locks on its free balance, each with its identifier and the amount
+    /// it keeps from being transferred.
+    locks: BTreeMap<T::AccountId, Vec<(LockId, T::Balance)>>,
+    /// The accounts which are frozen, and can neither send nor receive transfers.
+    frozen: BTreeSet<T::AccountId>,
+    /// A map from an account to its free balance, as stored in "balances" before version 1 of this
+    /// storage, which added reserved balances. The migration to version 1 moves them into "balances",
+    /// so this is only ever filled by loading the state of an older runtime, and never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    legacy_balances: BTreeMap<T::AccountId, T::Balance>,
+    /// The accounts created or reaped, waiting to be taken by the runtime, which reports them to the
+    /// system pallet.
+    /// They are taken right after every call, so they are never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    provider_changes: Vec<ProviderChange<T::AccountId>>,
+    /// The events emitted since they were last taken.
+    /// Events are not part of the state, so they are never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    events: Vec<Event<T>>,
+    /// The journal of the mutations made while executing the last block.
+    /// Like events, the journal is not part of the state.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    journal: Journal<Undo<T>>,
+}
+
+/// The balances of an account as stored by any version of this storage. Before version 1, only the
+/// free balance of every account was stored.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum StoredAccount<Balance> {
+    Data(AccountData<Balance>),
+    Free(Balance),
+}
+
+/// The balances pallet as stored by any version of its storage, which is deserialized first so the
+/// balances stored before version 1 can be told apart from account data.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(bound = "T::AccountId: serde::de::DeserializeOwned, T::Balance: serde::de::DeserializeOwned")]
+struct StoredPallet<T: Config> {
+    balances: BTreeMap<T::AccountId, StoredAccount<T::Balance>>,
+    total_issuance: T::Balance,
+    allowances: BTreeMap<T::AccountId, BTreeMap<T::AccountId, T::Balance>>,
+    locks: BTreeMap<T::AccountId, Vec<(LockId, T::Balance)>>,
+    frozen: BTreeSet<T::AccountId>,
+}
+
+#[cfg(feature = "serde")]
+impl<T: Config> From<StoredPallet<T>> for Pallet<T> {
+    fn from(stored: StoredPallet<T>) -> Self {
+        let mut pallet = Self::new() ;
+        for (who, account) in stored.balances {
+            match account {
+                StoredAccount::Data(account) => {
+                    pallet.balances.insert(who, account) ;
+                }
+                StoredAccount::Free(free) => {
+                    pallet.legacy_balances.insert(who, free) ;
+                }
+            }
+        }
+        pallet.total_issuance = stored.total_issuance ;
+        pallet.allowances = stored.allowances ;
+        pallet.locks = stored.locks ;
+        pallet.frozen = stored.frozen ;
+        pallet
+    }
 }
 
 impl<T: Config> Pallet<T> {
     /// Create a new instance of our balances module.
     pub fn new() -> Self {
         Self {
-            balances: BTreeMap::new(),
+            balances: T::AccountStore::default(),
+            total_issuance: T::Balance::zero(),
+            allowances: BTreeMap::new(),
+            locks: BTreeMap::new(),
+            frozen: BTreeSet::new(),
+            legacy_balances: BTreeMap::new(),
+            provider_changes: Vec::new(),
+            events: Vec::new(),
+            journal: Journal::new(),
         }
     }
 
-    /// Set the balance of an account "who" to some "amount".
-    pub fn set_balance(&mut self, who: &T::AccountId, amount: T::Balance) {
-        self.balances.insert(who.clone(), amount) ;
+    /// Set the free balance of an account "who" to some "amount".
+    /// The total issuance goes up or down by as much as the balance does, and this function fails if
+    /// the total issuance would overflow or underflow.
+    pub fn set_balance(&mut self, who: &T::AccountId, amount: T::Balance) -> crate::support::DispatchResult {
+        let mut account = self.account(who) ;
+        let new_total_issuance = match amount.checked_sub(&account.free) {
+            Some(increase) => self.total_issuance.checked_add(&increase).ok_or(DispatchError::TotalIssuanceOverflow) ?,
+            None => {
+                let decrease = account.free.saturating_sub(amount) ;
+                self.total_issuance.checked_sub(&decrease).ok_or(DispatchError::TotalIssuanceUnderflow) ?
+            },
+        } ;
+        account.free = amount ;
+
+        self.set_account(who, account) ;
+        self.set_total_issuance(new_total_issuance) ;
+
+        Ok(())
     }
 
-    /// Get the balance of an account "who".
+    /// Get the free balance of an account "who".
     /// If the account has no stored balance, we return zero.
     pub fn balance(&self, who: &T::AccountId) -> T::Balance {
-        *self.balances.get(who).unwrap_or(&T::Balance::zero()) 
+        self.account(who).free
+    }
+
+    /// Get the reserved balance of an account "who".
+    /// If the account has no stored balance, we return zero.
+    pub fn reserved_balance(&self, who: &T::AccountId) -> T::Balance {
+        self.account(who).reserved
+    }
+
+    /// Get the number of accounts holding some balance.
+    pub fn account_count(&self) -> usize {
+        self.balances.len()
+    }
+
+    /// Get the amount "spender" may still transfer on behalf of "owner".
+    /// If the owner never approved the spender, we return zero.
+    pub fn allowance(&self, owner: &T::AccountId, spender: &T::AccountId) -> T::Balance {
+        self.allowances.get(owner).and_then(|spenders| spenders.get(spender)).copied().unwrap_or(T::Balance::zero())
+    }
+
+    /// Get the total issuance, i.e. the sum of the balances of all accounts.
+    pub fn total_issuance(&self) -> T::Balance {
+        self.total_issuance
+    }
+
+    /// Mint some "amount" of new balance into the account "who".
+    /// This increases the total issuance, and fails if the total issuance would overflow.
+    pub fn mint(&mut self, who: &T::AccountId, amount: T::Balance) -> crate::support::DispatchResult {
+        let new_total_issuance = self.total_issuance.checked_add(&amount).ok_or(DispatchError::TotalIssuanceOverflow) ?;
+        self.increase_balance(who, amount) ?;
+        self.set_total_issuance(new_total_issuance) ;
+
+        Ok(())
+    }
+
+    /// Mint up to some "amount" of new balance into the account "who".
+    /// Unlike "mint", this never fails: if the balance of "who" or the total issuance would overflow,
+    /// only as much is minted as fits, and the amount actually minted is returned.
+    pub fn deposit_saturating(&mut self, who: &T::AccountId, amount: T::Balance) -> T::Balance {
+        let mut account = self.account(who) ;
+        let deposited = min(
+            account.free.saturating_add(amount).saturating_sub(account.free),
+            self.total_issuance.saturating_add(amount).saturating_sub(self.total_issuance),
+        ) ;
+        if deposited.is_zero() {
+            return deposited ;
+        }
+        account.free = account.free.saturating_add(deposited) ;
+
+        self.set_account(who, account) ;
+        self.set_total_issuance(self.total_issuance.saturating_add(deposited)) ;
+
+        deposited
+    }
+
+    /// Burn some "amount" of free balance from the account "who".
+    /// This decreases the total issuance. If the account is left with no balance, it is removed
+    /// from storage entirely.
+    pub fn burn(&mut self, who: &T::AccountId, amount: T::Balance) -> crate::support::DispatchResult {
+        // Insufficient funds are reported first. Balances add up to the total issuance, so that is
+        // the only way the total issuance can underflow.
+        let new_total_issuance = self.total_issuance.checked_sub(&amount) ;
+        self.decrease_balance(who, amount) ?;
+        self.set_total_issuance(new_total_issuance.ok_or(DispatchError::TotalIssuanceUnderflow) ?) ;
+
+        Ok(())
+    }
+
+    /// Slash up to some "amount" from the free balance of the account "who".
+    /// Unlike "burn", this never fails: if the account holds less than "amount", all of its free
+    /// balance is slashed. The reserved balance is left alone, since it is set aside by other
+    /// pallets, like the stake bonded with the staking pallet. The slashed balance is removed from
+    /// the total issuance, and the amount actually slashed is returned.
+    pub fn slash(&mut self, who: &T::AccountId, amount: T::Balance) -> T::Balance {
+        let mut account = self.account(who) ;
+        let slashed = min(amount, account.free) ;
+        account.free = account.free.saturating_sub(slashed) ;
+        self.set_account(who, account) ;
+        self.set_total_issuance(self.total_issuance.saturating_sub(slashed)) ;
+
+        slashed
+    }
+
+    /// Slash up to some "amount" from the reserved balance of the account "who", like "slash" does
+    /// from its free balance.
+    /// The pallet which reserved the balance is not told, so it is up to the caller to keep its
+    /// records in line, like the bonded stake of the staking pallet.
+    pub fn slash_reserved(&mut self, who: &T::AccountId, amount: T::Balance) -> T::Balance {
+        let mut account = self.account(who) ;
+        let slashed = min(amount, account.reserved) ;
+        account.reserved = account.reserved.saturating_sub(slashed) ;
+        self.set_account(who, account) ;
+        self.set_total_issuance(self.total_issuance.saturating_sub(slashed)) ;
+
+        slashed
+    }
+
+    /// Lock some "amount" of the free balance of "who" under "id", so it cannot be transferred.
+    /// Any amount locked under "id" before is replaced, so locking zero removes the lock. The locks
+    /// under other ids are left alone.
+    pub fn set_lock(&mut self, id: LockId, who: &T::AccountId, amount: T::Balance) {
+        let mut locks = self.locks.get(who).cloned().unwrap_or_default() ;
+        locks.retain(|(lock_id, _)| *lock_id != id) ;
+        if !amount.is_zero() {
+            locks.push((id, amount)) ;
+        }
+        self.set_locks(who, locks) ;
+    }
+
+    /// Remove the lock under "id" on the free balance of "who", if there is one.
+    pub fn remove_lock(&mut self, id: LockId, who: &T::AccountId) {
+        self.set_lock(id, who, T::Balance::zero()) ;
+    }
+
+    /// Get the locked balance of an account "who".
+    /// The locks overlap, so this is the largest amount locked under any id, or zero without a lock.
+    pub fn locked_balance(&self, who: &T::AccountId) -> T::Balance {
+        self.locks
+            .get(who)
+            .into_iter()
+            .flatten()
+            .fold(T::Balance::zero(), |locked, (_, amount)| if locked < *amount { *amount } else { locked })
+    }
+
+    /// Get the most "who" can send while keeping its account alive, like with "transfer_keep_alive".
+    /// The locked balance and the transfer fee must stay behind, and so must the existential deposit,
+    /// unless some reserved balance keeps the account alive. Frozen accounts cannot send anything.
+    pub fn transferable(&self, who: &T::AccountId) -> T::Balance {
+        if self.is_frozen(who) {
+            return T::Balance::zero() ;
+        }
+        let account = self.account(who) ;
+        let locked = self.locked_balance(who) ;
+        // Without an existential deposit, an account is still reaped once its free balance is zero.
+        let floor = if account.reserved.is_zero() {
+            let existential_deposit = if T::EXISTENTIAL_DEPOSIT.is_zero() { T::Balance::one() } else { T::EXISTENTIAL_DEPOSIT } ;
+            if locked < existential_deposit { existential_deposit } else { locked }
+        } else {
+            locked
+        } ;
+        account.free.saturating_sub(floor).saturating_sub(self.transfer_fee(who))
+    }
+
+    /// Freeze the account "who", so it can neither send nor receive transfers until it is thawed.
+    /// Its balances can still be queried, and freezing an account twice has no further effect.
+    pub fn freeze(&mut self, who: &T::AccountId) {
+        self.set_frozen(who, true) ;
+    }
+
+    /// Thaw the account "who", so it can send and receive transfers again.
+    pub fn thaw(&mut self, who: &T::AccountId) {
+        self.set_frozen(who, false) ;
+    }
+
+    /// Check whether the account "who" is frozen.
+    pub fn is_frozen(&self, who: &T::AccountId) -> bool {
+        self.frozen.contains(who)
+    }
+
+    /// Move some "amount" from the free balance of "who" to their reserved balance.
+    pub fn reserve(&mut self, who: &T::AccountId, amount: T::Balance) -> crate::support::DispatchResult {
+        let mut account = self.account(who) ;
+        account.free = account.free.checked_sub(&amount).ok_or(DispatchError::InsufficientFreeBalance) ?;
+        account.reserved = account.reserved.checked_add(&amount).ok_or(DispatchError::BalanceOverflow) ?;
+
+        self.set_account(who, account) ;
+        Ok(())
+    }
+
+    /// Move some "amount" from the reserved balance of "who" back to their free balance.
+    /// If more is requested than is reserved, only the reserved balance is moved.
+    pub fn unreserve(&mut self, who: &T::AccountId, amount: T::Balance) -> crate::support::DispatchResult {
+        let mut account = self.account(who) ;
+        let amount = if amount > account.reserved { account.reserved } else { amount } ;
+        account.reserved = account.reserved.checked_sub(&amount).ok_or(DispatchError::InsufficientReservedBalance) ?;
+        account.increase_free(amount) ?;
+
+        self.set_account(who, account) ;
+        Ok(())
+    }
+
+    /// Check whether transferring some "amount" from "from" to "to" would succeed, without
+    /// touching storage. This returns the same errors as the transfer itself.
+    pub fn can_transfer(
+        &self,
+        from: &T::AccountId,
+        to: &T::AccountId,
+        amount: T::Balance
+    ) -> crate::support::DispatchResult {
+        self.transferred_accounts(from, to, amount).map(|_| ())
+    }
+
+    /// Take all the events emitted since they were last taken, leaving no events behind.
+    pub fn take_events(&mut self) -> Vec<Event<T>> {
+        core::mem::take(&mut self.events)
+    }
+
+    /// Get the stored balances of the account "who", or an empty account if nothing is stored.
+    fn account(&self, who: &T::AccountId) -> AccountData<T::Balance> {
+        self.balances.get(who).copied().unwrap_or_default()
+    }
+
+    /// Increase the free balance of "who" by "amount", without touching the total issuance.
+    /// Every balance an account receives goes through here, so this function fails with
+    /// "BalanceOverflow" whichever way the balance would overflow.
+    fn increase_balance(&mut self, who: &T::AccountId, amount: T::Balance) -> crate::support::DispatchResult {
+        let mut account = self.account(who) ;
+        account.increase_free(amount) ?;
+        self.set_account(who, account) ;
+        Ok(())
+    }
+
+    /// Decrease the free balance of "who" by "amount", without touching the total issuance.
+    /// This function fails with "InsufficientFunds" if "who" holds less than "amount".
+    fn decrease_balance(&mut self, who: &T::AccountId, amount: T::Balance) -> crate::support::DispatchResult {
+        let mut account = self.account(who) ;
+        account.decrease_free(amount) ?;
+        self.set_account(who, account) ;
+        Ok(())
+    }
+
+    /// Reverse a mutation of the storage, as recorded by "undo" in the journal.
+    fn undo(&mut self, undo: Undo<T>) {
+        match undo {
+            Undo::Account(who, account) => crate::support::restore_entry(&mut self.balances, who, account),
+            Undo::TotalIssuance(total_issuance) => self.total_issuance = total_issuance,
+            Undo::Allowance(owner, spender, amount) => self.put_allowance(&owner, &spender, amount.unwrap_or(T::Balance::zero())),
+            Undo::Locks(who, locks) => crate::support::restore_entry(&mut self.locks, who, locks),
+            Undo::Frozen(who, true) => { self.frozen.insert(who) ; },
+            Undo::Frozen(who, false) => { self.frozen.remove(&who) ; },
+        }
+    }
+
+    /// Store the balances of the account "who".
+    /// An account with no balance at all is removed from storage rather than stored as zero.
+    /// Creating or removing an account adds or removes the provider this pallet gives it.
+    /// The account id is only cloned when a new account is created or removed.
+    fn set_account(&mut self, who: &T::AccountId, account: AccountData<T::Balance>) {
+        self.journal.record(|| Undo::Account(who.clone(), self.balances.get(who).copied())) ;
+        if account.free.is_zero() && account.reserved.is_zero() {
+            if self.balances.remove(who).is_some() {
+                self.provider_changes.push(ProviderChange::Removed(who.clone())) ;
+            }
+        } else if let Some(stored) = self.balances.get_mut(who) {
+            *stored = account ;
+        } else {
+            self.balances.insert(who.clone(), account) ;
+            self.provider_changes.push(ProviderChange::Added(who.clone())) ;
+        }
+    }
+
+    /// Freeze the account "who" if "frozen" is set, or thaw it otherwise.
+    fn set_frozen(&mut self, who: &T::AccountId, frozen: bool) {
+        if self.is_frozen(who) == frozen {
+            return ;
+        }
+        self.journal.record(|| Undo::Frozen(who.clone(), !frozen)) ;
+        if frozen {
+            self.frozen.insert(who.clone()) ;
+        } else {
+            self.frozen.remove(who) ;
+        }
+    }
+
+    /// Replace the locks of "who" with "locks", removing them from storage if there are none.
+    fn set_locks(&mut self, who: &T::AccountId, locks: Vec<(LockId, T::Balance)>) {
+        self.journal.record(|| Undo::Locks(who.clone(), self.locks.get(who).cloned())) ;
+        crate::support::restore_entry(&mut self.locks, who.clone(), Some(locks).filter(|locks| !locks.is_empty())) ;
+    }
+
+    /// Set the total issuance to "total_issuance".
+    fn set_total_issuance(&mut self, total_issuance: T::Balance) {
+        self.journal.record(|| Undo::TotalIssuance(self.total_issuance)) ;
+        self.total_issuance = total_issuance ;
+    }
+
+    /// Set the allowance of "spender" on behalf of "owner" to "amount", removing it if "amount" is
+    /// zero.
+    fn set_allowance(&mut self, owner: &T::AccountId, spender: &T::AccountId, amount: T::Balance) {
+        self.journal.record(|| {
+            let old = self.allowances.get(owner).and_then(|spenders| spenders.get(spender)).copied() ;
+            Undo::Allowance(owner.clone(), spender.clone(), old)
+        }) ;
+        self.put_allowance(owner, spender, amount) ;
+    }
+
+    /// Store the allowance of "spender" on behalf of "owner" without journaling it, removing it if
+    /// "amount" is zero, along with the owner once it has no spenders left.
+    /// The account ids are only cloned when a new allowance is created.
+    fn put_allowance(&mut self, owner: &T::AccountId, spender: &T::AccountId, amount: T::Balance) {
+        match self.allowances.get_mut(owner) {
+            Some(spenders) if amount.is_zero() => {
+                spenders.remove(spender) ;
+                if spenders.is_empty() {
+                    self.allowances.remove(owner) ;
+                }
+            },
+            Some(spenders) => match spenders.get_mut(spender) {
+                Some(allowance) => *allowance = amount,
+                None => { spenders.insert(spender.clone(), amount) ; },
+            },
+            None if amount.is_zero() => {},
+            None => { self.allowances.insert(owner.clone(), BTreeMap::from([(spender.clone(), amount)])) ; },
+        }
+    }
+
+    /// The fee "from" pays for every transfer.
+    /// The fee account itself pays no fee, since the fee would be paid back to it.
+    fn transfer_fee(&self, from: &T::AccountId) -> T::Balance {
+        if T::TRANSFER_FEE.is_zero() || *from == T::fee_account() {
+            return T::Balance::zero() ;
+        }
+        T::TRANSFER_FEE
+    }
+
+    /// Check the request to transfer some "amount" from "from" to "to" before looking at any balance:
+    /// it fails if "amount" is zero or below the minimum transfer, if "from" and "to" are the same
+    /// account, or if either of them is frozen.
+    fn ensure_transfer_request(&self, from: &T::AccountId, to: &T::AccountId, amount: T::Balance) -> crate::support::DispatchResult {
+        // Transfers which would not move any balance are rejected.
+        if amount.is_zero() {
+            return Err(DispatchError::CannotTransferZero) ;
+        }
+        if amount < T::MINIMUM_TRANSFER {
+            return Err(DispatchError::BelowMinimumTransfer) ;
+        }
+        if from == to {
+            return Err(DispatchError::CannotTransferToSelf) ;
+        }
+        // Frozen accounts can neither send nor receive any balance.
+        if self.is_frozen(from) || self.is_frozen(to) {
+            return Err(DispatchError::AccountFrozen) ;
+        }
+        Ok(())
+    }
+
+    /// Get how much of some "amount" "to" can receive from "from", as limited by the overflow of its
+    /// own free balance and of the free balance of the fee account, which takes the fee of "from".
+    /// If the fee account cannot take the fee, nothing can be received.
+    fn receivable(&self, from: &T::AccountId, to: &T::AccountId, amount: T::Balance) -> T::Balance {
+        let room = |who: &T::AccountId, amount: T::Balance| {
+            self.balance(who).saturating_add(amount).saturating_sub(self.balance(who))
+        } ;
+        let fee = self.transfer_fee(from) ;
+        if fee.is_zero() {
+            return room(to, amount) ;
+        }
+        let fee_account = T::fee_account() ;
+        if *to == fee_account {
+            // The fee account receives the fee on top of the amount.
+            room(to, amount.saturating_add(fee)).saturating_sub(fee)
+        } else if room(&fee_account, fee) < fee {
+            T::Balance::zero()
+        } else {
+            room(to, amount)
+        }
+    }
+
+    /// Calculate the balances of both "from" & "to" accounts after transferring some "amount"
+    /// between them, without touching storage, along with the fee "from" pays on top of "amount".
+    #[allow(clippy::type_complexity)]
+    fn transferred_accounts(
+        &self,
+        from: &T::AccountId,
+        to: &T::AccountId,
+        amount: T::Balance
+    ) -> Result<(AccountDataOf<T>, AccountDataOf<T>, T::Balance), DispatchError> {
+        self.ensure_transfer_request(from, to, amount) ?;
+
+        // Get balance of both user pre-transfer.
+        let mut from_account = self.account(from) ;
+        let mut to_account = self.account(to) ;
+
+        // Calculate new balances of both accounts while keeping check of underflow and overflow.
+        // The sender must be able to cover the fee along with the amount.
+        let fee = self.transfer_fee(from) ;
+        let debit = amount.checked_add(&fee).ok_or(DispatchError::Overflow) ?;
+        from_account.decrease_free(debit) ?;
+        to_account.increase_free(amount) ?;
+
+        // The locked balance must stay behind.
+        if from_account.free < self.locked_balance(from) {
+            return Err(DispatchError::LiquidityRestrictions) ;
+        }
+
+        // The fee account must be able to take the fee, which may be paid on top of the amount it
+        // receives.
+        if !fee.is_zero() {
+            let fee_account = T::fee_account() ;
+            let mut fees = if *to == fee_account { to_account } else { self.account(&fee_account) } ;
+            fees.increase_free(fee) ?;
+        }
+
+        Ok((from_account, to_account, fee))
+    }
+
+    /// Move some "amount" from the free balance of "from" to the free balance of "to", and the
+    /// transfer fee from "from" to the fee account.
+    /// If "keep_alive" is set, the transfer fails rather than reaping the "from" account.
+    /// The "Transferred" event is left to the caller, so it can move the account ids into it rather
+    /// than clone them.
+    fn do_transfer(
+        &mut self,
+        from: &T::AccountId,
+        to: &T::AccountId,
+        amount: T::Balance,
+        keep_alive: bool
+    ) -> crate::support::DispatchResult {
+        let (mut from_account, to_account, fee) = self.transferred_accounts(from, to, amount) ?;
+
+        // Accounts which still hold some reserved balance are never reaped.
+        let reap = from_account.reserved.is_zero()
+            && (from_account.free < T::EXISTENTIAL_DEPOSIT || from_account.free.is_zero()) ;
+        if reap && keep_alive {
+            return Err(DispatchError::WouldKillAccount) ;
+        }
+
+        // Update balances of both accounts post-transfer.
+        // If "from" is left with less than the existential deposit, their account is reaped
+        // and the remaining dust is burned from the total issuance. Otherwise, balance only moves
+        // between accounts, so the total issuance stays the same.
+        if reap {
+            self.set_total_issuance(self.total_issuance.checked_sub(&from_account.free).ok_or(DispatchError::TotalIssuanceUnderflow) ?) ;
+            from_account.free = T::Balance::zero() ;
+        }
+        self.set_account(from, from_account) ;
+        self.set_account(to, to_account) ;
+
+        // The fee account was checked to take the fee above, so this never fails.
+        if !fee.is_zero() {
+            self.increase_balance(&T::fee_account(), fee) ?;
+            self.events.push(Event::FeePaid { who: from.clone(), amount: fee }) ;
+        }
+        Ok(())
+    }
+}
+
+// The accounts can only be iterated over when they are stored in an ordered map, so the order is
+// always the same.
+impl<T: Config> Pallet<T> where T::AccountStore: OrderedStorageMap<T::AccountId, AccountData<T::Balance>> {
+    /// Iterate over every account holding some balance, along with its free balance, in the order of
+    /// the map they are stored in: by account, or by the hash of the account in a "HashMap".
+    pub fn accounts(&self) -> impl Iterator<Item = (&T::AccountId, &T::Balance)> {
+        self.balances.iter().map(|(who, account)| (who, &account.free))
+    }
+
+    /// Get the "n" accounts with the highest free balance, from highest to lowest.
+    /// Accounts with the same balance are ordered by account, so the result is deterministic.
+    pub fn top_balances(&self, n: usize) -> Vec<(T::AccountId, T::Balance)> {
+        // Sort references first, so only the accounts we return are cloned.
+        let mut accounts = self.accounts().collect::<Vec<_>>() ;
+        accounts.sort_by(|(a, a_balance), (b, b_balance)| {
+            b_balance.partial_cmp(a_balance).unwrap_or(core::cmp::Ordering::Equal).then_with(|| a.cmp(b))
+        }) ;
+        accounts.into_iter().take(n).map(|(who, amount)| (who.clone(), *amount)).collect()
+    }
+}
+
+// Every account holding some balance is provided for by this pallet.
+impl<T: Config> crate::support::AccountProvider<T::AccountId> for Pallet<T> {
+    fn take_provider_changes(&mut self) -> Vec<ProviderChange<T::AccountId>> {
+        core::mem::take(&mut self.provider_changes)
+    }
+}
+
+// The balances pallet is the currency of our runtime, so other pallets can move funds through it.
+impl<T: Config> crate::support::Currency<T::AccountId> for Pallet<T> {
+    type Balance = T::Balance ;
+
+    fn transfer(&mut self, from: &T::AccountId, to: &T::AccountId, amount: T::Balance) -> crate::support::DispatchResult {
+        self.do_transfer(from, to, amount, false) ?;
+        self.events.push(Event::Transferred { from: from.clone(), to: to.clone(), amount }) ;
+        Ok(())
+    }
+
+    fn deposit(&mut self, who: &T::AccountId, amount: T::Balance) -> crate::support::DispatchResult {
+        self.mint(who, amount)
+    }
+
+    fn withdraw(&mut self, who: &T::AccountId, amount: T::Balance) -> crate::support::DispatchResult {
+        self.burn(who, amount)
+    }
+
+    fn total_balance(&self, who: &T::AccountId) -> T::Balance {
+        let account = self.account(who) ;
+        account.free.checked_add(&account.reserved).unwrap_or(account.free)
+    }
+}
+
+impl<T: Config> crate::support::LockableCurrency<T::AccountId> for Pallet<T> {
+    fn set_lock(&mut self, id: LockId, who: &T::AccountId, amount: T::Balance) {
+        Pallet::set_lock(self, id, who, amount)
+    }
+
+    fn remove_lock(&mut self, id: LockId, who: &T::AccountId) {
+        Pallet::remove_lock(self, id, who)
+    }
+
+    fn locked_balance(&self, who: &T::AccountId) -> T::Balance {
+        Pallet::locked_balance(self, who)
+    }
+}
+
+impl<T: Config> crate::support::ReservableCurrency<T::AccountId> for Pallet<T> {
+    fn reserve(&mut self, who: &T::AccountId, amount: T::Balance) -> crate::support::DispatchResult {
+        Pallet::reserve(self, who, amount)
+    }
+
+    fn unreserve(&mut self, who: &T::AccountId, amount: T::Balance) -> crate::support::DispatchResult {
+        Pallet::unreserve(self, who, amount)
+    }
+
+    fn reserved_balance(&self, who: &T::AccountId) -> T::Balance {
+        Pallet::reserved_balance(self, who)
+    }
+}
+
+// The mutations of this pallet are journaled while a block is executed, so the runtime can undo them.
+impl<T: Config> crate::support::Journaled for Pallet<T> {
+    fn start_journal(&mut self) {
+        self.journal.start() ;
+    }
+
+    fn stop_journal(&mut self) {
+        self.journal.stop() ;
+    }
+
+    fn undo_journal(&mut self) {
+        for undo in self.journal.take() {
+            self.undo(undo) ;
+        }
+    }
+
+    fn checkpoint_journal(&mut self) -> crate::support::JournalCheckpoint {
+        self.journal.checkpoint()
+    }
+
+    fn commit_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        self.journal.commit(checkpoint) ;
+    }
+
+    fn rollback_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        for undo in self.journal.rollback(checkpoint) {
+            self.undo(undo) ;
+        }
+    }
+}
+
+// The balances pallet does not need to execute any logic as part of a block.
+// Version 1 of its storage keeps the "AccountData" of every account, rather than only its free balance.
+impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {
+    const STORAGE_VERSION: u32 = 1 ;
+
+    fn on_runtime_upgrade(&mut self) -> crate::support::Weight {
+        // The free balances were already counted in the total issuance, so only their layout changes.
+        // Accounts were not provided for before version 1 either, so every migrated account is created.
+        let legacy = core::mem::take(&mut self.legacy_balances) ;
+        let migrated = legacy.len() as u64 ;
+        for (who, free) in legacy {
+            self.set_account(&who, AccountData { free, reserved: T::Balance::zero() }) ;
+        }
+        crate::support::Weight(migrated)
     }
 }
 
-// Only this function will be called by the user from this pallet, so we will separate this from the other 
+// Only these functions will be called by the user from this pallet, so we will separate these from the other 
 // pallet functions and only add rust macro to this implementation of our Pallet.
+// Every call borrows the account ids it takes, which are only cloned into storage and events.
 #[macros::call]
 impl<T: Config> Pallet<T> {
     /// Transfer some "amount" from one account to another.
@@ -45,24 +807,141 @@ impl<T: Config> Pallet<T> {
     /// mathematical overflow occurs.
     pub fn transfer(
         &mut self, 
-        caller: T::AccountId,
-        to: T::AccountId,
+        caller: &T::AccountId,
+        to: &T::AccountId,
         amount: T::Balance
     ) -> crate::support::DispatchResult {
+        self.do_transfer(caller, to, amount, false) ?;
+        self.events.push(Event::Transferred { from: caller.clone(), to: to.clone(), amount }) ;
+        Ok(())
+    }
 
-        // Get balance of both user pre-transfer.
-        let caller_balance = self.balance(&caller) ;
-        let to_balance = self.balance(&to) ;
+    /// Transfer some "amount" from one account to another, making sure the caller's account is
+    /// not reaped.
+    /// This function fails if the caller would be left with less than the existential deposit.
+    pub fn transfer_keep_alive(
+        &mut self,
+        caller: &T::AccountId,
+        to: &T::AccountId,
+        amount: T::Balance
+    ) -> crate::support::DispatchResult {
+        self.do_transfer(caller, to, amount, true) ?;
+        self.events.push(Event::Transferred { from: caller.clone(), to: to.clone(), amount }) ;
+        Ok(())
+    }
 
-        // Calculate new balances of both "caller" & "to" accounts while keeping check of underflow and overflow.
-        let new_caller_balance = caller_balance.checked_sub(&amount).ok_or("Insufficient funds.") ?;
-        let new_to_balance = to_balance.checked_add(&amount).ok_or("Overflow.") ?;
+    /// Make multiple transfers from the caller at once.
+    /// Either every transfer succeeds, or none of them are applied.
+    pub fn batch_transfer(
+        &mut self,
+        caller: &T::AccountId,
+        transfers: Vec<(T::AccountId, T::Balance)>
+    ) -> crate::support::DispatchResult {
+        // Journal the mutations of the batch, so we can roll them back if any of the transfers fail.
+        let checkpoint = self.journal.checkpoint() ;
+        let provider_changes = self.provider_changes.len() ;
+        let events = self.events.len() ;
 
-        // Update balances of both accounts post-transfer.
-        self.balances.insert(caller, new_caller_balance) ;
-        self.balances.insert(to, new_to_balance) ;
+        for (to, amount) in transfers {
+            if self.do_transfer(caller, &to, amount, false).is_err() {
+                for undo in self.journal.rollback(checkpoint) {
+                    self.undo(undo) ;
+                }
+                self.provider_changes.truncate(provider_changes) ;
+                self.events.truncate(events) ;
+                return Err(DispatchError::BatchTransferFailed) ;
+            }
+            self.events.push(Event::Transferred { from: caller.clone(), to, amount }) ;
+        }
+        self.journal.commit(checkpoint) ;
+        Ok(())
+    }
+
+    /// Transfer some "amount" between two arbitrary accounts.
+    /// This is a privileged call, which can only be made by the root origin.
+    pub fn force_transfer(
+        &mut self,
+        origin: Origin<T::AccountId>,
+        from: &T::AccountId,
+        to: &T::AccountId,
+        amount: T::Balance
+    ) -> crate::support::DispatchResult {
+        origin.ensure_root() ?;
+        self.do_transfer(from, to, amount, false) ?;
+        self.events.push(Event::Transferred { from: from.clone(), to: to.clone(), amount }) ;
+        Ok(())
+    }
+
+    /// Allow the account "spender" to transfer up to "amount" on behalf of the caller.
+    /// Any allowance approved before is replaced, so approving zero revokes the allowance.
+    pub fn approve(
+        &mut self,
+        caller: &T::AccountId,
+        spender: &T::AccountId,
+        amount: T::Balance
+    ) -> crate::support::DispatchResult {
+        self.set_allowance(caller, spender, amount) ;
+
+        self.events.push(Event::Approved { owner: caller.clone(), spender: spender.clone(), amount }) ;
+        Ok(())
+    }
+
+    /// Transfer some "amount" from the account "owner" to the account "to", on behalf of "owner".
+    /// The caller must have been approved to spend at least "amount" by "owner", and their allowance
+    /// is reduced by "amount".
+    pub fn transfer_from(
+        &mut self,
+        caller: &T::AccountId,
+        owner: &T::AccountId,
+        to: &T::AccountId,
+        amount: T::Balance
+    ) -> crate::support::DispatchResult {
+        let allowance = self.allowance(owner, caller).checked_sub(&amount).ok_or(DispatchError::AllowanceExceeded) ?;
+        self.do_transfer(owner, to, amount, false) ?;
+        self.events.push(Event::Transferred { from: owner.clone(), to: to.clone(), amount }) ;
+
+        self.set_allowance(owner, caller, allowance) ;
+        Ok(())
+    }
+
+    /// Transfer up to some "amount" from one account to another.
+    /// Unlike "transfer", this moves as much as possible rather than failing: if the caller holds
+    /// less than "amount" beyond what "transferable" keeps behind, or the balance of "to" or of the
+    /// fee account would overflow, only as much is moved as can be, so the caller is never reaped.
+    /// The amount actually moved is reported in the event, and by "transfer_at_most".
+    pub fn transfer_saturating(
+        &mut self,
+        caller: &T::AccountId,
+        to: &T::AccountId,
+        amount: T::Balance
+    ) -> crate::support::DispatchResult {
+        self.transfer_at_most(caller, to, amount).map(|_| ())
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    /// Transfer up to some "amount" from "from" to "to" like "transfer_saturating", returning the
+    /// amount actually moved.
+    /// The request itself is checked like a transfer: it fails if "amount" is zero or below the
+    /// minimum transfer, if "from" and "to" are the same account, or if either of them is frozen.
+    /// If the amount which can be moved is zero or below the minimum transfer, nothing is moved, no
+    /// event is emitted, and zero is returned.
+    pub fn transfer_at_most(
+        &mut self,
+        from: &T::AccountId,
+        to: &T::AccountId,
+        amount: T::Balance
+    ) -> Result<T::Balance, DispatchError> {
+        self.ensure_transfer_request(from, to, amount) ?;
 
-        Ok(()) 
+        let amount = min(amount, min(self.transferable(from), self.receivable(from, to, amount))) ;
+        if amount.is_zero() || amount < T::MINIMUM_TRANSFER {
+            return Ok(T::Balance::zero()) ;
+        }
+
+        self.do_transfer(from, to, amount, true) ?;
+        self.events.push(Event::Transferred { from: from.clone(), to: to.clone(), amount }) ;
+        Ok(amount)
     }
 }
 
@@ -101,29 +980,171 @@ impl<T: Config> Pallet<T> {
 
 #[cfg(test)]
 mod tests {
+    use crate::support::{DispatchError, LockId, Origin} ;
+
+    // The identifiers of the locks put on balances in these tests.
+    const STAKING_ID: LockId = *b"staking " ;
+    const VESTING_ID: LockId = *b"vesting " ;
+
+    #[derive(Debug, PartialEq)]
     struct TestConfig ;
     impl crate::system::Config for TestConfig {
         type AccountId = String ;
         type BlockNumber = u32 ;
         type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
     }
     impl crate::balances::Config for TestConfig {
         type Balance = u128 ;
+        const EXISTENTIAL_DEPOSIT: u128 = 10 ;
+        const TRANSFER_FEE: u128 = 0 ;
+        const MINIMUM_TRANSFER: u128 = 0 ;
+        fn fee_account() -> String {
+            "fees".to_string()
+        }
+        type AccountStore = std::collections::BTreeMap<String, crate::balances::AccountData<u128>> ;
     }
 
-    #[test]
-    fn init_balances() {
-        // Instantiating a balances struct.
-        let mut balances = super::Pallet::<TestConfig>::new();
+    // The same pallet, but with its accounts stored in a hash map.
+    #[derive(Debug, PartialEq)]
+    struct HashMapConfig ;
+    impl crate::system::Config for HashMapConfig {
+        type AccountId = String ;
+        type BlockNumber = u32 ;
+        type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
+    }
+    impl crate::balances::Config for HashMapConfig {
+        type Balance = u128 ;
+        const EXISTENTIAL_DEPOSIT: u128 = 10 ;
+        const TRANSFER_FEE: u128 = 0 ;
+        const MINIMUM_TRANSFER: u128 = 0 ;
+        fn fee_account() -> String {
+            "fees".to_string()
+        }
+        type AccountStore = std::collections::HashMap<String, crate::balances::AccountData<u128>> ;
+    }
 
-        // Assert that the balance of "alice" starts at zero. 
-        assert_eq!(balances.balance(&"alice".to_string()), 0) ;
-        // Set balance of "alice" to 100.
-        balances.set_balance(&"alice".to_string(), 100) ;
-        // Assert that "alice" has now balance of 100.
-        assert_eq!(balances.balance(&"alice".to_string()), 100) ;
-        // Assert balance of "bob" has not changed and is equal to zero.
-        assert_eq!(balances.balance(&"bob".to_string()), 0) ;
+    /// Run the basic balances calls against the storage backend chosen by "T", which must behave the
+    /// same whatever the backend is.
+    fn check_storage_backend<T>()
+    where
+        T: crate::balances::Config<Balance = u128> + crate::system::Config<AccountId = String>,
+    {
+        use crate::support::StorageMap ;
+
+        let mut balances = super::Pallet::<T>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+
+        assert_eq!(balances.mint(&alice, 100), Ok(())) ;
+        assert_eq!(balances.transfer(&alice, &bob, 40), Ok(())) ;
+        assert_eq!(balances.balance(&alice), 60) ;
+        assert_eq!(balances.balance(&bob), 40) ;
+        assert_eq!(balances.account_count(), 2) ;
+
+        // Reserved balance is stored in the same account.
+        assert_eq!(balances.reserve(&alice, 20), Ok(())) ;
+        assert_eq!(balances.balance(&alice), 40) ;
+        assert_eq!(balances.reserved_balance(&alice), 20) ;
+
+        // Leaving less than the existential deposit reaps the account.
+        assert_eq!(balances.transfer(&bob, &alice, 35), Ok(())) ;
+        assert!(!balances.balances.contains_key(&bob)) ;
+        assert_eq!(balances.balance(&alice), 75) ;
+        assert_eq!(balances.total_issuance(), 95) ;
+
+        // Burning the whole account removes it from storage.
+        assert_eq!(balances.unreserve(&alice, 20), Ok(())) ;
+        assert_eq!(balances.burn(&alice, 95), Ok(())) ;
+        assert!(balances.balances.is_empty()) ;
+        assert_eq!(balances.total_issuance(), 0) ;
+    }
+
+    #[test]
+    fn migrate_account_data() {
+        use crate::support::{Hooks, ProviderChange, Weight} ;
+
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+
+        // Before version 1, only the free balance of every account was stored.
+        balances.legacy_balances.insert(bob.clone(), 50) ;
+        assert_eq!(<super::Pallet<TestConfig> as Hooks<u32>>::STORAGE_VERSION, 1) ;
+        assert_eq!(balances.on_runtime_upgrade(), Weight(1)) ;
+        assert_eq!(balances.account(&bob), super::AccountData { free: 50, reserved: 0 }) ;
+        assert_eq!(balances.balance(&alice), 100) ;
+        assert!(balances.legacy_balances.is_empty()) ;
+        // The migrated account is created like any other account.
+        assert_eq!(
+            crate::support::AccountProvider::take_provider_changes(&mut balances),
+            vec![ProviderChange::Added(alice.clone()), ProviderChange::Added(bob.clone())]
+        ) ;
+
+        // There is nothing left to migrate afterwards.
+        assert_eq!(balances.on_runtime_upgrade(), Weight(0)) ;
+        assert_eq!(balances.account(&bob), super::AccountData { free: 50, reserved: 0 }) ;
+    }
+
+    #[test]
+    fn btree_map_storage() {
+        check_storage_backend::<TestConfig>() ;
+    }
+
+    #[test]
+    fn hash_map_storage() {
+        check_storage_backend::<HashMapConfig>() ;
+    }
+
+    #[test]
+    fn init_balances() {
+        // Instantiating a balances struct.
+        let mut balances = super::Pallet::<TestConfig>::new();
+
+        // Assert that the balance of "alice" starts at zero. 
+        assert_eq!(balances.balance(&"alice".to_string()), 0) ;
+        // Set balance of "alice" to 100.
+        assert_eq!(balances.set_balance(&"alice".to_string(), 100), Ok(())) ;
+        // Assert that "alice" has now balance of 100.
+        assert_eq!(balances.balance(&"alice".to_string()), 100) ;
+        // Assert balance of "bob" has not changed and is equal to zero.
+        assert_eq!(balances.balance(&"bob".to_string()), 0) ;
+    }
+
+    #[test]
+    fn set_balance_issuance() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+
+        // The total issuance follows every balance set, up and down.
+        assert_eq!(balances.set_balance(&alice, 150), Ok(())) ;
+        assert_eq!(balances.total_issuance(), 150) ;
+        assert_eq!(balances.set_balance(&alice, 40), Ok(())) ;
+        assert_eq!(balances.total_issuance(), 40) ;
+        assert_eq!(balances.set_balance(&bob, 10), Ok(())) ;
+        assert_eq!(balances.total_issuance(), 50) ;
+        assert_eq!(balances.set_balance(&bob, 0), Ok(())) ;
+        assert_eq!(balances.total_issuance(), 40) ;
+        assert_eq!(balances.account_count(), 1) ;
+
+        // A balance the total issuance cannot make room for is rejected, leaving everything as it was.
+        assert_eq!(balances.set_balance(&bob, u128::MAX), Err(DispatchError::TotalIssuanceOverflow)) ;
+        assert_eq!(balances.balance(&bob), 0) ;
+        assert_eq!(balances.total_issuance(), 40) ;
     }
 
     #[test]
@@ -133,16 +1154,16 @@ mod tests {
         
         // Alice cannot transfer funds she doesn't have.
         assert_eq!(
-            balances.transfer("alice".to_string(), "bob".to_string(), 50),
-            Err("Insufficient funds.")
+            balances.transfer(&"alice".to_string(), &"bob".to_string(), 50),
+            Err(DispatchError::InsufficientFunds)
         ) ;
 
         // Providing alice with some balance.
-        balances.set_balance(&"alice".to_string(), 100) ;
+        assert_eq!(balances.set_balance(&"alice".to_string(), 100), Ok(())) ;
 
         // Alice can now transfer funds.
         assert_eq!(
-            balances.transfer("alice".to_string(), "bob".to_string(), 55),
+            balances.transfer(&"alice".to_string(), &"bob".to_string(), 55),
             Ok(())
         ) ;
 
@@ -152,8 +1173,884 @@ mod tests {
 
         // Alice can no longer transfer funds greater than amount of 45.
         assert_eq!(
-            balances.transfer("alice".to_string(), "bob".to_string(), 50),
-            Err("Insufficient funds.")
+            balances.transfer(&"alice".to_string(), &"bob".to_string(), 50),
+            Err(DispatchError::InsufficientFunds)
+        ) ;
+    }
+
+    #[test]
+    fn mint_balance() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+
+        // Minting credits the account and increases the total issuance.
+        assert_eq!(balances.mint(&"alice".to_string(), 100), Ok(())) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 100) ;
+        assert_eq!(balances.total_issuance(), 100) ;
+
+        // Transfers only move balance around, so the total issuance is unchanged and still equals
+        // the sum of all balances.
+        assert_eq!(balances.transfer(&"alice".to_string(), &"bob".to_string(), 40), Ok(())) ;
+        assert_eq!(balances.total_issuance(), 100) ;
+        assert_eq!(
+            balances.balances.values().map(|account| account.free + account.reserved).sum::<u128>(),
+            balances.total_issuance()
+        ) ;
+
+        // Minting beyond the maximum total issuance fails.
+        assert_eq!(balances.mint(&"bob".to_string(), u128::MAX), Err(DispatchError::TotalIssuanceOverflow)) ;
+        assert_eq!(balances.total_issuance(), 100) ;
+    }
+
+    #[test]
+    fn burn_balance() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let _ = balances.mint(&"alice".to_string(), 100) ;
+
+        // Alice cannot burn more than she holds.
+        assert_eq!(balances.burn(&"alice".to_string(), 101), Err(DispatchError::InsufficientFunds)) ;
+        assert_eq!(balances.total_issuance(), 100) ;
+
+        // Burning part of the balance reduces both the balance and the total issuance.
+        assert_eq!(balances.burn(&"alice".to_string(), 30), Ok(())) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 70) ;
+        assert_eq!(balances.total_issuance(), 70) ;
+
+        // Burning the full balance removes the account from storage.
+        assert_eq!(balances.burn(&"alice".to_string(), 70), Ok(())) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 0) ;
+        assert_eq!(balances.balances.len(), 0) ;
+        assert_eq!(balances.total_issuance(), 0) ;
+    }
+
+    #[test]
+    fn reap_dust_accounts() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let _ = balances.mint(&"alice".to_string(), 100) ;
+
+        // Leaving exactly the existential deposit keeps the account alive.
+        assert_eq!(balances.transfer(&"alice".to_string(), &"bob".to_string(), 90), Ok(())) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 10) ;
+        assert!(balances.balances.contains_key("alice")) ;
+
+        // Leaving less than the existential deposit reaps the account and burns the dust.
+        assert_eq!(balances.transfer(&"bob".to_string(), &"alice".to_string(), 85), Ok(())) ;
+        assert!(!balances.balances.contains_key("bob")) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 95) ;
+        assert_eq!(balances.total_issuance(), 95) ;
+
+        // Transferring the entire balance also reaps the account.
+        assert_eq!(balances.transfer(&"alice".to_string(), &"charlie".to_string(), 95), Ok(())) ;
+        assert!(!balances.balances.contains_key("alice")) ;
+        assert_eq!(balances.balance(&"charlie".to_string()), 95) ;
+        assert_eq!(balances.total_issuance(), 95) ;
+    }
+
+    #[test]
+    fn transfer_keep_alive() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let _ = balances.mint(&"alice".to_string(), 100) ;
+
+        // Alice cannot go one unit below the existential deposit.
+        assert_eq!(
+            balances.transfer_keep_alive(&"alice".to_string(), &"bob".to_string(), 91),
+            Err(DispatchError::WouldKillAccount)
+        ) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 100) ;
+
+        // Alice can keep exactly the existential deposit.
+        assert_eq!(balances.transfer_keep_alive(&"alice".to_string(), &"bob".to_string(), 90), Ok(())) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 10) ;
+        assert_eq!(balances.balance(&"bob".to_string()), 90) ;
+    }
+
+    #[test]
+    fn transferable() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+
+        // The existential deposit stays behind.
+        assert_eq!(balances.transferable(&alice), 90) ;
+        assert_eq!(balances.transferable(&bob), 0) ;
+
+        // Reserved funds keep the account alive, so all of the free balance can be sent.
+        let _ = balances.reserve(&alice, 30) ;
+        assert_eq!(balances.transferable(&alice), 70) ;
+        assert_eq!(balances.transfer_keep_alive(&alice, &bob, 71), Err(DispatchError::InsufficientFunds)) ;
+
+        // A lock above the existential deposit is the floor instead.
+        let _ = balances.unreserve(&alice, 30) ;
+        balances.set_lock(STAKING_ID, &alice, 40) ;
+        assert_eq!(balances.transferable(&alice), 60) ;
+        assert_eq!(balances.transfer_keep_alive(&alice, &bob, 61), Err(DispatchError::LiquidityRestrictions)) ;
+        assert_eq!(balances.transfer_keep_alive(&alice, &bob, 60), Ok(())) ;
+        assert_eq!(balances.transferable(&alice), 0) ;
+
+        balances.freeze(&bob) ;
+        assert_eq!(balances.transferable(&bob), 0) ;
+    }
+
+    #[test]
+    fn reserve_balance() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let _ = balances.mint(&"alice".to_string(), 100) ;
+
+        // Alice cannot reserve more than her free balance.
+        assert_eq!(balances.reserve(&"alice".to_string(), 101), Err(DispatchError::InsufficientFreeBalance)) ;
+
+        // Reserving moves funds from free to reserved.
+        assert_eq!(balances.reserve(&"alice".to_string(), 60), Ok(())) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 40) ;
+        assert_eq!(balances.reserved_balance(&"alice".to_string()), 60) ;
+
+        // Transfers can only spend the free balance.
+        assert_eq!(
+            balances.transfer(&"alice".to_string(), &"bob".to_string(), 50),
+            Err(DispatchError::InsufficientFunds)
+        ) ;
+        assert_eq!(balances.transfer(&"alice".to_string(), &"bob".to_string(), 40), Ok(())) ;
+        assert_eq!(balances.reserved_balance(&"alice".to_string()), 60) ;
+
+        // Partially unreserving moves funds back to free.
+        assert_eq!(balances.unreserve(&"alice".to_string(), 20), Ok(())) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 20) ;
+        assert_eq!(balances.reserved_balance(&"alice".to_string()), 40) ;
+
+        // Unreserving more than is reserved only moves what is reserved.
+        assert_eq!(balances.unreserve(&"alice".to_string(), 100), Ok(())) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 60) ;
+        assert_eq!(balances.reserved_balance(&"alice".to_string()), 0) ;
+        assert_eq!(balances.total_issuance(), 100) ;
+    }
+
+    #[test]
+    fn batch_transfer() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let _ = balances.mint(&"alice".to_string(), 100) ;
+
+        // A batch where every transfer succeeds is fully applied.
+        assert_eq!(
+            balances.batch_transfer(
+                &"alice".to_string(),
+                vec![("bob".to_string(), 20), ("charlie".to_string(), 30)]
+            ),
+            Ok(())
+        ) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 50) ;
+        assert_eq!(balances.balance(&"bob".to_string()), 20) ;
+        assert_eq!(balances.balance(&"charlie".to_string()), 30) ;
+
+        // The third transfer fails due to insufficient funds, so none of the transfers are applied.
+        assert_eq!(
+            balances.batch_transfer(
+                &"alice".to_string(),
+                vec![("bob".to_string(), 10), ("charlie".to_string(), 10), ("dave".to_string(), 40)]
+            ),
+            Err(DispatchError::BatchTransferFailed)
+        ) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 50) ;
+        assert_eq!(balances.balance(&"bob".to_string()), 20) ;
+        assert_eq!(balances.balance(&"charlie".to_string()), 30) ;
+        assert_eq!(balances.balance(&"dave".to_string()), 0) ;
+        assert_eq!(balances.total_issuance(), 100) ;
+
+        // Accounts created by a failed batch are not reported as created either.
+        let _ = crate::support::AccountProvider::take_provider_changes(&mut balances) ;
+        let _ = balances.batch_transfer(&"alice".to_string(), vec![("eve".to_string(), 10), ("dave".to_string(), 1000)]) ;
+        assert_eq!(crate::support::AccountProvider::take_provider_changes(&mut balances), vec![]) ;
+
+        // Within a block, a failed batch only rolls back its own transfers, and a successful batch can
+        // still be undone with the rest of the block.
+        crate::support::Journaled::start_journal(&mut balances) ;
+        assert_eq!(balances.transfer(&"alice".to_string(), &"bob".to_string(), 10), Ok(())) ;
+        let _ = balances.batch_transfer(&"alice".to_string(), vec![("eve".to_string(), 10), ("dave".to_string(), 1000)]) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 40) ;
+        assert_eq!(balances.batch_transfer(&"alice".to_string(), vec![("eve".to_string(), 10)]), Ok(())) ;
+        assert_eq!(balances.balance(&"eve".to_string()), 10) ;
+        crate::support::Journaled::undo_journal(&mut balances) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 50) ;
+        assert_eq!(balances.balance(&"bob".to_string()), 20) ;
+        assert_eq!(balances.balance(&"eve".to_string()), 0) ;
+    }
+
+    #[test]
+    fn force_transfer() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let _ = balances.mint(&"alice".to_string(), 100) ;
+
+        // Only the root origin can force a transfer, not even the account the funds are taken from.
+        assert_eq!(
+            balances.force_transfer(Origin::Signed("alice".to_string()), &"alice".to_string(), &"bob".to_string(), 50),
+            Err(DispatchError::BadOrigin)
+        ) ;
+        assert_eq!(
+            balances.force_transfer(Origin::None, &"alice".to_string(), &"bob".to_string(), 50),
+            Err(DispatchError::BadOrigin)
+        ) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 100) ;
+
+        // The root origin can move funds between any two accounts.
+        assert_eq!(
+            balances.force_transfer(Origin::Root, &"alice".to_string(), &"bob".to_string(), 50),
+            Ok(())
+        ) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 50) ;
+        assert_eq!(balances.balance(&"bob".to_string()), 50) ;
+
+        // Forced transfers still check for sufficient funds.
+        assert_eq!(
+            balances.force_transfer(Origin::Root, &"alice".to_string(), &"bob".to_string(), 51),
+            Err(DispatchError::InsufficientFunds)
+        ) ;
+    }
+
+    #[test]
+    fn transfer_events() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let _ = balances.mint(&"alice".to_string(), 100) ;
+
+        // Failed transfers emit no events.
+        let _ = balances.transfer(&"bob".to_string(), &"alice".to_string(), 10) ;
+
+        let _ = balances.transfer(&"alice".to_string(), &"bob".to_string(), 30) ;
+        let _ = balances.transfer(&"bob".to_string(), &"charlie".to_string(), 10) ;
+        assert_eq!(
+            balances.take_events(),
+            vec![
+                super::Event::Transferred { from: "alice".to_string(), to: "bob".to_string(), amount: 30 },
+                super::Event::Transferred { from: "bob".to_string(), to: "charlie".to_string(), amount: 10 },
+            ]
+        ) ;
+
+        // Taking the events empties the buffer.
+        assert_eq!(balances.take_events(), vec![]) ;
+    }
+
+    #[test]
+    fn can_transfer() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+
+        // "can_transfer" agrees with the real transfer, whichever way it goes.
+        for amount in [150, 60, 60] {
+            let expected = balances.can_transfer(&alice, &bob, amount) ;
+            assert_eq!(balances.transfer(&alice, &bob, amount), expected) ;
+        }
+        assert_eq!(balances.balance(&alice), 40) ;
+        assert_eq!(balances.can_transfer(&alice, &bob, 60), Err(DispatchError::InsufficientFunds)) ;
+
+        // The check also catches overflows at the recipient, without changing any balances. Balances
+        // add up to the total issuance, so such a balance can only be stored directly.
+        balances.set_account(&bob, super::AccountData { free: u128::MAX, reserved: 0 }) ;
+        assert_eq!(balances.can_transfer(&alice, &bob, 10), Err(DispatchError::BalanceOverflow)) ;
+        assert_eq!(balances.transfer(&alice, &bob, 10), Err(DispatchError::BalanceOverflow)) ;
+        assert_eq!(balances.balance(&alice), 40) ;
+        assert_eq!(balances.balance(&bob), u128::MAX) ;
+    }
+
+    #[test]
+    fn reject_noop_transfers() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let _ = balances.mint(&"alice".to_string(), 100) ;
+
+        // Zero-amount transfers are rejected.
+        assert_eq!(
+            balances.transfer(&"alice".to_string(), &"bob".to_string(), 0),
+            Err(DispatchError::CannotTransferZero)
+        ) ;
+
+        // Self transfers are rejected.
+        assert_eq!(
+            balances.transfer(&"alice".to_string(), &"alice".to_string(), 50),
+            Err(DispatchError::CannotTransferToSelf)
+        ) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 100) ;
+        assert_eq!(balances.take_events(), vec![]) ;
+
+        // Legitimate transfers still work.
+        assert_eq!(balances.transfer(&"alice".to_string(), &"bob".to_string(), 50), Ok(())) ;
+        assert_eq!(balances.balance(&"bob".to_string()), 50) ;
+    }
+
+    #[test]
+    fn slash_balance() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let _ = balances.mint(&"alice".to_string(), 100) ;
+        let _ = balances.mint(&"bob".to_string(), 100) ;
+
+        // Slashing less than the balance takes exactly the requested amount.
+        assert_eq!(balances.slash(&"bob".to_string(), 30), 30) ;
+        assert_eq!(balances.balance(&"bob".to_string()), 70) ;
+
+        // Slashing more than the free balance takes all of it, but leaves the reserved balance alone.
+        let _ = balances.reserve(&"alice".to_string(), 40) ;
+        assert_eq!(balances.slash(&"alice".to_string(), 150), 60) ;
+        assert_eq!(balances.balance(&"alice".to_string()), 0) ;
+        assert_eq!(balances.reserved_balance(&"alice".to_string()), 40) ;
+        assert_eq!(balances.total_issuance(), 110) ;
+
+        // The reserved balance is only slashed on request, and the account is reaped once it is gone.
+        assert_eq!(balances.slash_reserved(&"alice".to_string(), 150), 40) ;
+        assert_eq!(balances.reserved_balance(&"alice".to_string()), 0) ;
+        assert_eq!(balances.account_count(), 1) ;
+        assert_eq!(balances.total_issuance(), 70) ;
+    }
+
+    #[test]
+    fn deposit_saturating() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+
+        // Depositing less than would overflow deposits exactly the requested amount.
+        assert_eq!(balances.deposit_saturating(&alice, u128::MAX - 10), u128::MAX - 10) ;
+        assert_eq!(balances.balance(&alice), u128::MAX - 10) ;
+
+        // Depositing more caps the balance at its maximum, rather than failing like "mint".
+        assert_eq!(balances.mint(&alice, 20), Err(DispatchError::TotalIssuanceOverflow)) ;
+        assert_eq!(balances.deposit_saturating(&alice, 20), 10) ;
+        assert_eq!(balances.balance(&alice), u128::MAX) ;
+
+        // The total issuance caps the deposit too, so it stays the sum of every balance.
+        assert_eq!(balances.deposit_saturating(&bob, 20), 0) ;
+        assert_eq!(balances.balance(&bob), 0) ;
+        assert_eq!(balances.total_issuance(), u128::MAX) ;
+    }
+
+    #[test]
+    fn transfer_saturating() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let charlie = "charlie".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+        let _ = balances.mint(&bob, 100) ;
+
+        // Transferring more than held moves only the available balance, rather than failing, and the
+        // existential deposit stays behind so the sender is not reaped.
+        assert_eq!(balances.transfer(&alice, &bob, 150), Err(DispatchError::InsufficientFunds)) ;
+        assert_eq!(balances.transfer_saturating(&alice, &bob, 150), Ok(())) ;
+        assert_eq!(balances.balance(&alice), 10) ;
+        assert_eq!(balances.balance(&bob), 190) ;
+
+        // The locked balance stays behind too, and the amount actually moved is returned.
+        balances.set_lock(STAKING_ID, &bob, 150) ;
+        assert_eq!(balances.transfer_at_most(&bob, &charlie, 100), Ok(40)) ;
+        assert_eq!(balances.balance(&bob), 150) ;
+        assert_eq!(balances.balance(&charlie), 40) ;
+        assert_eq!(balances.take_events(), vec![
+            super::Event::Transferred { from: alice.clone(), to: bob.clone(), amount: 90 },
+            super::Event::Transferred { from: bob.clone(), to: charlie.clone(), amount: 40 },
+        ]) ;
+
+        // Nothing is moved from an account without a transferable balance, but that is not an error.
+        assert_eq!(balances.transfer_at_most(&alice, &bob, 10), Ok(0)) ;
+        assert_eq!(balances.transfer_saturating(&alice, &bob, 10), Ok(())) ;
+        assert_eq!(balances.balance(&alice), 10) ;
+        assert_eq!(balances.take_events(), vec![]) ;
+
+        // The request itself is still checked like a transfer.
+        assert_eq!(balances.transfer_at_most(&alice, &bob, 0), Err(DispatchError::CannotTransferZero)) ;
+        assert_eq!(balances.transfer_at_most(&bob, &bob, 10), Err(DispatchError::CannotTransferToSelf)) ;
+        balances.freeze(&charlie) ;
+        assert_eq!(balances.transfer_at_most(&bob, &charlie, 10), Err(DispatchError::AccountFrozen)) ;
+        assert_eq!(balances.transfer_at_most(&charlie, &bob, 10), Err(DispatchError::AccountFrozen)) ;
+        assert_eq!(balances.total_issuance(), 200) ;
+    }
+
+    #[test]
+    fn freeze_and_thaw() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+        let _ = balances.mint(&bob, 100) ;
+
+        // A frozen account can neither send nor receive, even if the transfer would fail anyway.
+        balances.freeze(&bob) ;
+        assert!(balances.is_frozen(&bob)) ;
+        assert_eq!(balances.transfer(&alice, &bob, 10), Err(DispatchError::AccountFrozen)) ;
+        assert_eq!(balances.transfer(&bob, &alice, 10), Err(DispatchError::AccountFrozen)) ;
+        assert_eq!(balances.transfer(&bob, &alice, 500), Err(DispatchError::AccountFrozen)) ;
+        assert_eq!(balances.can_transfer(&alice, &bob, 10), Err(DispatchError::AccountFrozen)) ;
+
+        // Its balances can still be queried.
+        assert_eq!(balances.balance(&bob), 100) ;
+        assert_eq!(balances.balance(&alice), 100) ;
+
+        // Once thawed, transfers work again.
+        balances.thaw(&bob) ;
+        assert!(!balances.is_frozen(&bob)) ;
+        assert_eq!(balances.transfer(&alice, &bob, 10), Ok(())) ;
+        assert_eq!(balances.transfer(&bob, &alice, 20), Ok(())) ;
+        assert_eq!(balances.balance(&alice), 110) ;
+        assert_eq!(balances.balance(&bob), 90) ;
+    }
+
+    // The same pallet, but with a fee paid for every transfer.
+    #[derive(Debug, PartialEq)]
+    struct FeeConfig ;
+    impl crate::system::Config for FeeConfig {
+        type AccountId = String ;
+        type BlockNumber = u32 ;
+        type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
+    }
+    impl crate::balances::Config for FeeConfig {
+        type Balance = u128 ;
+        const EXISTENTIAL_DEPOSIT: u128 = 10 ;
+        const TRANSFER_FEE: u128 = 5 ;
+        const MINIMUM_TRANSFER: u128 = 0 ;
+        fn fee_account() -> String {
+            "fees".to_string()
+        }
+        type AccountStore = std::collections::BTreeMap<String, crate::balances::AccountData<u128>> ;
+    }
+
+    #[test]
+    fn transfer_fee() {
+        let mut balances = super::Pallet::<FeeConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let fees = "fees".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+
+        // The recipient gets the amount, the fee account gets the fee, and the sender pays both.
+        assert_eq!(balances.transfer(&alice, &bob, 50), Ok(())) ;
+        assert_eq!(balances.balance(&bob), 50) ;
+        assert_eq!(balances.balance(&fees), 5) ;
+        assert_eq!(balances.balance(&alice), 45) ;
+        assert_eq!(balances.total_issuance(), 100) ;
+        assert_eq!(balances.take_events(), vec![
+            super::Event::FeePaid { who: alice.clone(), amount: 5 },
+            super::Event::Transferred { from: alice.clone(), to: bob.clone(), amount: 50 },
+        ]) ;
+
+        // The sender must be able to cover the fee along with the amount.
+        assert_eq!(balances.transfer(&alice, &bob, 41), Err(DispatchError::InsufficientFunds)) ;
+        assert_eq!(balances.can_transfer(&alice, &bob, 40), Ok(())) ;
+        assert_eq!(balances.balance(&alice), 45) ;
+        // Keeping the account alive, the existential deposit stays behind on top of the fee.
+        assert_eq!(balances.transferable(&alice), 30) ;
+
+        // The fee account pays no fee, and a fee paid to it is added to the amount it receives.
+        assert_eq!(balances.transfer(&fees, &bob, 5), Ok(())) ;
+        assert_eq!(balances.balance(&fees), 0) ;
+        assert_eq!(balances.transfer(&bob, &fees, 10), Ok(())) ;
+        assert_eq!(balances.balance(&fees), 15) ;
+        assert_eq!(balances.balance(&bob), 40) ;
+    }
+
+    #[test]
+    fn transfer_saturating_fee() {
+        let mut balances = super::Pallet::<FeeConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let fees = "fees".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+        balances.set_account(&fees, super::AccountData { free: u128::MAX - 20, reserved: 0 }) ;
+
+        // The fee account takes the fee on top of the amount it receives, so less is moved to it.
+        assert_eq!(balances.transfer_at_most(&alice, &fees, 50), Ok(15)) ;
+        assert_eq!(balances.balance(&fees), u128::MAX) ;
+        assert_eq!(balances.balance(&alice), 80) ;
+
+        // Once the fee account cannot take the fee, nothing can be moved to anyone else either.
+        assert_eq!(balances.transfer_at_most(&alice, &bob, 10), Ok(0)) ;
+        assert_eq!(balances.balance(&bob), 0) ;
+        assert_eq!(balances.balance(&alice), 80) ;
+    }
+
+    #[test]
+    fn balance_overflow() {
+        use crate::support::{Currency, ReservableCurrency} ;
+
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+
+        // Balances add up to the total issuance, so a balance at its maximum can only be stored directly.
+        balances.set_account(&bob, super::AccountData { free: u128::MAX, reserved: 0 }) ;
+
+        // Every way of crediting "bob" fails the same way, and leaves every balance as it was.
+        assert_eq!(balances.mint(&bob, 1), Err(DispatchError::BalanceOverflow)) ;
+        assert_eq!(Currency::deposit(&mut balances, &bob, 1), Err(DispatchError::BalanceOverflow)) ;
+        assert_eq!(balances.transfer(&alice, &bob, 10), Err(DispatchError::BalanceOverflow)) ;
+        assert_eq!(balances.transfer_keep_alive(&alice, &bob, 10), Err(DispatchError::BalanceOverflow)) ;
+        assert_eq!(
+            balances.force_transfer(Origin::Root, &alice, &bob, 10),
+            Err(DispatchError::BalanceOverflow)
         ) ;
+        assert_eq!(balances.approve(&alice, &"charlie".to_string(), 10), Ok(())) ;
+        assert_eq!(
+            balances.transfer_from(&"charlie".to_string(), &alice, &bob, 10),
+            Err(DispatchError::BalanceOverflow)
+        ) ;
+        assert_eq!(balances.allowance(&alice, &"charlie".to_string()), 10) ;
+        assert_eq!(balances.batch_transfer(&alice, vec![(bob.clone(), 10)]), Err(DispatchError::BatchTransferFailed)) ;
+        assert_eq!(balances.balance(&alice), 100) ;
+        assert_eq!(balances.balance(&bob), u128::MAX) ;
+        assert_eq!(balances.total_issuance(), 100) ;
+
+        // Moving balance between the free and reserved balances of an account is checked the same way.
+        balances.set_account(&bob, super::AccountData { free: u128::MAX, reserved: 1 }) ;
+        assert_eq!(ReservableCurrency::unreserve(&mut balances, &bob, 1), Err(DispatchError::BalanceOverflow)) ;
+        balances.set_account(&bob, super::AccountData { free: 1, reserved: u128::MAX }) ;
+        assert_eq!(ReservableCurrency::reserve(&mut balances, &bob, 1), Err(DispatchError::BalanceOverflow)) ;
+        assert_eq!(balances.reserved_balance(&bob), u128::MAX) ;
+
+        // Taking balance out of an account is checked the other way around.
+        assert_eq!(Currency::withdraw(&mut balances, &alice, 101), Err(DispatchError::InsufficientFunds)) ;
+        assert_eq!(balances.burn(&alice, 101), Err(DispatchError::InsufficientFunds)) ;
+
+        // The fee account is credited through the same check as the recipient.
+        let mut balances = super::Pallet::<FeeConfig>::new() ;
+        let _ = balances.mint(&alice, 100) ;
+        balances.set_account(&"fees".to_string(), super::AccountData { free: u128::MAX, reserved: 0 }) ;
+        assert_eq!(balances.can_transfer(&alice, &bob, 10), Err(DispatchError::BalanceOverflow)) ;
+        assert_eq!(balances.transfer(&alice, &bob, 10), Err(DispatchError::BalanceOverflow)) ;
+        assert_eq!(balances.balance(&alice), 100) ;
+        assert_eq!(balances.balance(&bob), 0) ;
+    }
+
+    // The same pallet, but with a minimum amount for every transfer.
+    #[derive(Debug, PartialEq)]
+    struct MinimumConfig ;
+    impl crate::system::Config for MinimumConfig {
+        type AccountId = String ;
+        type BlockNumber = u32 ;
+        type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
     }
-}
\ No newline at end of file
+    impl crate::balances::Config for MinimumConfig {
+        type Balance = u128 ;
+        const EXISTENTIAL_DEPOSIT: u128 = 1 ;
+        const TRANSFER_FEE: u128 = 0 ;
+        const MINIMUM_TRANSFER: u128 = 10 ;
+        fn fee_account() -> String {
+            "fees".to_string()
+        }
+        type AccountStore = std::collections::BTreeMap<String, crate::balances::AccountData<u128>> ;
+    }
+
+    #[test]
+    fn minimum_transfer() {
+        let mut balances = super::Pallet::<MinimumConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+
+        // The amount is checked before the balances, even when the sender could not afford it anyway.
+        assert_eq!(balances.transfer(&alice, &bob, 9), Err(DispatchError::BelowMinimumTransfer)) ;
+        assert_eq!(balances.transfer(&bob, &alice, 9), Err(DispatchError::BelowMinimumTransfer)) ;
+        assert_eq!(balances.transfer(&alice, &bob, 0), Err(DispatchError::CannotTransferZero)) ;
+        assert_eq!(balances.balance(&alice), 100) ;
+
+        assert_eq!(balances.transfer(&alice, &bob, 10), Ok(())) ;
+        assert_eq!(balances.balance(&alice), 90) ;
+        assert_eq!(balances.balance(&bob), 10) ;
+
+        // A saturating transfer must ask for the minimum, and moves nothing if less could be moved.
+        assert_eq!(balances.transfer_at_most(&alice, &bob, 9), Err(DispatchError::BelowMinimumTransfer)) ;
+        balances.set_lock(STAKING_ID, &alice, 85) ;
+        assert_eq!(balances.transfer_at_most(&alice, &bob, 50), Ok(0)) ;
+        assert_eq!(balances.balance(&alice), 90) ;
+        assert_eq!(balances.transfer_at_most(&alice, &bob, 5), Err(DispatchError::BelowMinimumTransfer)) ;
+    }
+
+    #[test]
+    fn currency() {
+        use crate::support::Currency ;
+
+        // A pallet which pays a fixed salary, using whatever currency it is given.
+        struct Payroll {
+            salary: u128,
+        }
+        impl Payroll {
+            fn pay(
+                &self,
+                currency: &mut impl Currency<String, Balance = u128>,
+                from: &String,
+                to: &String
+            ) -> crate::support::DispatchResult {
+                currency.transfer(from, to, self.salary)
+            }
+        }
+
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let payroll = Payroll { salary: 30 } ;
+
+        assert_eq!(balances.deposit(&alice, 100), Ok(())) ;
+        assert_eq!(payroll.pay(&mut balances, &alice, &bob), Ok(())) ;
+        assert_eq!(balances.total_balance(&alice), 70) ;
+        assert_eq!(balances.total_balance(&bob), 30) ;
+
+        // The total balance includes reserved balance.
+        let _ = balances.reserve(&alice, 50) ;
+        assert_eq!(balances.total_balance(&alice), 70) ;
+
+        assert_eq!(balances.withdraw(&bob, 10), Ok(())) ;
+        assert_eq!(balances.withdraw(&bob, 100), Err(DispatchError::InsufficientFunds)) ;
+        assert_eq!(balances.total_balance(&bob), 20) ;
+        assert_eq!(balances.total_issuance(), 90) ;
+    }
+
+    #[test]
+    fn approve_and_transfer_from() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let charlie = "charlie".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+
+        // Nothing can be spent on behalf of an account which approved nothing.
+        assert_eq!(balances.allowance(&alice, &bob), 0) ;
+        assert_eq!(balances.transfer_from(&bob, &alice, &charlie, 10), Err(DispatchError::AllowanceExceeded)) ;
+
+        assert_eq!(balances.approve(&alice, &bob, 50), Ok(())) ;
+        assert_eq!(balances.allowance(&alice, &bob), 50) ;
+
+        // Spending on behalf of the owner reduces the allowance.
+        assert_eq!(balances.transfer_from(&bob, &alice, &charlie, 30), Ok(())) ;
+        assert_eq!(balances.balance(&alice), 70) ;
+        assert_eq!(balances.balance(&charlie), 30) ;
+        assert_eq!(balances.balance(&bob), 0) ;
+        assert_eq!(balances.allowance(&alice, &bob), 20) ;
+
+        // The allowance cannot be exceeded, and can only be used by the spender it was approved for.
+        assert_eq!(balances.transfer_from(&bob, &alice, &charlie, 30), Err(DispatchError::AllowanceExceeded)) ;
+        assert_eq!(balances.transfer_from(&charlie, &alice, &charlie, 10), Err(DispatchError::AllowanceExceeded)) ;
+        assert_eq!(balances.allowance(&alice, &bob), 20) ;
+
+        // Spending the rest of the allowance removes it.
+        assert_eq!(balances.transfer_from(&bob, &alice, &charlie, 20), Ok(())) ;
+        assert!(balances.allowances.is_empty()) ;
+    }
+
+    #[test]
+    fn transfer_from_insufficient_funds() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 20) ;
+        let _ = balances.approve(&alice, &bob, 100) ;
+
+        // An allowance larger than the balance of the owner does not allow spending more than it.
+        assert_eq!(balances.transfer_from(&bob, &alice, &bob, 50), Err(DispatchError::InsufficientFunds)) ;
+        assert_eq!(balances.allowance(&alice, &bob), 100) ;
+        assert_eq!(balances.balance(&alice), 20) ;
+
+        // Approving zero revokes the allowance.
+        assert_eq!(balances.approve(&alice, &bob, 0), Ok(())) ;
+        assert_eq!(balances.allowance(&alice, &bob), 0) ;
+        assert_eq!(
+            balances.take_events(),
+            vec![
+                super::Event::Approved { owner: alice.clone(), spender: bob.clone(), amount: 100 },
+                super::Event::Approved { owner: alice.clone(), spender: bob.clone(), amount: 0 },
+            ]
+        ) ;
+    }
+
+    #[test]
+    fn locked_balance() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+        balances.set_lock(STAKING_ID, &alice, 60) ;
+
+        // Only the balance which is not locked can be transferred.
+        assert_eq!(balances.transfer(&alice, &bob, 50), Err(DispatchError::LiquidityRestrictions)) ;
+        assert_eq!(balances.can_transfer(&alice, &bob, 50), Err(DispatchError::LiquidityRestrictions)) ;
+        assert_eq!(balances.transfer(&alice, &bob, 40), Ok(())) ;
+        assert_eq!(balances.balance(&alice), 60) ;
+
+        // Locking zero unlocks everything.
+        balances.set_lock(STAKING_ID, &alice, 0) ;
+        assert_eq!(balances.locked_balance(&alice), 0) ;
+        assert_eq!(balances.transfer(&alice, &bob, 50), Ok(())) ;
+    }
+
+    #[test]
+    fn overlapping_locks() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+
+        // Locks under different ids overlap, so only the larger one counts.
+        balances.set_lock(STAKING_ID, &alice, 30) ;
+        balances.set_lock(VESTING_ID, &alice, 50) ;
+        assert_eq!(balances.locked_balance(&alice), 50) ;
+        assert_eq!(balances.transfer(&alice, &bob, 51), Err(DispatchError::LiquidityRestrictions)) ;
+        assert_eq!(balances.transfer(&alice, &bob, 20), Ok(())) ;
+
+        // Raising one lock above the other makes it the limit, and replaces its old amount.
+        balances.set_lock(STAKING_ID, &alice, 70) ;
+        assert_eq!(balances.locked_balance(&alice), 70) ;
+        assert_eq!(balances.transfer(&alice, &bob, 11), Err(DispatchError::LiquidityRestrictions)) ;
+        assert_eq!(balances.transfer(&alice, &bob, 10), Ok(())) ;
+
+        // Removing the larger lock leaves the smaller one in place.
+        balances.remove_lock(STAKING_ID, &alice) ;
+        assert_eq!(balances.locked_balance(&alice), 50) ;
+        balances.remove_lock(VESTING_ID, &alice) ;
+        assert_eq!(balances.locked_balance(&alice), 0) ;
+        assert!(balances.locks.is_empty()) ;
+    }
+
+    #[test]
+    fn iterate_accounts() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        let _ = balances.mint(&"charlie".to_string(), 30) ;
+        let _ = balances.mint(&"alice".to_string(), 10) ;
+        let _ = balances.mint(&"bob".to_string(), 20) ;
+
+        // Accounts are iterated in order, whatever order they were funded in.
+        assert_eq!(balances.account_count(), 3) ;
+        assert_eq!(
+            balances.accounts().map(|(who, amount)| (who.as_str(), *amount)).collect::<Vec<_>>(),
+            vec![("alice", 10), ("bob", 20), ("charlie", 30)]
+        ) ;
+
+        // Accounts stored in a hash map are iterated in the order of their hashes instead.
+        let mut balances = super::Pallet::<HashMapConfig>::new() ;
+        let _ = balances.mint(&"charlie".to_string(), 30) ;
+        let _ = balances.mint(&"alice".to_string(), 10) ;
+        let _ = balances.mint(&"bob".to_string(), 20) ;
+        assert_eq!(
+            balances.accounts().map(|(who, amount)| (who.as_str(), *amount)).collect::<Vec<_>>(),
+            vec![("bob", 20), ("alice", 10), ("charlie", 30)]
+        ) ;
+    }
+
+    #[test]
+    fn top_balances() {
+        let mut balances = super::Pallet::<TestConfig>::new() ;
+        for (who, amount) in [("alice", 10), ("bob", 50), ("charlie", 30), ("dave", 50), ("eve", 20)] {
+            let _ = balances.mint(&who.to_string(), amount) ;
+        }
+
+        // Ties are broken by account.
+        assert_eq!(balances.top_balances(2), vec![("bob".to_string(), 50), ("dave".to_string(), 50)]) ;
+        assert_eq!(balances.top_balances(3).last(), Some(&("charlie".to_string(), 30))) ;
+        assert_eq!(balances.top_balances(10).len(), 5) ;
+        assert_eq!(balances.top_balances(0), vec![]) ;
+    }
+
+    #[test]
+    fn dispatch_transfer() {
+        use crate::support::Dispatch ;
+
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let mut direct = super::Pallet::<TestConfig>::new() ;
+        let _ = direct.mint(&alice, 100) ;
+        let mut dispatched = super::Pallet::<TestConfig>::new() ;
+        let _ = dispatched.mint(&alice, 100) ;
+
+        // Dispatching the generated call has the same effect as calling the function directly.
+        assert_eq!(direct.transfer(&alice, &bob, 30), Ok(())) ;
+        let call = super::Call::transfer { to: bob.clone(), amount: 30 } ;
+        assert!(dispatched.dispatch(Origin::Signed(alice.clone()), call).is_ok()) ;
+        assert_eq!(dispatched, direct) ;
+
+        // Errors are passed on as they are.
+        let call = super::Call::transfer { to: bob.clone(), amount: 1000 } ;
+        assert_eq!(dispatched.dispatch(Origin::Signed(alice.clone()), call), Err(DispatchError::InsufficientFunds)) ;
+        assert_eq!(dispatched.balance(&alice), 70) ;
+    }
+
+    thread_local! {
+        // The number of times a "CountedId" was cloned on this thread.
+        static CLONES: core::cell::Cell<usize> = const { core::cell::Cell::new(0) } ;
+    }
+
+    // An account id which counts how often it is cloned.
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct CountedId(&'static str) ;
+    impl Clone for CountedId {
+        fn clone(&self) -> Self {
+            CLONES.with(|clones| clones.set(clones.get() + 1)) ;
+            CountedId(self.0)
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct CountedConfig ;
+    impl crate::system::Config for CountedConfig {
+        type AccountId = CountedId ;
+        type BlockNumber = u32 ;
+        type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
+    }
+    impl crate::balances::Config for CountedConfig {
+        type Balance = u128 ;
+        const EXISTENTIAL_DEPOSIT: u128 = 10 ;
+        const TRANSFER_FEE: u128 = 0 ;
+        const MINIMUM_TRANSFER: u128 = 0 ;
+        fn fee_account() -> CountedId {
+            CountedId("fees")
+        }
+        type AccountStore = std::collections::BTreeMap<CountedId, crate::balances::AccountData<u128>> ;
+    }
+
+    #[test]
+    fn transfer_without_clones() {
+        let clones = || CLONES.with(|clones| clones.get()) ;
+        let mut balances = super::Pallet::<CountedConfig>::new() ;
+        let _ = balances.mint(&CountedId("alice"), 100) ;
+        let _ = balances.mint(&CountedId("bob"), 100) ;
+        let before = clones() ;
+
+        // Reading balances never clones the account ids.
+        assert_eq!(balances.balance(&CountedId("alice")), 100) ;
+        assert_eq!(balances.can_transfer(&CountedId("alice"), &CountedId("bob"), 50), Ok(())) ;
+        assert_eq!(
+            balances.transfer(&CountedId("alice"), &CountedId("bob"), 1000),
+            Err(DispatchError::InsufficientFunds)
+        ) ;
+        assert_eq!(clones(), before) ;
+
+        // Transferring between existing accounts only clones the ids into the event.
+        assert_eq!(balances.transfer(&CountedId("alice"), &CountedId("bob"), 50), Ok(())) ;
+        assert_eq!(clones(), before + 2) ;
+
+        // Creating a new account also clones its id once for storage, and once to report it to the runtime.
+        assert_eq!(balances.transfer(&CountedId("alice"), &CountedId("charlie"), 20), Ok(())) ;
+        assert_eq!(clones(), before + 6) ;
+        assert_eq!(balances.balance(&CountedId("charlie")), 20) ;
+
+        // Allowances are looked up without cloning the account ids either.
+        assert_eq!(balances.approve(&CountedId("alice"), &CountedId("bob"), 10), Ok(())) ;
+        let before = clones() ;
+        assert_eq!(balances.allowance(&CountedId("alice"), &CountedId("bob")), 10) ;
+        assert_eq!(
+            balances.transfer_from(&CountedId("bob"), &CountedId("alice"), &CountedId("charlie"), 20),
+            Err(DispatchError::AllowanceExceeded)
+        ) ;
+        assert_eq!(clones(), before) ;
+    }
+}
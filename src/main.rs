@@ -1,7 +1,19 @@
+// Not every pallet function is used by our `main` function, some of them are only exercised by tests.
+#![allow(dead_code)]
+
 mod balances ;
 mod system ;
 mod support ;
 mod proof_of_existence ;
+mod staking ;
+mod voting ;
+mod treasury ;
+mod nft ;
+mod multisig ;
+mod scheduler ;
+mod vesting ;
+mod utility ;
+mod pool ;
 
 use crate::support::Dispatch ;
 
@@ -9,37 +21,363 @@ use crate::support::Dispatch ;
 /// Modules are configured for these types directly, and they satisfy all of our trait requirements.
 mod types {
 	pub type AccountId = String ;
-	pub type Balance = u128 ; 
+	// Blocks given as JSON cannot carry 128 bit numbers in their calls, so we stick to 64 bits.
+	pub type Balance = u64 ;
 	pub type BlockNumber = u32 ;
 	pub type Nonce = u32 ;
-	pub type Extrinsic = crate::support::Extrinsic<AccountId, crate::RuntimeCall> ;
-	pub type Header = crate::support::Header<BlockNumber> ;
+	pub type Hash = u64 ;
+	pub type Hashing = crate::support::Fnv ;
+	pub type Moment = u64 ;
+	pub type Extrinsic = crate::support::SignedExtrinsic<AccountId, crate::RuntimeCall, Nonce, crate::support::Signature> ;
+	pub type Header = crate::support::Header<BlockNumber, Moment, Hash> ;
 	pub type Block = crate::support::Block<Header, Extrinsic> ;
-	pub type Content = &'static str ;
+	pub type Content = String ;
+	pub type ProposalId = u32 ;
+	pub type Description = String ;
+	pub type CollectionId = u32 ;
+	pub type ItemId = u32 ;
+	pub type Metadata = String ;
+	pub type CallHash = Hash ;
+}
+
+// Every pallet of our main Runtime is declared once, along with its config.
+support::construct_runtime! {
+	/// This is our main Runtime.
+	/// It accumulates all the different pallets we want to use.
+	#[derive(Debug, Clone, PartialEq)]
+	#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+	pub struct Runtime {
+		system: system {
+			type AccountId = types::AccountId ;
+			type BlockNumber = types::BlockNumber ;
+			type Nonce = types::Nonce ;
+			type Hash = types::Hash ;
+			type Hashing = types::Hashing ;
+			type Moment = types::Moment ;
+			const BLOCK_HASH_COUNT: types::BlockNumber = 256 ;
+			const MAX_BLOCK_WEIGHT: support::Weight = support::Weight(20) ;
+			type RuntimeEvent = RuntimeEvent ;
+			// An account may submit at most a full block of extrinsics over any 10 blocks.
+			const RATE_LIMIT: Option<u32> = Some(20) ;
+			const RATE_LIMIT_WINDOW: u32 = 10 ;
+		},
+		#[account_provider]
+		balances: balances {
+			type Balance = types::Balance ;
+			const EXISTENTIAL_DEPOSIT: types::Balance = 1 ;
+			// Transfers are free, so their fees never reach the fee account.
+			const TRANSFER_FEE: types::Balance = 0 ;
+			const MINIMUM_TRANSFER: types::Balance = 0 ;
+			fn fee_account() -> types::AccountId {
+				"fees".to_string()
+			}
+			type AccountStore = std::collections::BTreeMap<types::AccountId, balances::AccountData<types::Balance>> ;
+		},
+		#[dispatch_with(balances)]
+		proof_of_existence: proof_of_existence {
+			type Content = types::Content ;
+			const MAX_CLAIMS: u32 = 100 ;
+			const MAX_CONTENT_LEN: u32 = 256 ;
+			type Currency = balances::Pallet<Self> ;
+			const CLAIM_DEPOSIT: types::Balance = 1 ;
+		},
+		#[dispatch_with(balances)]
+		staking: staking {
+			type Currency = balances::Pallet<Runtime> ;
+		},
+		#[dispatch_with(balances)]
+		voting: voting {
+			type ProposalId = types::ProposalId ;
+			type Description = types::Description ;
+			type Currency = balances::Pallet<Runtime> ;
+		},
+		#[dispatch_with(balances)]
+		#[charge_fees(balances)]
+		treasury: treasury {
+			type Currency = balances::Pallet<Runtime> ;
+			const FEE_PER_EXTRINSIC: types::Balance = 1 ;
+		},
+		nft: nft {
+			type CollectionId = types::CollectionId ;
+			type ItemId = types::ItemId ;
+			type Metadata = types::Metadata ;
+		},
+		#[queued_calls]
+		multisig: multisig {
+			type RuntimeCall = RuntimeCall ;
+			type CallHash = types::CallHash ;
+			fn hash_call(call: &RuntimeCall) -> types::CallHash {
+				<types::Hashing as support::Hasher>::hash(&support::Encode::encode(call))
+			}
+		},
+		#[queued_calls]
+		scheduler: scheduler {
+			type RuntimeCall = RuntimeCall ;
+		},
+		#[dispatch_with(balances)]
+		vesting: vesting {
+			type Currency = balances::Pallet<Runtime> ;
+			fn block_number_to_balance(number: types::BlockNumber) -> types::Balance {
+				number.into()
+			}
+		},
+		#[batch_calls]
+		utility: utility {
+			type RuntimeCall = RuntimeCall ;
+		},
+	}
+	with {
+		/// Where the errors of failed extrinsics are reported.
+		#[cfg_attr(feature = "serde", serde(skip))]
+		logger: support::SharedLogger,
+	}
 }
 
-/// This is our main Runtime.
-/// It accumulates all the different pallets we want to use.
-#[macros::runtime]
-#[derive(Debug)]
-pub struct Runtime {
-	system: system::Pallet<Self>,
-	balances: balances::Pallet<Self>,
-	proof_of_existence: proof_of_existence::Pallet<Self>,
+/// The initial state of our Runtime, set up before any block is executed.
+#[derive(Debug, Default)]
+pub struct GenesisConfig {
+	/// The accounts to endow, along with the balance each of them starts with.
+	pub balances: Vec<(types::AccountId, types::Balance)>,
+	/// The content which is already claimed, along with the owner of each claim.
+	pub claims: Vec<(types::Content, types::AccountId)>,
 }
 
-impl system::Config for Runtime {
-	type AccountId = types::AccountId ;
-	type BlockNumber = types::BlockNumber ;
-	type Nonce = types::Nonce ;
+impl Runtime {
+	/// Create a new instance of our Runtime, with the state of every pallet seeded from "config".
+	/// This panics if the config is invalid, e.g. if the same content is claimed twice.
+	fn from_genesis(config: GenesisConfig) -> Self {
+		let mut runtime = Self::new() ;
+		for (who, amount) in config.balances {
+			runtime.balances.mint(&who, amount).expect("Invalid genesis balance.") ;
+		}
+		for (claim, owner) in config.claims {
+			runtime.proof_of_existence.create_claim(owner, &mut runtime.balances, claim).expect("Invalid genesis claim.") ;
+		}
+
+		// Genesis state is not the result of any extrinsic, so it emits no events. The endowed
+		// accounts still exist though.
+		runtime.collect_provider_changes() ;
+		let _ = runtime.proof_of_existence.take_events() ;
+		runtime
+	}
+
+	/// Build the next block out of the extrinsics in "pool", picking the extrinsics paying the most fee
+	/// for their weight first, as long as the weight of the block stays within "max_weight".
+	/// Every extrinsic pays the same flat fee, so the lightest extrinsic pays the most for its weight.
+	/// The extrinsics of every caller are still picked in nonce order, starting from its current nonce
+	/// without gaps, so once the next extrinsic of a caller does not fit, none of its later ones do.
+	/// Extrinsics not signed by their caller, or whose nonce was used already, are left out.
+	/// The block is timestamped one after the current block. This function fails if the block number
+	/// or the timestamp would overflow.
+	fn build_block(&self, pool: &[types::Extrinsic], max_weight: support::Weight) -> Result<types::Block, support::DispatchError> {
+		// The extrinsics of every caller which can be executed one after the other, in nonce order.
+		let mut queues = std::collections::BTreeMap::<&types::AccountId, Vec<&types::Extrinsic>>::new() ;
+		for extrinsic in pool.iter().filter(|extrinsic| extrinsic.verify()) {
+			queues.entry(&extrinsic.caller).or_default().push(extrinsic) ;
+		}
+		let mut queues = queues
+			.into_iter()
+			.map(|(caller, mut queue)| {
+				queue.sort_by_key(|extrinsic| extrinsic.nonce) ;
+				let mut next = self.system.nonce(caller) ;
+				queue.retain(|extrinsic| extrinsic.nonce >= next) ;
+				queue.dedup_by_key(|extrinsic| extrinsic.nonce) ;
+				queue
+					.into_iter()
+					.take_while(|extrinsic| {
+						let in_order = extrinsic.nonce == next ;
+						next = next.saturating_add(1) ;
+						in_order
+					})
+					.collect::<std::collections::VecDeque<_>>()
+			})
+			.collect::<Vec<_>>() ;
+
+		let mut used_weight = support::Weight(0) ;
+		let mut extrinsics = Vec::new() ;
+		// Out of the next extrinsic of every caller, pick the lightest one, until none is left.
+		while let Some(index) = (0..queues.len())
+			.filter(|index| !queues[*index].is_empty())
+			.min_by_key(|index| self.weight(&queues[*index][0].call))
+		{
+			let weight = self.weight(&queues[index][0].call) ;
+			let total_weight = support::Weight(used_weight.0.saturating_add(weight.0)) ;
+			if total_weight > max_weight {
+				queues[index].clear() ;
+				continue ;
+			}
+			used_weight = total_weight ;
+			extrinsics.extend(queues[index].pop_front().cloned()) ;
+		}
+
+		let (block_number, timestamp) = self.next_block() ?;
+		Ok(types::Block::new::<types::Hashing>(self.parent_hash(), block_number, timestamp, extrinsics))
+	}
+
+	/// Get the number and the timestamp of the next block, one after the current block each.
+	/// This function fails if either of them would overflow.
+	fn next_block(&self) -> Result<(types::BlockNumber, types::Moment), support::DispatchError> {
+		let block_number = self.system.next_block_number() ?;
+		let timestamp = self.system.timestamp().checked_add(1).ok_or(support::DispatchError::TimestampOverflow) ?;
+		Ok((block_number, timestamp))
+	}
+
+	/// Parse a command like "parse_command" does, and execute it as the only extrinsic of the next
+	/// block, signed by its caller with their current nonce.
+	/// This function fails if the command is malformed, the next block number or timestamp would
+	/// overflow, or the block is invalid, and otherwise returns the result of the extrinsic.
+	fn execute_command(&mut self, line: &str) -> Result<support::DispatchResult, String> {
+		let (caller, call) = parse_command(line) ?;
+		let nonce = self.system.nonce(&caller) ;
+		let extrinsic = support::Extrinsic { caller, call, nonce }.sign() ;
+		let (block_number, timestamp) = self.next_block() ?;
+		let block = types::Block::new::<types::Hashing>(self.parent_hash(), block_number, timestamp, vec![extrinsic]) ;
+		let results = self.execute_block(block) ?;
+		Ok(results[0])
+	}
+
+	/// Get read-only access to the state of this runtime, for frontends which only look at the state.
+	fn query(&self) -> RuntimeApi<'_> {
+		RuntimeApi { runtime: self }
+	}
 }
 
-impl balances::Config for Runtime {
-	type Balance = types::Balance ;
+/// The read-only API of our Runtime, as returned by "Runtime::query".
+/// It only ever reads the state of the pallets, so frontends do not need access to the pallets themselves.
+pub struct RuntimeApi<'a> {
+	runtime: &'a Runtime,
 }
 
-impl proof_of_existence::Config for Runtime {
-	type Content = types::Content ;
+impl RuntimeApi<'_> {
+	/// Get the balance of the account "who".
+	pub fn balance_of(&self, who: &types::AccountId) -> types::Balance {
+		self.runtime.balances.balance(who)
+	}
+
+	/// Get the nonce of the account "who", which its next extrinsic must carry.
+	pub fn nonce_of(&self, who: &types::AccountId) -> types::Nonce {
+		self.runtime.system.nonce(who)
+	}
+
+	/// Get the owner of the claim on "content", if it is claimed.
+	pub fn claim_owner(&self, content: &types::Content) -> Option<&types::AccountId> {
+		self.runtime.proof_of_existence.get_claim(content)
+	}
+
+	/// Get the number of the last block executed, which is zero before the first block.
+	pub fn current_block(&self) -> types::BlockNumber {
+		self.runtime.system.block_number()
+	}
+}
+
+/// Turn a "line" like "alice transfer bob 30" into its caller and call, for making calls from a REPL.
+/// Every line starts with the caller, followed by the name of the call and its arguments, separated by
+/// whitespace. These commands are supported:
+/// - "transfer <to> <amount>" and "transfer_keep_alive <to> <amount>", of the balances pallet.
+/// - "create_claim <claim>", "revoke_claim <claim>" and "transfer_claim <claim> <new_owner>", of the
+///   proof of existence pallet.
+/// - "bond <amount>" and "unbond <amount>", of the staking pallet.
+fn parse_command(line: &str) -> Result<(types::AccountId, RuntimeCall), String> {
+	let mut words = line.split_whitespace() ;
+	let caller = words.next().ok_or("Missing caller.") ?.to_string() ;
+	let command = words.next().ok_or("Missing command.") ? ;
+	let args = words.collect::<Vec<_>>() ;
+
+	// Every command takes a fixed number of arguments.
+	let expect_args = |count: usize| {
+		if args.len() != count {
+			return Err(format!("The command \"{}\" takes {} arguments, but {} were given.", command, count, args.len())) ;
+		}
+		Ok(())
+	} ;
+	let word = |index: usize| args[index].to_string() ;
+	let balance = |index: usize| {
+		args[index].parse::<types::Balance>().map_err(|_| format!("Invalid amount \"{}\".", args[index]))
+	} ;
+
+	let call = match command {
+		"transfer" => {
+			expect_args(2) ?;
+			RuntimeCall::balances(balances::Call::transfer { to: word(0), amount: balance(1) ? })
+		},
+		"transfer_keep_alive" => {
+			expect_args(2) ?;
+			RuntimeCall::balances(balances::Call::transfer_keep_alive { to: word(0), amount: balance(1) ? })
+		},
+		"create_claim" => {
+			expect_args(1) ?;
+			RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: word(0) })
+		},
+		"revoke_claim" => {
+			expect_args(1) ?;
+			RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim { claim: word(0) })
+		},
+		"transfer_claim" => {
+			expect_args(2) ?;
+			RuntimeCall::proof_of_existence(proof_of_existence::Call::transfer_claim { claim: word(0), new_owner: word(1) })
+		},
+		"bond" => {
+			expect_args(1) ?;
+			RuntimeCall::staking(staking::Call::bond { amount: balance(0) ? })
+		},
+		"unbond" => {
+			expect_args(1) ?;
+			RuntimeCall::staking(staking::Call::unbond { amount: balance(0) ? })
+		},
+		_ => return Err(format!("Unknown command \"{}\".", command)),
+	} ;
+	Ok((caller, call))
+}
+
+/// Read commands from "input" one line at a time, and execute every one of them in a block of its own
+/// with "Runtime::execute_command", until the input ends or a line says "quit".
+/// The result of every command is written to "output", followed by the events its block emitted.
+/// Malformed commands and failing calls are reported the same way, and the loop carries on.
+fn run_repl(runtime: &mut Runtime, input: impl std::io::BufRead, output: &mut impl std::io::Write) -> std::io::Result<()> {
+	for line in input.lines() {
+		let line = line ?;
+		match line.trim() {
+			"" => continue,
+			"quit" => break,
+			command => match runtime.execute_command(command) {
+				Ok(Ok(())) => writeln!(output, "Block {}: Ok.", runtime.system.block_number()) ?,
+				Ok(Err(e)) => writeln!(output, "Block {}: Error: {}", runtime.system.block_number(), e) ?,
+				Err(e) => writeln!(output, "Error: {}", e) ?,
+			},
+		}
+		for event in runtime.take_events() {
+			writeln!(output, "\t{:?}", event) ?;
+		}
+	}
+	Ok(())
+}
+
+#[cfg(feature = "serde")]
+impl Runtime {
+	/// Export the state of every pallet as JSON.
+	fn export_state(&self) -> String {
+		serde_json::to_string(self).expect("The runtime state can always be serialized.")
+	}
+
+	/// Replace the state of every pallet with the state exported to "json".
+	/// The logger of this runtime is kept, and the state is left untouched if "json" is invalid.
+	/// A state exported by an older runtime is migrated to the storage layout of this one.
+	fn import_state(&mut self, json: &str) -> Result<(), String> {
+		let logger = self.logger.clone() ;
+		*self = serde_json::from_str(json).map_err(|e| e.to_string()) ?;
+		self.logger = logger ;
+		self.runtime_upgrade() ;
+		Ok(())
+	}
+
+	/// Execute a block given as JSON, like "execute_block".
+	/// Every call is identified by the name of its pallet and the name of the call, for example:
+	/// {"pallet": "balances", "call": "transfer", "to": "bob", "amount": 30}
+	fn execute_block_json(&mut self, json: &str) -> Result<(), String> {
+		let block: types::Block = serde_json::from_str(json).map_err(|e| e.to_string()) ?;
+		self.execute_block(block) ?;
+		Ok(())
+	}
 }
 
 // All of the below code is provided by the "#[macro::runtime]" and does not need to be implemented.
@@ -110,63 +448,1771 @@ impl proof_of_existence::Config for Runtime {
 // }
 
 fn main() {
-	// Instantiating a new instance of our Runtime.
-	let mut runtime = Runtime::new() ;
+	// Instantiating a new instance of our Runtime, where "alice" starts with 100, allowing us to
+	// execute transactions.
+	let mut runtime = Runtime::from_genesis(GenesisConfig {
+		balances: vec![("alice".to_string(), 100)],
+		..Default::default()
+	}) ;
 
-	// Creating users.
-	let alice = "alice".to_string() ;
-	let bob = "bob".to_string() ;
-	let charlie = "charlie".to_string() ;
+	// Executing the commands given on stdin, like "alice transfer bob 30", one block at a time.
+	println!("Enter commands like \"alice transfer bob 30\" or \"alice create_claim hello\", or \"quit\" to stop.") ;
+	run_repl(&mut runtime, std::io::stdin().lock(), &mut std::io::stdout()).expect("Failed to read the commands.") ;
 
-	// Set balance of "alice" to 100, allowing us to execute transactions.
-	runtime.balances.set_balance(&alice, 100) ;
+	// Print our final runtime.
+	println!("{:#?}", runtime) ;
+}
 
-	// Instantiating first block and executing extrinsics.
-	let block_1 = types::Block{
-		header: support::Header{
-			block_number: 1,
-		},
-		extrinsics: vec![
-			support::Extrinsic{
+#[cfg(test)]
+mod tests {
+	use super::* ;
+
+	#[test]
+	fn replay_protection() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+
+		let transfer = |nonce| support::Extrinsic {
+			caller: alice.clone(),
+			call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+			nonce,
+		}.sign() ;
+
+		// An extrinsic with the correct nonce, a stale nonce, and a future nonce. Only the first one
+		// is executed, and only it increments the nonce of "alice" and is charged a fee.
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![transfer(0), transfer(0), transfer(5)]) ;
+		assert_eq!(
+			runtime.execute_block(block),
+			Ok(vec![Ok(()), Err(support::DispatchError::InvalidNonce), Err(support::DispatchError::InvalidNonce)])
+		) ;
+
+		assert_eq!(runtime.system.nonce(&alice), 1) ;
+		assert_eq!(runtime.balances.balance(&alice), 89) ;
+		assert_eq!(runtime.balances.balance(&bob), 10) ;
+	}
+
+	#[test]
+	fn extrinsic_signatures() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+
+		let transfer = |nonce| support::Extrinsic {
+			caller: alice.clone(),
+			call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+			nonce,
+		}.sign() ;
+		assert!(transfer(0).verify()) ;
+
+		// "bob" cannot sign on behalf of "alice", and a signature cannot be reused for another nonce.
+		let mut forged = transfer(0) ;
+		let payload = support::signing_payload(&forged.call, &forged.nonce) ;
+		forged.signature = support::Signature::sign(&bob, &payload) ;
+		assert!(!forged.verify()) ;
+		let mut replayed = transfer(1) ;
+		replayed.signature = transfer(0).signature ;
+		assert!(!replayed.verify()) ;
+
+		// Neither of them is executed, so they do not increment the nonce of "alice" nor charge a fee.
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![forged, replayed, transfer(0)]) ;
+		assert_eq!(
+			runtime.execute_block(block),
+			Ok(vec![Err(support::DispatchError::InvalidSignature), Err(support::DispatchError::InvalidSignature), Ok(())])
+		) ;
+		assert_eq!(runtime.system.nonce(&alice), 1) ;
+		assert_eq!(runtime.balances.balance(&alice), 89) ;
+		assert_eq!(runtime.balances.balance(&bob), 10) ;
+	}
+
+	#[cfg(feature = "history")]
+	#[test]
+	fn extrinsic_history() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+		runtime.balances.mint(&bob, 100).expect("Mint failed.") ;
+
+		let transfer = |caller: &String, nonce| support::Extrinsic {
+			caller: caller.clone(),
+			call: RuntimeCall::balances(balances::Call::transfer { to: "charlie".to_string(), amount: 10 }),
+			nonce,
+		}.sign() ;
+		// Alice submits extrinsics in blocks 1 and 3, but not in block 2. An extrinsic with an invalid
+		// nonce does not count, since it does not increment her nonce.
+		let blocks = [vec![transfer(&alice, 0)], vec![transfer(&bob, 0), transfer(&alice, 5)], vec![transfer(&alice, 1)]] ;
+		for (number, extrinsics) in (1..).zip(blocks) {
+			let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), number, number.into(), extrinsics) ;
+			runtime.execute_block(block).expect("Invalid block.") ;
+		}
+		assert_eq!(runtime.system.extrinsic_blocks(&alice), &[1, 3]) ;
+		assert_eq!(runtime.system.extrinsic_blocks(&bob), &[2]) ;
+	}
+
+	#[test]
+	fn transaction_pool() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+		let mut pool = pool::TransactionPool::<Runtime, RuntimeCall, support::Signature>::new() ;
+
+		let transfer = |nonce| support::Extrinsic {
+			caller: alice.clone(),
+			call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+			nonce,
+		}.sign() ;
+		for nonce in [1, 0, 3] {
+			assert_eq!(pool.submit(&runtime.system, transfer(nonce)), Ok(())) ;
+		}
+
+		// The ready extrinsics come out in nonce order, so every one of them is executed.
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, pool.ready(&runtime.system)) ;
+		assert_eq!(runtime.execute_block(block), Ok(vec![Ok(()), Ok(())])) ;
+		assert_eq!(runtime.balances.balance(&bob), 20) ;
+
+		// The extrinsic with nonce 3 waits until the one with nonce 2 shows up.
+		assert_eq!(pool.len(), 1) ;
+		assert_eq!(pool.submit(&runtime.system, transfer(2)), Ok(())) ;
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 2, 2, pool.ready(&runtime.system)) ;
+		assert_eq!(runtime.execute_block(block), Ok(vec![Ok(()), Ok(())])) ;
+		assert_eq!(runtime.balances.balance(&bob), 40) ;
+		assert!(pool.is_empty()) ;
+	}
+
+	#[test]
+	fn dispatch_routes_calls() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+		let snapshot = runtime.snapshot() ;
+
+		// Every call is routed to the pallet named by its variant, and only that pallet is modified, apart
+		// from the claim deposit reserved in balances.
+		let transfer = RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }) ;
+		assert!(runtime.dispatch(support::Origin::Signed(alice.clone()), transfer).is_ok()) ;
+		assert_eq!(runtime.balances.balance(&bob), 10) ;
+		assert_eq!(runtime.proof_of_existence, snapshot.0.proof_of_existence) ;
+
+		let claim = RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: "Hello".to_string() }) ;
+		assert!(runtime.dispatch(support::Origin::Signed(bob.clone()), claim).is_ok()) ;
+		assert_eq!(runtime.proof_of_existence.get_claim(&"Hello".to_string()), Some(&bob)) ;
+		assert_eq!(runtime.balances.balance(&bob), 10 - 1) ;
+		assert_eq!(runtime.balances.reserved_balance(&bob), 1) ;
+
+		// Errors of the pallet are passed on by the runtime.
+		let claim = RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: "Hello".to_string() }) ;
+		assert_eq!(runtime.dispatch(support::Origin::Signed(alice.clone()), claim), Err(support::DispatchError::AlreadyClaimed)) ;
+	}
+
+	#[test]
+	fn dispatch_reports_pallet() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+
+		// A dispatched call reports the name of the pallet which handled it.
+		let transfer = RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }) ;
+		assert_eq!(runtime.dispatch_named(support::Origin::Signed(alice.clone()), transfer), Ok("balances")) ;
+		let claim = RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: "Hello".to_string() }) ;
+		assert_eq!(runtime.dispatch_named(support::Origin::Signed(bob.clone()), claim), Ok("proof_of_existence")) ;
+
+		// Failed calls report their error instead.
+		let claim = RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: "Hello".to_string() }) ;
+		assert_eq!(runtime.dispatch_named(support::Origin::Signed(alice.clone()), claim), Err(support::DispatchError::AlreadyClaimed)) ;
+	}
+
+	#[test]
+	fn origins() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+		let transfer = || RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }) ;
+		let force_transfer = || RuntimeCall::balances(balances::Call::force_transfer {
+			from: alice.clone(),
+			to: bob.clone(),
+			amount: 20,
+		}) ;
+
+		// Calls made by an account must be signed by it.
+		assert!(runtime.dispatch(support::Origin::Signed(alice.clone()), transfer()).is_ok()) ;
+		assert_eq!(runtime.dispatch(support::Origin::Root, transfer()), Err(support::DispatchError::BadOrigin)) ;
+		assert_eq!(runtime.dispatch(support::Origin::None, transfer()), Err(support::DispatchError::BadOrigin)) ;
+		assert_eq!(runtime.balances.balance(&bob), 10) ;
+
+		// Privileged calls reject every origin but root, including the pallets dispatched with a context.
+		assert_eq!(runtime.dispatch(support::Origin::Signed(alice.clone()), force_transfer()), Err(support::DispatchError::BadOrigin)) ;
+		let spend = RuntimeCall::treasury(treasury::Call::spend { to: bob.clone(), amount: 1 }) ;
+		assert_eq!(runtime.dispatch(support::Origin::Signed(alice.clone()), spend), Err(support::DispatchError::BadOrigin)) ;
+		assert!(runtime.dispatch(support::Origin::Root, force_transfer()).is_ok()) ;
+		assert_eq!(runtime.balances.balance(&alice), 70) ;
+		assert_eq!(runtime.balances.balance(&bob), 30) ;
+
+		// Extrinsics are always signed, so they cannot make privileged calls.
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![support::Extrinsic {
+			caller: alice.clone(),
+			call: force_transfer(),
+			nonce: 0,
+		}.sign()]) ;
+		assert_eq!(runtime.execute_block(block), Ok(vec![Err(support::DispatchError::BadOrigin)])) ;
+		assert_eq!(runtime.balances.balance(&bob), 30) ;
+	}
+
+	#[test]
+	fn extrinsics_root() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+		let transfer = |amount, nonce| support::Extrinsic {
+			caller: alice.clone(),
+			call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount }),
+			nonce,
+		}.sign() ;
+
+		// The root commits to every extrinsic and to their order.
+		let root = |extrinsics: &[types::Extrinsic]| support::compute_extrinsics_root::<types::Hashing, _>(extrinsics) ;
+		assert_ne!(root(&[]), root(&[transfer(10, 0)])) ;
+		assert_ne!(root(&[transfer(10, 0), transfer(20, 1)]), root(&[transfer(20, 1), transfer(10, 0)])) ;
+		assert_ne!(root(&[transfer(10, 0), transfer(20, 1)]), root(&[transfer(10, 0), transfer(21, 1)])) ;
+
+		// A block whose extrinsic was tampered with after the root was computed is rejected as a
+		// whole, without modifying any state.
+		let mut block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![transfer(10, 0), transfer(20, 1)]) ;
+		block.extrinsics[1] = transfer(80, 1) ;
+		assert_eq!(runtime.execute_block(block), Err(support::DispatchError::InvalidExtrinsicsRoot)) ;
+		assert_eq!(runtime.system.block_number(), 0) ;
+		assert_eq!(runtime.balances.balance(&bob), 0) ;
+
+		// A block with a matching root is executed.
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![transfer(10, 0), transfer(20, 1)]) ;
+		assert_eq!(block.header.extrinsics_root, root(&block.extrinsics)) ;
+		assert_eq!(runtime.execute_block(block), Ok(vec![Ok(()), Ok(())])) ;
+		assert_eq!(runtime.balances.balance(&bob), 30) ;
+	}
+
+	#[test]
+	fn parent_hash() {
+		let mut runtime = Runtime::new() ;
+
+		// The first block is built on top of the zero hash.
+		assert_eq!(runtime.parent_hash(), 0) ;
+		let block_1 = types::Block::new::<types::Hashing>(0, 1, 1, vec![]) ;
+		let hash_1 = block_1.header.hash_with::<types::Hashing>() ;
+		assert_eq!(runtime.execute_block(block_1), Ok(vec![])) ;
+		assert_eq!(runtime.parent_hash(), hash_1) ;
+
+		// The next block must name the block before it as its parent.
+		let block_2 = types::Block::new::<types::Hashing>(hash_1, 2, 2, vec![]) ;
+		let hash_2 = block_2.header.hash_with::<types::Hashing>() ;
+		assert_eq!(runtime.execute_block(block_2), Ok(vec![])) ;
+		assert_eq!(runtime.system.block_hash(2), Some(hash_2)) ;
+
+		// A block on top of any other block is rejected.
+		let snapshot = runtime.snapshot() ;
+		assert_eq!(runtime.validate_block(&types::Block::new::<types::Hashing>(0, 3, 3, vec![])), Err(support::DispatchError::ParentHashMismatch)) ;
+		assert_eq!(runtime.execute_block(types::Block::new::<types::Hashing>(hash_1, 3, 3, vec![])), Err(support::DispatchError::ParentHashMismatch)) ;
+		assert_eq!(runtime.snapshot(), snapshot) ;
+		assert_eq!(runtime.execute_block(types::Block::new::<types::Hashing>(hash_2, 3, 3, vec![])), Ok(vec![])) ;
+	}
+
+	#[test]
+	fn state_root() {
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		let genesis = |amount| Runtime::from_genesis(GenesisConfig {
+			balances: vec![(alice.clone(), amount)],
+			..Default::default()
+		}) ;
+		let block = || types::Block::new::<types::Hashing>(0, 1, 1, vec![support::Extrinsic {
+			caller: alice.clone(),
+			call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 30 }),
+			nonce: 0,
+		}.sign()]) ;
+
+		// Runtimes reaching the same state have the same state root, which is stored once the block
+		// is executed.
+		let mut first = genesis(100) ;
+		let mut second = genesis(100) ;
+		assert_eq!(first.verify_state_root(first.state_root()), Err(support::DispatchError::StateRootMismatch)) ;
+		first.execute_block(block()).expect("Invalid block.") ;
+		second.execute_block(block()).expect("Invalid block.") ;
+		assert_eq!(first.state_root(), second.state_root()) ;
+		assert_eq!(first.system.state_root(), Some(first.state_root())) ;
+		assert_eq!(second.verify_state_root(first.state_root()), Ok(())) ;
+
+		// Events are not part of the state.
+		let _ = first.take_events() ;
+		assert_eq!(first.state_root(), second.state_root()) ;
+
+		// A single differing balance changes the state root.
+		let mut third = genesis(101) ;
+		third.execute_block(block()).expect("Invalid block.") ;
+		assert_ne!(third.state_root(), first.state_root()) ;
+		assert_eq!(third.verify_state_root(first.state_root()), Err(support::DispatchError::StateRootMismatch)) ;
+		second.balances.mint(&bob, 1).expect("Mint failed.") ;
+		assert_ne!(second.state_root(), first.state_root()) ;
+	}
+
+	#[test]
+	fn block_weight_limit() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+
+		let charlie = "charlie".to_string() ;
+		runtime.balances.mint(&charlie, 100).expect("Mint failed.") ;
+		let pay_bob = RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 1 }) ;
+		let transfer = |caller: &String, nonce| support::Extrinsic { caller: caller.clone(), call: pay_bob.clone(), nonce }.sign() ;
+
+		// Every transfer weighs 1, so only the first 20 fit into a block, even when the ones after them
+		// are valid. Since "alice" may only make 20 extrinsics at a time, the others are made by "charlie".
+		let mut extrinsics = (0..20).map(|nonce| transfer(&alice, nonce)).collect::<Vec<_>>() ;
+		extrinsics.extend((0..5).map(|_| transfer(&charlie, 0))) ;
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, extrinsics) ;
+		let results = runtime.execute_block(block).expect("Invalid block.") ;
+
+		// The extrinsics which did not fit are skipped.
+		assert_eq!(results[..20], vec![Ok(()); 20]) ;
+		assert_eq!(results[20..], vec![Err(support::DispatchError::ExhaustsResources); 5]) ;
+		assert_eq!(runtime.system.nonce(&alice), 20) ;
+		assert_eq!(runtime.balances.balance(&bob), 20) ;
+
+		assert_eq!(runtime.system.nonce(&charlie), 0) ;
+
+		// An invalid extrinsic is skipped for being invalid, however much it weighs, and does not fill
+		// the block for the extrinsics after it.
+		let batch = RuntimeCall::utility(utility::Call::batch { calls: vec![pay_bob.clone(); 25] }) ;
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 2, 2, vec![
+			support::Extrinsic { caller: charlie.clone(), call: batch, nonce: 1 }.sign(),
+			transfer(&charlie, 0),
+		]) ;
+		assert_eq!(runtime.execute_block(block), Ok(vec![Err(support::DispatchError::InvalidNonce), Ok(())])) ;
+		assert_eq!(runtime.balances.balance(&bob), 21) ;
+	}
+
+	#[test]
+	fn execution_report() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+		let transfer = |nonce, amount| support::Extrinsic {
+			caller: alice.clone(),
+			call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount }),
+			nonce,
+		}.sign() ;
+
+		// An extrinsic with a wrong nonce is skipped, while a failed transfer is included and charged its
+		// weight, so the block is full after 20 included transfers and the last one is skipped.
+		// The last transfer is made by "bob", since "alice" may only make 20 extrinsics at a time.
+		let mut extrinsics = vec![transfer(0, 1), transfer(5, 1), transfer(1, 1000)] ;
+		extrinsics.extend((2..=19).map(|nonce| transfer(nonce, 1))) ;
+		extrinsics.push(support::Extrinsic {
+			caller: bob.clone(),
+			call: RuntimeCall::balances(balances::Call::transfer { to: alice.clone(), amount: 1 }),
+			nonce: 0,
+		}.sign()) ;
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, extrinsics) ;
+		let report = runtime.execute_block_detailed(block).expect("Invalid block.") ;
+		assert_eq!(report, support::ExecutionReport {
+			included: 20,
+			skipped: 2,
+			total_weight: support::Weight(20),
+			errors: vec![
+				(1, support::DispatchError::InvalidNonce),
+				(2, support::DispatchError::InsufficientFunds),
+				(21, support::DispatchError::ExhaustsResources),
+			],
+		}) ;
+		assert_eq!(runtime.balances.balance(&bob), 19) ;
+
+		// The report tells the same as the results "execute_block" returns.
+		let results = report.results() ;
+		assert_eq!(results.len(), 22) ;
+		assert_eq!(results[1], Err(support::DispatchError::InvalidNonce)) ;
+		assert_eq!(results[3..21], vec![Ok(()); 18]) ;
+	}
+
+	#[test]
+	fn rate_limit() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+		let transfers = |runtime: &Runtime, nonces: core::ops::Range<u32>| {
+			let block_number = runtime.system.block_number() + 1 ;
+			types::Block::new::<types::Hashing>(runtime.parent_hash(), block_number, block_number.into(), nonces.map(|nonce| support::Extrinsic {
 				caller: alice.clone(),
-				call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 30 })
-			},
-			support::Extrinsic{
+				call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 1 }),
+				nonce,
+			}.sign()).collect())
+		} ;
+
+		// After a full block, "alice" cannot submit anything else for the next 9 blocks, and their
+		// nonce is left alone.
+		let block = transfers(&runtime, 0..20) ;
+		assert_eq!(runtime.execute_block(block), Ok(vec![Ok(()); 20])) ;
+		for _ in 2..=10 {
+			let block = transfers(&runtime, 20..21) ;
+			assert_eq!(runtime.execute_block(block), Ok(vec![Err(support::DispatchError::RateLimitExceeded)])) ;
+		}
+		assert_eq!(runtime.system.nonce(&alice), 20) ;
+
+		// Once the full block slides out of the window, "alice" can submit again.
+		let block = transfers(&runtime, 20..21) ;
+		assert_eq!(runtime.execute_block(block), Ok(vec![Ok(())])) ;
+		assert_eq!(runtime.balances.balance(&bob), 21) ;
+	}
+
+	#[test]
+	fn execute_block_results() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![
+			support::Extrinsic {
+				caller: alice.clone(),
+				call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 60 }),
+				nonce: 0,
+			}.sign(),
+			support::Extrinsic {
+				caller: alice.clone(),
+				call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 60 }),
+				nonce: 1,
+			}.sign(),
+			support::Extrinsic {
+				caller: bob.clone(),
+				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: "hello".to_string() }),
+				nonce: 0,
+			}.sign(),
+		]) ;
+
+		// The result of every extrinsic is returned in order.
+		assert_eq!(
+			runtime.execute_block(block),
+			Ok(vec![Ok(()), Err(support::DispatchError::InsufficientFunds), Ok(())])
+		) ;
+
+		// A block with the wrong block number is rejected as a whole.
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 5, 5, vec![]) ;
+		assert_eq!(runtime.execute_block(block), Err(support::DispatchError::InvalidBlockNumber)) ;
+	}
+
+	#[test]
+	fn runtime_events() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![
+			support::Extrinsic {
+				caller: alice.clone(),
+				call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 30 }),
+				nonce: 0,
+			}.sign(),
+			support::Extrinsic {
+				caller: bob.clone(),
+				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: "Hello".to_string() }),
+				nonce: 0,
+			}.sign(),
+			// A failed extrinsic emits no events, other than those of paying its fee.
+			support::Extrinsic {
 				caller: alice.clone(),
-				call: RuntimeCall::balances(balances::Call::transfer { to: charlie, amount: 20 })
-			}],
-	};
+				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: "Hello".to_string() }),
+				nonce: 1,
+			}.sign(),
+			support::Extrinsic {
+				caller: bob.clone(),
+				call: RuntimeCall::balances(balances::Call::transfer { to: alice.clone(), amount: 10 }),
+				nonce: 1,
+			}.sign(),
+		]) ;
+		runtime.execute_block(block).expect("Invalid block.") ;
 
-	// Instantiating second block and executing extrinsics.
-	let block_2 = types::Block {
-		header: support::Header { 
-			block_number: 2 
-		},
-		extrinsics: vec![
+		// The events of all pallets are combined, in the order of the extrinsics emitting them.
+		assert_eq!(
+			runtime.take_events(),
+			vec![
+				RuntimeEvent::treasury(treasury::Event::Deposited { who: alice.clone(), amount: 1 }),
+				RuntimeEvent::balances(balances::Event::Transferred { from: alice.clone(), to: bob.clone(), amount: 30 }),
+				RuntimeEvent::treasury(treasury::Event::Deposited { who: bob.clone(), amount: 1 }),
+				RuntimeEvent::proof_of_existence(proof_of_existence::Event::Created { owner: bob.clone(), claim: "Hello".to_string() }),
+				RuntimeEvent::treasury(treasury::Event::Deposited { who: alice.clone(), amount: 1 }),
+				RuntimeEvent::treasury(treasury::Event::Deposited { who: bob.clone(), amount: 1 }),
+				RuntimeEvent::balances(balances::Event::Transferred { from: bob, to: alice, amount: 10 }),
+			]
+		) ;
+		assert_eq!(runtime.take_events(), vec![]) ;
+	}
+
+	#[test]
+	fn genesis_config() {
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		let runtime = Runtime::from_genesis(GenesisConfig {
+			balances: vec![(alice.clone(), 100), (bob.clone(), 50)],
+			claims: vec![("Hello".to_string(), alice.clone()), ("World".to_string(), bob.clone())],
+		}) ;
+
+		// The seeded state is present before any block is executed.
+		assert_eq!(runtime.system.block_number(), 0) ;
+		// The deposits of the claims are reserved from their owners.
+		assert_eq!(runtime.balances.balance(&alice), 99) ;
+		assert_eq!(runtime.balances.balance(&bob), 49) ;
+		assert_eq!(runtime.balances.total_issuance(), 150) ;
+		assert_eq!(runtime.proof_of_existence.get_claim(&"Hello".to_string()), Some(&alice)) ;
+		assert_eq!(runtime.proof_of_existence.get_claim(&"World".to_string()), Some(&bob)) ;
+	}
+
+	#[test]
+	fn runtime_api() {
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		let mut runtime = Runtime::from_genesis(GenesisConfig {
+			balances: vec![(alice.clone(), 100)],
+			claims: vec![("Hello".to_string(), alice.clone())],
+		}) ;
+
+		// The seeded state can be queried before any block is executed.
+		assert_eq!(runtime.query().current_block(), 0) ;
+		assert_eq!(runtime.query().balance_of(&alice), 99) ;
+		assert_eq!(runtime.query().balance_of(&bob), 0) ;
+		assert_eq!(runtime.query().nonce_of(&alice), 0) ;
+		assert_eq!(runtime.query().claim_owner(&"Hello".to_string()), Some(&alice)) ;
+		assert_eq!(runtime.query().claim_owner(&"World".to_string()), None) ;
+
+		// Queries see the state after every block.
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![
 			support::Extrinsic {
 				caller: alice.clone(),
-				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: "Hello" })
-			},
+				call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 30 }),
+				nonce: 0,
+			}.sign(),
 			support::Extrinsic {
 				caller: bob.clone(),
-				// This will result into an error as the content "Hello" has already been claimed by 'alice'.
-				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: "Hello" })
-			},
+				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: "World".to_string() }),
+				nonce: 0,
+			}.sign(),
+		]) ;
+		runtime.execute_block(block).expect("Invalid block.") ;
+		let api = runtime.query() ;
+		assert_eq!(api.current_block(), 1) ;
+		assert_eq!(api.balance_of(&alice), 68) ;
+		assert_eq!(api.balance_of(&bob), 28) ;
+		assert_eq!(api.nonce_of(&alice), 1) ;
+		assert_eq!(api.nonce_of(&bob), 1) ;
+		assert_eq!(api.claim_owner(&"World".to_string()), Some(&bob)) ;
+	}
+
+	#[test]
+	fn snapshot_and_restore() {
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		let mut runtime = Runtime::from_genesis(GenesisConfig {
+			balances: vec![(alice.clone(), 100)],
+			claims: vec![("Hello".to_string(), alice.clone())],
+		}) ;
+		let snapshot = runtime.snapshot() ;
+
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![
 			support::Extrinsic {
-				caller: alice,
-				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim { claim: "Hello" })
-			},
+				caller: alice.clone(),
+				call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 30 }),
+				nonce: 0,
+			}.sign(),
 			support::Extrinsic {
-				caller: bob,
-				// Since, 'alice' has revoked her claim, 'bob' can now claim the content, "Hello".
- 				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim { claim: "Hello" })
-			}]
-	} ;
+				caller: alice.clone(),
+				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim { claim: "Hello".to_string() }),
+				nonce: 1,
+			}.sign(),
+		]) ;
+		runtime.execute_block(block).expect("Invalid block.") ;
+		assert_eq!(runtime.balances.balance(&bob), 30) ;
+		assert_ne!(runtime.snapshot(), snapshot) ;
 
-	// Executing blocks.
-	runtime.execute_block(block_1).expect("Invalid block.") ;
-	runtime.execute_block(block_2).expect("Invalid block.") ;
+		// Restoring rolls back the storage of every pallet, including the block number and nonces.
+		runtime.restore(snapshot.clone()) ;
+		assert_eq!(runtime.snapshot(), snapshot) ;
+		assert_eq!(runtime.system.block_number(), 0) ;
+		assert_eq!(runtime.system.nonce(&alice), 0) ;
+		assert_eq!(runtime.balances.balance(&alice), 99) ;
+		assert_eq!(runtime.proof_of_existence.get_claim(&"Hello".to_string()), Some(&alice)) ;
+	}
 
-	// Print our final runtime.
-	println!("{:#?}", runtime) ;
+	#[test]
+	fn build_block() {
+		let mut runtime = Runtime::new() ;
+		for who in ["alice", "bob", "charlie", "dave"] {
+			runtime.balances.mint(&who.to_string(), 100).expect("Mint failed.") ;
+		}
+		let extrinsic = |caller: &str, nonce, call| support::Extrinsic { caller: caller.to_string(), call, nonce }.sign() ;
+		// Claims weigh 5, while transfers weigh 1.
+		let claim = |claim: &str| RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: claim.to_string() }) ;
+		let transfer = || RuntimeCall::balances(balances::Call::transfer { to: "eve".to_string(), amount: 10 }) ;
+		let mut forged = extrinsic("dave", 0, transfer()) ;
+		forged.caller = "alice".to_string() ;
+		let pool = vec![
+			extrinsic("alice", 1, transfer()),
+			extrinsic("alice", 0, claim("Hello")),
+			extrinsic("bob", 0, transfer()),
+			extrinsic("charlie", 0, claim("World")),
+			extrinsic("bob", 1, transfer()),
+			// A gap in the nonces of "dave", and an extrinsic not signed by its caller, are left out.
+			extrinsic("dave", 1, transfer()),
+			forged,
+		] ;
+		let picked = |runtime: &Runtime, max_weight| runtime
+			.build_block(&pool, support::Weight(max_weight))
+			.expect("Invalid block.")
+			.extrinsics
+			.iter()
+			.map(|extrinsic| (extrinsic.caller.clone(), extrinsic.nonce))
+			.collect::<Vec<_>>() ;
+		let picks = |picks: &[(&str, u32)]| picks.iter().map(|(who, nonce)| (who.to_string(), *nonce)).collect::<Vec<_>>() ;
+
+		// The lightest extrinsics come first, but never before an earlier nonce of the same caller.
+		assert_eq!(picked(&runtime, 100), picks(&[("bob", 0), ("bob", 1), ("alice", 0), ("alice", 1), ("charlie", 0)])) ;
+		// An extrinsic which does not fit keeps out the later extrinsics of its caller.
+		assert_eq!(picked(&runtime, 7), picks(&[("bob", 0), ("bob", 1), ("alice", 0)])) ;
+		assert_eq!(picked(&runtime, 1), picks(&[("bob", 0)])) ;
+		assert_eq!(picked(&runtime, 0), picks(&[])) ;
+
+		// The block can be executed right away.
+		let block = runtime.build_block(&pool, support::Weight(7)).expect("Invalid block.") ;
+		assert_eq!(runtime.execute_block(block), Ok(vec![Ok(()), Ok(()), Ok(())])) ;
+		assert_eq!(picked(&runtime, 100), picks(&[("alice", 1), ("charlie", 0)])) ;
+
+		// No block can be built once the timestamp would overflow.
+		runtime.system.set_timestamp(types::Moment::MAX) ;
+		assert_eq!(runtime.build_block(&pool, support::Weight(7)).err(), Some(support::DispatchError::TimestampOverflow)) ;
+	}
+
+	#[test]
+	fn execute_blocks() {
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		let transfer = |nonce| support::Extrinsic {
+			caller: alice.clone(),
+			call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+			nonce,
+		}.sign() ;
+		// A chain of blocks, each naming the one before it as its parent.
+		let chain = |numbers: &[u32]| {
+			let mut parent_hash = 0 ;
+			numbers.iter().zip(0..).map(|(number, nonce)| {
+				let block = types::Block::new::<types::Hashing>(parent_hash, *number, nonce as u64 + 1, vec![transfer(nonce)]) ;
+				parent_hash = block.header.hash_with::<types::Hashing>() ;
+				block
+			}).collect::<Vec<_>>()
+		} ;
+
+		let mut runtime = Runtime::new() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+		assert_eq!(runtime.execute_blocks(chain(&[1, 2, 3])), Ok(())) ;
+		assert_eq!(runtime.system.block_number(), 3) ;
+		assert_eq!(runtime.balances.balance(&bob), 30) ;
+
+		// The blocks before the invalid one stay executed, and the ones after it are not.
+		let mut runtime = Runtime::new() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+		assert_eq!(
+			runtime.execute_blocks(chain(&[1, 3, 4])),
+			Err((1, support::DispatchError::InvalidBlockNumber))
+		) ;
+		assert_eq!(runtime.system.block_number(), 1) ;
+		assert_eq!(runtime.balances.balance(&bob), 10) ;
+	}
+
+	#[test]
+	fn dry_run_block() {
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		let mut runtime = Runtime::new() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+		let snapshot = runtime.snapshot() ;
+
+		let transfer = |nonce, amount| support::Extrinsic {
+			caller: alice.clone(),
+			call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount }),
+			nonce,
+		}.sign() ;
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![transfer(0, 60), transfer(1, 60), transfer(2, 30)]) ;
+		assert_eq!(runtime.dry_run_block(&block), Ok(vec![
+			Ok(()),
+			Err(support::DispatchError::InsufficientFunds),
+			Ok(()),
+		])) ;
+
+		// Nothing the block did is kept, so it can still be executed for real.
+		assert_eq!(runtime.snapshot(), snapshot) ;
+		assert_eq!(runtime.balances.balance(&alice), 100) ;
+		assert_eq!(runtime.system.nonce(&alice), 0) ;
+		assert_eq!(runtime.system.block_number(), 0) ;
+		assert_eq!(runtime.execute_block(block), Ok(vec![
+			Ok(()),
+			Err(support::DispatchError::InsufficientFunds),
+			Ok(()),
+		])) ;
+		assert_eq!(runtime.balances.balance(&bob), 90) ;
+
+		// An invalid block is rejected like by "execute_block".
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 3, 2, vec![]) ;
+		assert_eq!(runtime.dry_run_block(&block), Err(support::DispatchError::InvalidBlockNumber)) ;
+	}
+
+	#[test]
+	fn events_since() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+
+		let transfer = |nonce, amount| support::Extrinsic {
+			caller: alice.clone(),
+			call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount }),
+			nonce,
+		}.sign() ;
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![transfer(0, 10)]) ;
+		runtime.execute_block(block).expect("Invalid block.") ;
+		// A consumer reads the events of the first block, and remembers where it stopped.
+		let cursor = runtime.next_event_index() ;
+		assert_eq!(runtime.events_since(0), runtime.events()) ;
+
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 2, 2, vec![transfer(1, 20)]) ;
+		runtime.execute_block(block).expect("Invalid block.") ;
+		// The events accumulate across blocks, and the consumer only gets the new ones.
+		let new_events = runtime.events_since(cursor) ;
+		assert_eq!(new_events.last(), Some(&RuntimeEvent::balances(balances::Event::Transferred {
+			from: alice.clone(),
+			to: bob.clone(),
+			amount: 20,
+		}))) ;
+		assert_eq!(runtime.events().len(), cursor + new_events.len()) ;
+		let old_transfer = RuntimeEvent::balances(balances::Event::Transferred { from: alice.clone(), to: bob.clone(), amount: 10 }) ;
+		assert!(runtime.events()[..cursor].contains(&old_transfer)) ;
+		assert!(!new_events.contains(&old_transfer)) ;
+
+		// Pruning the events already read keeps the rest at the same index.
+		let count = runtime.events_since(cursor).len() ;
+		runtime.prune_events(cursor) ;
+		assert_eq!(runtime.events().len(), count) ;
+		assert_eq!(runtime.events_since(cursor), runtime.events()) ;
+
+		// Taking the events, like the REPL does after every command, keeps the cursor valid too.
+		let cursor = runtime.next_event_index() ;
+		assert!(!runtime.take_events().is_empty()) ;
+		assert!(runtime.events_since(cursor).is_empty()) ;
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 3, 3, vec![transfer(2, 30)]) ;
+		runtime.execute_block(block).expect("Invalid block.") ;
+		assert_eq!(runtime.events_since(cursor), runtime.events()) ;
+		assert!(runtime.events_since(cursor).contains(&RuntimeEvent::balances(balances::Event::Transferred {
+			from: alice.clone(),
+			to: bob.clone(),
+			amount: 30,
+		}))) ;
+	}
+
+	#[test]
+	fn undo_last_block() {
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		let mut runtime = Runtime::from_genesis(GenesisConfig {
+			balances: vec![(alice.clone(), 100)],
+			claims: vec![("Hello".to_string(), alice.clone())],
+		}) ;
+		runtime.take_events() ;
+		let snapshot = runtime.snapshot() ;
+
+		let pay_bob = |amount| RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount }) ;
+		let calls = || vec![
+			pay_bob(30),
+			RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim { claim: "Hello".to_string() }),
+			RuntimeCall::staking(staking::Call::bond { amount: 10 }),
+			RuntimeCall::voting(voting::Call::create_proposal { id: 1, description: "Hello".to_string() }),
+			RuntimeCall::voting(voting::Call::vote { id: 1, aye: true }),
+			RuntimeCall::scheduler(scheduler::Call::schedule { at: 3, task: Box::new(pay_bob(5)) }),
+			RuntimeCall::utility(utility::Call::batch_all { calls: vec![pay_bob(10), pay_bob(20)] }),
+		] ;
+		let parent_hash = runtime.parent_hash() ;
+		let block = || types::Block::new::<types::Hashing>(parent_hash, 1, 1, calls()
+			.into_iter()
+			.enumerate()
+			.map(|(nonce, call)| support::Extrinsic { caller: alice.clone(), call, nonce: nonce as u32 }.sign())
+			.collect()) ;
+		let results = runtime.execute_block(block()).expect("Invalid block.") ;
+		assert!(results.iter().all(|res| res.is_ok())) ;
+		assert_eq!(runtime.balances.balance(&bob), 60) ;
+		assert!(!runtime.take_events().is_empty()) ;
+		assert_ne!(runtime.snapshot(), snapshot) ;
+
+		// Undoing the block puts the storage of every pallet back to what it was before the block, so
+		// the same block can be executed again.
+		runtime.undo_last_block() ;
+		assert_eq!(runtime.snapshot(), snapshot) ;
+		assert_eq!(runtime.state_root(), snapshot.0.state_root()) ;
+		assert_eq!(runtime.execute_block(block()), Ok(results)) ;
+		assert_eq!(runtime.balances.balance(&bob), 60) ;
+
+		// Nothing is journaled outside of a block, so undoing again does not touch the state.
+		runtime.undo_last_block() ;
+		runtime.take_events() ;
+		assert_eq!(runtime.snapshot(), snapshot) ;
+		runtime.balances.mint(&bob, 1).expect("Mint failed.") ;
+		runtime.undo_last_block() ;
+		assert_eq!(runtime.balances.balance(&bob), 1) ;
+	}
+
+	#[test]
+	fn validate_block() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+
+		let block = |block_number| types::Block::new::<types::Hashing>(0, block_number, block_number.into(), vec![support::Extrinsic {
+			caller: alice.clone(),
+			call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+			nonce: 0,
+		}.sign()]) ;
+
+		// Only the next block number is valid.
+		assert_eq!(runtime.system.expected_block_number(), 1) ;
+		assert_eq!(runtime.validate_block(&block(0)), Err(support::DispatchError::InvalidBlockNumber)) ;
+		assert_eq!(runtime.validate_block(&block(2)), Err(support::DispatchError::InvalidBlockNumber)) ;
+		assert_eq!(runtime.validate_block(&block(1)), Ok(())) ;
+
+		// An invalid block is rejected before anything is modified.
+		let snapshot = runtime.snapshot() ;
+		assert_eq!(runtime.execute_block(block(2)), Err(support::DispatchError::InvalidBlockNumber)) ;
+		assert_eq!(runtime.snapshot(), snapshot) ;
+		assert_eq!(runtime.system.block_number(), 0) ;
+		assert_eq!(runtime.system.nonce(&alice), 0) ;
+
+		assert_eq!(runtime.execute_block(block(1)), Ok(vec![Ok(())])) ;
+		assert_eq!(runtime.system.nonce(&alice), 1) ;
+
+		// After block 1, block 2 is expected, so block 5 is rejected without touching any nonce.
+		assert_eq!(runtime.system.expected_block_number(), 2) ;
+		let extrinsic = support::Extrinsic {
+			caller: alice.clone(),
+			call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+			nonce: 1,
+		}.sign() ;
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 5, 5, vec![extrinsic]) ;
+		assert_eq!(runtime.execute_block(block), Err(support::DispatchError::InvalidBlockNumber)) ;
+		assert_eq!(runtime.system.block_number(), 1) ;
+		assert_eq!(runtime.system.nonce(&alice), 1) ;
+	}
+
+	#[test]
+	fn block_timestamp() {
+		let mut runtime = Runtime::new() ;
+		let block = |parent_hash, block_number, timestamp| types::Block::new::<types::Hashing>(parent_hash, block_number, timestamp, vec![]) ;
+
+		assert_eq!(runtime.execute_block(block(runtime.parent_hash(), 1, 10)), Ok(vec![])) ;
+		assert_eq!(runtime.system.timestamp(), 10) ;
+
+		// A block which does not move the timestamp forward is rejected.
+		assert_eq!(runtime.validate_block(&block(runtime.parent_hash(), 2, 10)), Err(support::DispatchError::TimestampNotIncreasing)) ;
+		assert_eq!(runtime.execute_block(block(runtime.parent_hash(), 2, 9)), Err(support::DispatchError::TimestampNotIncreasing)) ;
+		assert_eq!(runtime.system.block_number(), 1) ;
+		assert_eq!(runtime.system.timestamp(), 10) ;
+
+		assert_eq!(runtime.execute_block(block(runtime.parent_hash(), 2, 11)), Ok(vec![])) ;
+		assert_eq!(runtime.system.timestamp(), 11) ;
+	}
+
+	#[test]
+	fn account_existence() {
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		let mut runtime = Runtime::from_genesis(GenesisConfig {
+			balances: vec![(alice.clone(), 100)],
+			..Default::default()
+		}) ;
+		assert!(runtime.system.account_exists(&alice)) ;
+		assert!(!runtime.system.account_exists(&bob)) ;
+
+		// Funding an account brings it into existence, and reaping an account removes it again. After
+		// paying the fee, "alice" transfers everything left.
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![support::Extrinsic {
+			caller: alice.clone(),
+			call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 99 }),
+			nonce: 0,
+		}.sign()]) ;
+		assert_eq!(runtime.execute_block(block), Ok(vec![Ok(())])) ;
+		assert!(!runtime.system.account_exists(&alice)) ;
+		assert!(runtime.system.account_exists(&bob)) ;
+	}
+
+	#[test]
+	fn extrinsic_fees() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+
+		let claim = |caller: &String, claim: &str| support::Extrinsic {
+			caller: caller.clone(),
+			call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: claim.to_string() }),
+			nonce: 0,
+		}.sign() ;
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![claim(&alice, "Hello"), claim(&bob, "World")]) ;
+
+		// The fee of "alice" is paid into the treasury before the claim is created.
+		// "bob" cannot pay the fee, so their call is skipped, like an extrinsic with an invalid nonce.
+		assert_eq!(runtime.execute_block(block), Ok(vec![Ok(()), Err(support::DispatchError::CannotPayFee)])) ;
+		assert_eq!(runtime.balances.balance(&alice), 100 - 1 - 1) ;
+		assert_eq!(runtime.treasury.pot(), 1) ;
+		assert_eq!(runtime.proof_of_existence.get_claim(&"World".to_string()), None) ;
+		assert_eq!(runtime.system.nonce(&bob), 0) ;
+	}
+
+	#[test]
+	fn log_extrinsic_errors() {
+		// A logger which keeps everything logged to it.
+		#[derive(Default)]
+		struct VecLogger(core::cell::RefCell<Vec<(u32, usize, String, String)>>) ;
+		impl support::Logger for VecLogger {
+			fn log_extrinsic_error(&self, block: u32, index: usize, call: &str, err: &str) {
+				self.0.borrow_mut().push((block, index, call.to_string(), err.to_string())) ;
+			}
+		}
+
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		let logger = std::rc::Rc::new(VecLogger::default()) ;
+		let mut runtime = Runtime::from_genesis(GenesisConfig {
+			balances: vec![(alice.clone(), 100)],
+			..Default::default()
+		}) ;
+		runtime.logger = support::SharedLogger(logger.clone()) ;
+
+		let transfer = |nonce, amount| support::Extrinsic {
+			caller: alice.clone(),
+			call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount }),
+			nonce,
+		}.sign() ;
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![transfer(0, 10), transfer(1, 1000), transfer(2, 10)]) ;
+
+		// A dry run only returns its errors, without logging them.
+		assert_eq!(runtime.dry_run_block(&block), Ok(vec![
+			Ok(()),
+			Err(support::DispatchError::InsufficientFunds),
+			Ok(()),
+		])) ;
+		assert!(logger.0.borrow().is_empty()) ;
+		runtime.execute_block(block).expect("Invalid block.") ;
+
+		// Only the failed extrinsic is logged, along with the name of its call.
+		assert_eq!(
+			*logger.0.borrow(),
+			vec![(1, 1, "balances::transfer".to_string(), "Insufficient funds.".to_string())]
+		) ;
+	}
+
+	#[test]
+	fn stake_through_runtime() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+
+		// Staking calls are given the balances pallet, so bonding reserves balance there.
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![
+			support::Extrinsic {
+				caller: alice.clone(),
+				call: RuntimeCall::staking(staking::Call::bond { amount: 70 }),
+				nonce: 0,
+			}.sign(),
+			support::Extrinsic {
+				caller: alice.clone(),
+				call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 50 }),
+				nonce: 1,
+			}.sign(),
+		]) ;
+		assert_eq!(runtime.execute_block(block), Ok(vec![Ok(()), Err(support::DispatchError::InsufficientFunds)])) ;
+
+		// Both extrinsics paid their fee.
+		assert_eq!(runtime.staking.bonded(&alice), 70) ;
+		assert_eq!(runtime.balances.balance(&alice), 28) ;
+		assert_eq!(runtime.balances.reserved_balance(&alice), 70) ;
+	}
+
+	#[test]
+	fn multisig_dispatch() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		let charlie = "charlie".to_string() ;
+		let fund = "fund".to_string() ;
+		runtime.balances.mint(&fund, 100).expect("Mint failed.") ;
+		runtime.balances.mint(&alice, 10).expect("Mint failed.") ;
+		runtime.balances.mint(&bob, 10).expect("Mint failed.") ;
+		runtime.multisig.create_multisig(fund.clone(), vec![alice.clone(), bob.clone()], 2).expect("Create failed.") ;
+
+		let pay_charlie = RuntimeCall::balances(balances::Call::transfer { to: charlie.clone(), amount: 40 }) ;
+		let approve = |caller: &String| support::Extrinsic {
+			caller: caller.clone(),
+			call: RuntimeCall::multisig(multisig::Call::approve { multisig: fund.clone(), proposal: Box::new(pay_charlie.clone()) }),
+			nonce: 0,
+		}.sign() ;
+
+		// Below the threshold, the call is not made.
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![approve(&alice)]) ;
+		assert_eq!(runtime.execute_block(block), Ok(vec![Ok(())])) ;
+		assert_eq!(runtime.balances.balance(&charlie), 0) ;
+
+		// Once the threshold is reached, the call is made on behalf of the multisig account.
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 2, 2, vec![approve(&bob)]) ;
+		assert_eq!(runtime.execute_block(block), Ok(vec![Ok(())])) ;
+		assert_eq!(runtime.balances.balance(&fund), 60) ;
+		assert_eq!(runtime.balances.balance(&charlie), 40) ;
+		assert_eq!(runtime.system.nonce(&fund), 0) ;
+	}
+
+	#[test]
+	fn scheduled_dispatch() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+
+		let pay_bob = RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 40 }) ;
+		let schedule = |at, nonce| support::Extrinsic {
+			caller: alice.clone(),
+			call: RuntimeCall::scheduler(scheduler::Call::schedule { at, task: Box::new(pay_bob.clone()) }),
+			nonce,
+		}.sign() ;
+		let empty_block = |parent_hash, block_number| types::Block::new::<types::Hashing>(parent_hash, block_number, block_number.into(), vec![]) ;
+
+		// Block 1 schedules the transfer for block 3. It cannot be scheduled for block 1 itself.
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![schedule(1, 0), schedule(3, 1)]) ;
+		assert_eq!(runtime.execute_block(block), Ok(vec![Err(support::DispatchError::ScheduledInPast), Ok(())])) ;
+
+		runtime.execute_block(empty_block(runtime.parent_hash(), 2)).expect("Invalid block.") ;
+		assert_eq!(runtime.balances.balance(&bob), 0) ;
+
+		// The transfer is made exactly when block 3 runs, and only then.
+		runtime.execute_block(empty_block(runtime.parent_hash(), 3)).expect("Invalid block.") ;
+		assert_eq!(runtime.balances.balance(&bob), 40) ;
+		// Only the extrinsics scheduling the transfer paid a fee, not the scheduled transfer itself.
+		runtime.execute_block(empty_block(runtime.parent_hash(), 4)).expect("Invalid block.") ;
+		assert_eq!(runtime.balances.balance(&alice), 58) ;
+		assert_eq!(runtime.balances.balance(&bob), 40) ;
+	}
+
+	#[test]
+	fn batch_dispatch() {
+		let mut runtime = Runtime::new() ;
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		let origin = support::Origin::Signed(alice.clone()) ;
+		runtime.balances.mint(&alice, 100).expect("Mint failed.") ;
+
+		let pay_bob = |amount| RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount }) ;
+		let calls = || vec![pay_bob(10), pay_bob(1000), pay_bob(20)] ;
+
+		// A batch stops at the call which fails midway, keeping the transfer before it. The batch
+		// declares the weight of all of its calls, and the weight of the call it never made is refunded.
+		let batch = RuntimeCall::utility(utility::Call::batch { calls: calls() }) ;
+		let transfer_weight = support::GetWeight::weight(&pay_bob(10)).0 ;
+		assert_eq!(support::GetWeight::weight(&batch), support::Weight(1 + 3 * transfer_weight)) ;
+		assert_eq!(runtime.dispatch(origin.clone(), batch), Ok(support::PostDispatchInfo {
+			actual_weight: support::Weight(1 + 2 * transfer_weight),
+		})) ;
+		assert_eq!(runtime.balances.balance(&bob), 10) ;
+		runtime.collect_events() ;
+		assert_eq!(runtime.take_events().last(), Some(&RuntimeEvent::utility(utility::Event::Interrupted {
+			origin: origin.clone(),
+			succeeded: 1,
+			error: support::DispatchError::InsufficientFunds,
+		}))) ;
+
+		// A forced batch runs every call despite the failure.
+		let force_batch = RuntimeCall::utility(utility::Call::force_batch { calls: calls() }) ;
+		assert!(runtime.dispatch(origin.clone(), force_batch).is_ok()) ;
+		assert_eq!(runtime.balances.balance(&bob), 40) ;
+		runtime.collect_events() ;
+		assert_eq!(runtime.take_events().last(), Some(&RuntimeEvent::utility(utility::Event::CompletedWithErrors {
+			origin: origin.clone(),
+			succeeded: 2,
+			failed: 1,
+		}))) ;
+
+		// An atomic batch fails as a whole, undoing the transfer made before the failure. The accounts
+		// created so far are reported first, like a block does after every extrinsic.
+		runtime.collect_provider_changes() ;
+		let snapshot = runtime.snapshot() ;
+		let batch_all = RuntimeCall::utility(utility::Call::batch_all { calls: calls() }) ;
+		assert_eq!(runtime.dispatch(origin.clone(), batch_all), Err(support::DispatchError::InsufficientFunds)) ;
+		assert_eq!(runtime.snapshot(), snapshot) ;
+		// The events of the transfer which was undone are dropped along with it.
+		runtime.collect_events() ;
+		assert!(runtime.take_events().is_empty()) ;
+		let batch_all = RuntimeCall::utility(utility::Call::batch_all { calls: vec![pay_bob(10), pay_bob(20)] }) ;
+		assert!(runtime.dispatch(origin.clone(), batch_all).is_ok()) ;
+		assert_eq!(runtime.balances.balance(&bob), 70) ;
+		runtime.collect_events() ;
+		assert_eq!(runtime.take_events().last(), Some(&RuntimeEvent::utility(utility::Event::Completed {
+			origin: origin.clone(),
+			succeeded: 2,
+		}))) ;
+
+		// The calls of a batch are made by its origin, so only a root batch can make privileged calls.
+		let force_transfer = RuntimeCall::balances(balances::Call::force_transfer { from: bob.clone(), to: alice.clone(), amount: 70 }) ;
+		let batch = || RuntimeCall::utility(utility::Call::batch { calls: vec![force_transfer.clone()] }) ;
+		assert!(runtime.dispatch(origin.clone(), batch()).is_ok()) ;
+		assert_eq!(runtime.balances.balance(&bob), 70) ;
+		assert!(runtime.dispatch(support::Origin::Root, batch()).is_ok()) ;
+		assert_eq!(runtime.balances.balance(&bob), 0) ;
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn export_and_import_state() {
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		let mut runtime = Runtime::from_genesis(GenesisConfig {
+			balances: vec![(alice.clone(), 100)],
+			claims: vec![("Hello".to_string(), alice.clone())],
+		}) ;
+		let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![
+			support::Extrinsic {
+				caller: alice.clone(),
+				call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 30 }),
+				nonce: 0,
+			}.sign(),
+			support::Extrinsic {
+				caller: alice.clone(),
+				call: RuntimeCall::voting(voting::Call::create_proposal { id: 1, description: "Hello".to_string() }),
+				nonce: 1,
+			}.sign(),
+			support::Extrinsic {
+				caller: bob.clone(),
+				call: RuntimeCall::voting(voting::Call::vote { id: 1, aye: true }),
+				nonce: 0,
+			}.sign(),
+		]) ;
+		runtime.execute_block(block).expect("Invalid block.") ;
+		let _ = runtime.take_events() ;
+		// The vote of "bob" is weighted by the balance left after paying the fee.
+		assert_eq!(runtime.voting.tally(&1), (29, 0)) ;
+		let original = runtime.snapshot() ;
+		let json = runtime.export_state() ;
+
+		// Mutate the state after exporting it.
+		runtime.balances.mint(&bob, 50).expect("Mint failed.") ;
+		runtime.proof_of_existence.revoke_claim(alice.clone(), &mut runtime.balances, "Hello".to_string()).expect("Revoke failed.") ;
+		runtime.system.inc_nonce(&bob) ;
+
+		// Importing restores the exported state exactly.
+		assert_eq!(runtime.import_state(&json), Ok(())) ;
+		assert_eq!(runtime.snapshot(), original) ;
+		assert_eq!(runtime.system.block_number(), 1) ;
+		assert_eq!(runtime.balances.balance(&bob), 29) ;
+		assert_eq!(runtime.proof_of_existence.get_claim(&"Hello".to_string()), Some(&alice)) ;
+
+		// Invalid JSON is rejected, leaving the state untouched.
+		assert!(runtime.import_state("{").is_err()) ;
+		assert_eq!(runtime.snapshot(), original) ;
+	}
+
+	#[test]
+	fn runtime_upgrade() {
+		let mut runtime = Runtime::new() ;
+
+		// A new runtime is stored with the current layout of every pallet, so nothing is migrated.
+		assert_eq!(runtime.system.storage_version("balances"), 1) ;
+		assert_eq!(runtime.system.storage_version("proof_of_existence"), 0) ;
+		assert_eq!(runtime.runtime_upgrade(), support::Weight(0)) ;
+
+		// A pallet stored with an older layout is migrated once, and then stored with the current one.
+		runtime.system.set_storage_version("balances", 0) ;
+		let snapshot = runtime.snapshot() ;
+		runtime.runtime_upgrade() ;
+		assert_ne!(runtime.snapshot(), snapshot) ;
+		assert_eq!(runtime.system.storage_version("balances"), 1) ;
+		let snapshot = runtime.snapshot() ;
+		assert_eq!(runtime.runtime_upgrade(), support::Weight(0)) ;
+		assert_eq!(runtime.snapshot(), snapshot) ;
+	}
+
+	#[test]
+	fn metadata() {
+		let metadata = Runtime::metadata() ;
+		let param = |name, type_name| support::ParamMetadata { name, type_name } ;
+
+		// Every pallet but system is described, with its calls and their parameters.
+		assert_eq!(metadata.pallets[0].name, "balances") ;
+		assert!(metadata.pallets.iter().all(|pallet| pallet.name != "system")) ;
+		let transfer = metadata.call("balances", "transfer").expect("No transfer call.") ;
+		assert_eq!(transfer.params, vec![param("to", "T::AccountId"), param("amount", "T::Balance")]) ;
+		let batch = metadata.call("utility", "batch").expect("No batch call.") ;
+		assert_eq!(batch.params, vec![param("calls", "Vec<T::RuntimeCall>")]) ;
+		assert_eq!(metadata.call("balances", "do_transfer"), None) ;
+	}
+
+	#[test]
+	fn parse_command() {
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		let claim = "hello".to_string() ;
+
+		// Every supported command is turned into its call, made by the first word of the line.
+		let commands = [
+			("alice transfer bob 30", RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 30 })),
+			("alice transfer_keep_alive bob 30", RuntimeCall::balances(balances::Call::transfer_keep_alive { to: bob.clone(), amount: 30 })),
+			("alice create_claim hello", RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: claim.clone() })),
+			("alice revoke_claim hello", RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim { claim: claim.clone() })),
+			(
+				"alice transfer_claim hello bob",
+				RuntimeCall::proof_of_existence(proof_of_existence::Call::transfer_claim { claim: claim.clone(), new_owner: bob.clone() }),
+			),
+			("alice bond 10", RuntimeCall::staking(staking::Call::bond { amount: 10 })),
+			("  alice   unbond\t10 ", RuntimeCall::staking(staking::Call::unbond { amount: 10 })),
+		] ;
+		for (line, call) in commands {
+			assert_eq!(super::parse_command(line), Ok((alice.clone(), call))) ;
+		}
+
+		// Malformed lines are rejected with the reason.
+		assert_eq!(super::parse_command(""), Err("Missing caller.".to_string())) ;
+		assert_eq!(super::parse_command("alice"), Err("Missing command.".to_string())) ;
+		assert_eq!(super::parse_command("alice mint 30"), Err("Unknown command \"mint\".".to_string())) ;
+		assert_eq!(
+			super::parse_command("alice transfer bob"),
+			Err("The command \"transfer\" takes 2 arguments, but 1 were given.".to_string()),
+		) ;
+		assert_eq!(
+			super::parse_command("alice create_claim hello world"),
+			Err("The command \"create_claim\" takes 1 arguments, but 2 were given.".to_string()),
+		) ;
+		assert_eq!(super::parse_command("alice transfer bob -5"), Err("Invalid amount \"-5\".".to_string())) ;
+		assert_eq!(super::parse_command("alice bond lots"), Err("Invalid amount \"lots\".".to_string())) ;
+	}
+
+	#[test]
+	fn execute_command() {
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		let mut runtime = Runtime::from_genesis(GenesisConfig { balances: vec![(alice.clone(), 100)], claims: vec![] }) ;
+
+		// Every command is executed in a block of its own, with the next nonce of its caller.
+		assert_eq!(runtime.execute_command("alice transfer bob 30"), Ok(Ok(()))) ;
+		assert_eq!(runtime.execute_command("alice create_claim hello"), Ok(Ok(()))) ;
+		assert_eq!(runtime.system.block_number(), 2) ;
+		assert_eq!(runtime.system.nonce(&alice), 2) ;
+		assert_eq!(runtime.balances.balance(&bob), 30) ;
+		assert_eq!(runtime.proof_of_existence.get_claim(&"hello".to_string()), Some(&alice)) ;
+
+		// A failing call still goes into a block, while a malformed command does not.
+		assert_eq!(runtime.execute_command("bob create_claim hello"), Ok(Err(support::DispatchError::AlreadyClaimed))) ;
+		assert_eq!(runtime.system.block_number(), 3) ;
+		assert!(runtime.execute_command("bob transfer alice").is_err()) ;
+		assert_eq!(runtime.system.block_number(), 3) ;
+
+		// An overflowing timestamp is reported rather than panicking.
+		runtime.system.set_timestamp(types::Moment::MAX) ;
+		assert_eq!(runtime.execute_command("alice transfer bob 10"), Err("Timestamp overflow.".to_string())) ;
+		assert_eq!(runtime.system.block_number(), 3) ;
+	}
+
+	#[test]
+	fn run_repl() {
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		let mut runtime = Runtime::from_genesis(GenesisConfig { balances: vec![(alice.clone(), 100)], claims: vec![] }) ;
+		let script = "alice transfer bob 30\n\nbob create_claim hello\nalice create_claim hello\nalice fly\nbob bond 10\nquit\nalice transfer bob 30\n" ;
+		let mut output = Vec::new() ;
+		assert!(super::run_repl(&mut runtime, script.as_bytes(), &mut output).is_ok()) ;
+
+		// Every command made a block, except the malformed one and the ones after "quit".
+		assert_eq!(runtime.system.block_number(), 4) ;
+		assert_eq!(runtime.balances.balance(&bob), 30 - 1 - 1 - 1 - 10) ;
+		assert_eq!(runtime.staking.bonded(&bob), 10) ;
+		assert_eq!(runtime.proof_of_existence.get_claim(&"hello".to_string()), Some(&bob)) ;
+
+		// The result of every command is reported, followed by the events of its block.
+		let output = String::from_utf8(output).expect("Invalid output.") ;
+		let results = output.lines().filter(|line| !line.starts_with('\t')).collect::<Vec<_>>() ;
+		assert_eq!(results, vec![
+			"Block 1: Ok.",
+			"Block 2: Ok.",
+			"Block 3: Error: This content is already been claimed.",
+			"Error: Unknown command \"fly\".",
+			"Block 4: Ok.",
+		]) ;
+		assert!(output.lines().any(|line| line.starts_with("\tbalances(Transferred"))) ;
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn import_old_state() {
+		let alice = "alice".to_string() ;
+		let dave = "dave".to_string() ;
+		let mut runtime = Runtime::new() ;
+
+		// A state exported before storage versions and providers, whose balances only stored the
+		// free balance of every account.
+		let state = r#"{
+			"system": { "block_number": 0, "nonce": {}, "block_hash": {}, "timestamp": 0, "state_root": null },
+			"balances": { "balances": { "alice": 100, "dave": 50 }, "total_issuance": 150, "allowances": {}, "locks": {}, "frozen": [] },
+			"proof_of_existence": { "claims": {}, "claim_count": {}, "expiry": {}, "history": {}, "block_number": 0 },
+			"staking": { "bonded": {} },
+			"voting": { "proposals": {}, "votes": [] },
+			"treasury": { "pot": 0 },
+			"nft": { "items": [] },
+			"multisig": { "multisigs": {}, "pending": [] },
+			"scheduler": { "agenda": {}, "block_number": 0 },
+			"vesting": { "schedules": {}, "block_number": 0 },
+			"utility": {}
+		}"# ;
+
+		// Importing it migrates every free balance into account data, creating the account, exactly once.
+		assert_eq!(runtime.import_state(state), Ok(())) ;
+		assert_eq!(runtime.balances.balance(&alice), 100) ;
+		assert_eq!(runtime.balances.balance(&dave), 50) ;
+		assert_eq!(runtime.balances.reserved_balance(&dave), 0) ;
+		assert_eq!(runtime.balances.total_issuance(), 150) ;
+		assert!(runtime.system.account_exists(&alice)) ;
+		assert!(runtime.system.account_exists(&dave)) ;
+		assert_eq!(runtime.system.storage_version("balances"), 1) ;
+		assert_eq!(runtime.runtime_upgrade(), support::Weight(0)) ;
+		assert_eq!(runtime.balances.balance(&dave), 50) ;
+
+		// The state is exported in the current layout afterwards.
+		let exported: serde_json::Value = serde_json::from_str(&runtime.export_state()).expect("Invalid JSON.") ;
+		assert_eq!(exported["balances"]["balances"]["dave"], serde_json::json!({ "free": 50, "reserved": 0 })) ;
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn execute_block_json() {
+		let alice = "alice".to_string() ;
+		let bob = "bob".to_string() ;
+		let mut runtime = Runtime::from_genesis(GenesisConfig {
+			balances: vec![(alice.clone(), 100)],
+			..Default::default()
+		}) ;
+
+		// The header must commit to the extrinsics, and each of them must be signed by its caller, so
+		// the root and the signatures are computed from the same extrinsics.
+		let extrinsics: [types::Extrinsic; 2] = [
+			support::Extrinsic {
+				caller: alice.clone(),
+				call: RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 30 }),
+				nonce: 0,
+			}.sign(),
+			support::Extrinsic {
+				caller: bob.clone(),
+				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: "Hello".to_string() }),
+				nonce: 0,
+			}.sign(),
+		] ;
+		let block = r#"{
+			"header": { "parent_hash": 0, "block_number": 1, "timestamp": 1, "extrinsics_root": ROOT },
+			"extrinsics": [
+				{
+					"caller": "alice",
+					"call": { "pallet": "balances", "call": "transfer", "to": "bob", "amount": 30 },
+					"nonce": 0,
+					"signature": ALICE
+				},
+				{
+					"caller": "bob",
+					"call": { "pallet": "proof_of_existence", "call": "create_claim", "claim": "Hello" },
+					"nonce": 0,
+					"signature": BOB
+				}
+			]
+		}"#
+			.replace("ROOT", &support::compute_extrinsics_root::<types::Hashing, _>(&extrinsics).to_string())
+			.replace("ALICE", &extrinsics[0].signature.0.to_string())
+			.replace("BOB", &extrinsics[1].signature.0.to_string()) ;
+		// Both extrinsics paid their fee, and "bob" the deposit of their claim.
+		assert_eq!(runtime.execute_block_json(&block), Ok(())) ;
+		assert_eq!(runtime.balances.balance(&alice), 69) ;
+		assert_eq!(runtime.balances.balance(&bob), 28) ;
+		assert_eq!(runtime.proof_of_existence.get_claim(&"Hello".to_string()), Some(&bob)) ;
+
+		// Unknown calls are rejected with an error naming them, and nothing is executed.
+		let block = r#"{
+			"header": { "parent_hash": 0, "block_number": 2, "timestamp": 2, "extrinsics_root": 0 },
+			"extrinsics": [
+				{
+					"caller": "alice",
+					"call": { "pallet": "balances", "call": "transferr", "to": "bob", "amount": 30 },
+					"nonce": 1,
+					"signature": 0
+				}
+			]
+		}"# ;
+		let err = runtime.execute_block_json(block).unwrap_err() ;
+		assert!(err.contains("unknown variant `transferr`"), "{}", err) ;
+		assert_eq!(runtime.system.block_number(), 1) ;
+
+		// As are unknown pallets.
+		let block = r#"{
+			"header": { "parent_hash": 0, "block_number": 2, "timestamp": 2, "extrinsics_root": 0 },
+			"extrinsics": [{ "caller": "alice", "call": { "pallet": "lottery", "call": "enter" }, "nonce": 1, "signature": 0 }]
+		}"# ;
+		let err = runtime.execute_block_json(block).unwrap_err() ;
+		assert!(err.contains("unknown variant `lottery`"), "{}", err) ;
+	}
+
+	// A runtime with a single pallet, which counts the hooks called on it.
+	mod hooks {
+		use crate::{support, system} ;
+		use crate::support::Dispatch ;
+
+		mod types {
+			// This runtime hashes with another hasher than the main runtime, to check that blocks are
+			// hashed with the hasher of the runtime executing them.
+			pub struct Hashing ;
+			impl crate::support::Hasher for Hashing {
+				type Output = u64 ;
+				// The djb2 hash function.
+				fn hash(data: &[u8]) -> u64 {
+					data.iter().fold(5381, |hash: u64, byte| hash.wrapping_mul(33) ^ *byte as u64)
+				}
+			}
+			pub type Extrinsic = crate::support::SignedExtrinsic<String, super::RuntimeCall, u32, crate::support::Signature> ;
+			pub type Block = crate::support::Block<crate::support::Header<u32, u64, u64>, Extrinsic> ;
+		}
+
+		mod counter {
+			pub trait Config: crate::system::Config<AccountId = String> {}
+
+			#[derive(Debug, Clone, PartialEq)]
+			pub enum Event<T: Config> {
+				Pinged { by: T::AccountId, who: T::AccountId },
+			}
+
+			#[derive(Debug, Clone, PartialEq, macros::Encode)]
+			pub struct Pallet<T: Config> {
+				pub initialized: u32,
+				pub finalized: u32,
+				#[encode(skip)]
+				events: Vec<Event<T>>,
+			}
+
+			impl<T: Config> Pallet<T> {
+				pub fn new() -> Self {
+					Self { initialized: 0, finalized: 0, events: Vec::new() }
+				}
+
+				pub fn take_events(&mut self) -> Vec<Event<T>> {
+					core::mem::take(&mut self.events)
+				}
+			}
+
+			impl<T: Config> crate::support::Journaled for Pallet<T> {}
+
+			impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {
+				fn on_initialize(&mut self, _block_number: T::BlockNumber) {
+					self.initialized += 1 ;
+				}
+
+				fn on_finalize(&mut self, _block_number: T::BlockNumber) {
+					// Every extrinsic in the block has been executed by now.
+					assert_eq!(self.initialized, self.finalized + 1) ;
+					self.finalized += 1 ;
+				}
+			}
+
+			#[macros::call]
+			impl<T: Config> Pallet<T> {
+				pub fn ping(&mut self, caller: T::AccountId, who: T::AccountId) -> crate::support::DispatchResult {
+					self.events.push(Event::Pinged { by: caller, who }) ;
+					Ok(())
+				}
+			}
+		}
+
+		#[macros::runtime]
+		#[derive(Debug, Clone, PartialEq)]
+		pub struct Runtime {
+			system: system::Pallet<Self>,
+			counter: counter::Pallet<Self>,
+		}
+
+		impl system::Config for Runtime {
+			type AccountId = String ;
+			type BlockNumber = u32 ;
+			type Nonce = u32 ;
+			type Hash = u64 ;
+			type Hashing = types::Hashing ;
+			type Moment = u64 ;
+			const BLOCK_HASH_COUNT: u32 = 256 ;
+			const MAX_BLOCK_WEIGHT: support::Weight = support::Weight(20) ;
+			type RuntimeEvent = RuntimeEvent ;
+		}
+
+		impl counter::Config for Runtime {}
+
+		#[test]
+		fn count_hooks() {
+			let mut runtime = Runtime::new() ;
+
+			// The hooks are called once per block, around the extrinsics.
+			for block_number in 1..=3 {
+				let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), block_number, block_number.into(), vec![support::Extrinsic {
+					caller: "alice".to_string(),
+					call: RuntimeCall::counter(counter::Call::ping { who: "bob".to_string() }),
+					nonce: block_number - 1,
+				}.sign()]) ;
+				assert_eq!(runtime.execute_block(block), Ok(vec![Ok(())])) ;
+			}
+			assert_eq!(runtime.counter.initialized, 3) ;
+			assert_eq!(runtime.counter.finalized, 3) ;
+			assert_eq!(runtime.take_events().len(), 3) ;
+
+			// No hooks are called for an invalid block.
+			let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 5, 5, vec![]) ;
+			assert!(runtime.execute_block(block).is_err()) ;
+			assert_eq!(runtime.counter.initialized, 3) ;
+		}
+
+		#[test]
+		fn hash_with_configured_hasher() {
+			use crate::support::Hasher ;
+
+			let mut runtime = Runtime::new() ;
+			let ping = support::Extrinsic {
+				caller: "alice".to_string(),
+				call: RuntimeCall::counter(counter::Call::ping { who: "bob".to_string() }),
+				nonce: 0,
+			}.sign() ;
+
+			// A block committing to its extrinsics with "Fnv" is rejected by this runtime.
+			let block = types::Block::new::<support::Fnv>(runtime.parent_hash(), 1, 1, vec![ping.clone()]) ;
+			assert_eq!(runtime.validate_block(&block), Err(support::DispatchError::InvalidExtrinsicsRoot)) ;
+
+			// The extrinsics root, the block hash and the state root are all computed with its hasher.
+			let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, vec![ping]) ;
+			let hash = block.header.hash_with::<types::Hashing>() ;
+			assert_ne!(hash, block.header.hash_with::<support::Fnv>()) ;
+			assert_eq!(runtime.execute_block(block), Ok(vec![Ok(())])) ;
+			assert_eq!(runtime.parent_hash(), hash) ;
+			let mut storage = support::Encode::encode(&runtime.system) ;
+			storage.extend(support::Encode::encode(&runtime.counter)) ;
+			assert_eq!(runtime.state_root(), types::Hashing::hash(&storage)) ;
+		}
+	}
+
+	// A runtime with a single pallet, whose calls use up less weight than they declare.
+	mod weights {
+		use crate::{support, system} ;
+		use crate::support::Dispatch ;
+
+		mod types {
+			pub type Hashing = crate::support::Fnv ;
+			pub type Extrinsic = crate::support::SignedExtrinsic<String, super::RuntimeCall, u32, crate::support::Signature> ;
+			pub type Block = crate::support::Block<crate::support::Header<u32, u64, u64>, Extrinsic> ;
+		}
+
+		mod refund {
+			use crate::support::{PostDispatchInfo, Weight} ;
+
+			pub trait Config: crate::system::Config {}
+
+			#[derive(Debug, Clone, PartialEq)]
+			pub enum Event<T: Config> {
+				Refunded { who: T::AccountId },
+			}
+
+			#[derive(Debug, Clone, PartialEq, macros::Encode)]
+			pub struct Pallet<T: Config> {
+				#[encode(skip)]
+				events: Vec<Event<T>>,
+			}
+
+			impl<T: Config> Pallet<T> {
+				pub fn new() -> Self {
+					Self { events: Vec::new() }
+				}
+
+				pub fn take_events(&mut self) -> Vec<Event<T>> {
+					core::mem::take(&mut self.events)
+				}
+			}
+
+			impl<T: Config> crate::support::Journaled for Pallet<T> {}
+
+			impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {}
+
+			#[allow(non_camel_case_types)]
+			#[derive(Debug, Clone, PartialEq)]
+			#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+			pub enum Call<T: Config> {
+				#[cfg_attr(feature = "serde", serde(skip))]
+				_Marker(core::marker::PhantomData<T>),
+				refund,
+			}
+
+			impl<T: Config> Call<T> {
+				pub fn name(&self) -> &'static str {
+					"refund"
+				}
+
+				pub fn metadata() -> Vec<crate::support::CallMetadata> {
+					vec![crate::support::CallMetadata { name: "refund", params: vec![] }]
+				}
+			}
+
+			impl<T: Config> crate::support::Encode for Call<T> {
+				fn encode_to(&self, dest: &mut Vec<u8>) {
+					dest.push(0) ;
+				}
+			}
+
+			// Every call declares the worst case weight of 5, but only ever uses up 1.
+			impl<T: Config> crate::support::GetWeight for Call<T> {
+				fn weight(&self) -> Weight {
+					Weight(5)
+				}
+			}
+
+			impl<T: Config> crate::support::Dispatch for Pallet<T> {
+				type Caller = crate::support::Origin<T::AccountId> ;
+				type Call = Call<T> ;
+
+				fn dispatch(&mut self, origin: Self::Caller, _call: Call<T>) -> crate::support::DispatchResultWithPostInfo {
+					let who = origin.ensure_signed() ?;
+					self.events.push(Event::Refunded { who }) ;
+					Ok(PostDispatchInfo { actual_weight: Weight(1) })
+				}
+
+				fn weight(&self, call: &Call<T>) -> Weight {
+					crate::support::GetWeight::weight(call)
+				}
+			}
+		}
+
+		#[macros::runtime]
+		#[derive(Debug, Clone, PartialEq)]
+		pub struct Runtime {
+			system: system::Pallet<Self>,
+			refund: refund::Pallet<Self>,
+		}
+
+		impl system::Config for Runtime {
+			type AccountId = String ;
+			type BlockNumber = u32 ;
+			type Nonce = u32 ;
+			type Hash = u64 ;
+			type Hashing = support::Fnv ;
+			type Moment = u64 ;
+			const BLOCK_HASH_COUNT: u32 = 256 ;
+			const MAX_BLOCK_WEIGHT: support::Weight = support::Weight(20) ;
+			type RuntimeEvent = RuntimeEvent ;
+		}
+
+		impl refund::Config for Runtime {}
+
+		#[test]
+		fn charge_actual_weight() {
+			let mut runtime = Runtime::new() ;
+			let block = types::Block::new::<types::Hashing>(runtime.parent_hash(), 1, 1, (0..20).map(|nonce| support::Extrinsic {
+				caller: "alice".to_string(),
+				call: RuntimeCall::refund(refund::Call::refund),
+				nonce,
+			}.sign()).collect()) ;
+			let results = runtime.execute_block(block).expect("Invalid block.") ;
+
+			// Charging the declared weight, only 4 calls would fit into the block. Charging the actual
+			// weight, a call fits as long as its declared weight does.
+			assert_eq!(results[..16], vec![Ok(()); 16]) ;
+			// The extrinsic which did not fit is skipped, and so are the ones after it, which no longer
+			// carry the nonce of "alice".
+			assert_eq!(results[16], Err(support::DispatchError::ExhaustsResources)) ;
+			assert_eq!(results[17..], vec![Err(support::DispatchError::InvalidNonce); 3]) ;
+			assert_eq!(runtime.system.nonce(&"alice".to_string()), 16) ;
+		}
+	}
+
+	// A runtime put together with "construct_runtime!", which declares every pallet once, along with
+	// its Config. "#[macros::runtime]" generates the rest, like "new", "RuntimeCall" and the dispatch of
+	// every call to its pallet.
+	mod registration {
+		use crate::{balances, proof_of_existence, support, system} ;
+		use crate::support::Dispatch ;
+
+		mod types {
+			pub type Hashing = crate::support::Fnv ;
+			pub type Extrinsic = crate::support::SignedExtrinsic<String, super::RuntimeCall, u32, crate::support::Signature> ;
+			pub type Block = crate::support::Block<crate::support::Header<u32, u64, u64>, Extrinsic> ;
+		}
+
+		support::construct_runtime! {
+			#[derive(Debug, Clone, PartialEq)]
+			pub struct Runtime {
+				system: system {
+					type AccountId = String ;
+					type BlockNumber = u32 ;
+					type Nonce = u32 ;
+					type Hash = u64 ;
+					type Hashing = support::Fnv ;
+					type Moment = u64 ;
+					const BLOCK_HASH_COUNT: u32 = 256 ;
+					const MAX_BLOCK_WEIGHT: support::Weight = support::Weight(20) ;
+					type RuntimeEvent = RuntimeEvent ;
+				},
+				#[account_provider]
+				balances: balances {
+					type Balance = u64 ;
+					const EXISTENTIAL_DEPOSIT: u64 = 1 ;
+					const TRANSFER_FEE: u64 = 0 ;
+					const MINIMUM_TRANSFER: u64 = 0 ;
+					fn fee_account() -> String {
+						"fees".to_string()
+					}
+					type AccountStore = std::collections::BTreeMap<String, balances::AccountData<u64>> ;
+				},
+				#[dispatch_with(balances)]
+				proof_of_existence: proof_of_existence {
+					type Content = String ;
+					const MAX_CLAIMS: u32 = 10 ;
+					const MAX_CONTENT_LEN: u32 = 32 ;
+					type Currency = balances::Pallet<Self> ;
+					const CLAIM_DEPOSIT: u64 = 1 ;
+				},
+			}
+		}
+
+		#[test]
+		fn dispatch_into_every_pallet() {
+			let mut runtime = Runtime::new() ;
+			let alice = "alice".to_string() ;
+			let bob = "bob".to_string() ;
+			let _ = runtime.balances.mint(&alice, 100) ;
+			let origin = support::Origin::Signed(alice.clone()) ;
+
+			// Every call is routed to the pallet declared for it, and nothing more had to be declared.
+			let transfer = RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 30 }) ;
+			let claim = RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim { claim: "hello".to_string() }) ;
+			assert_eq!(runtime.dispatch_named(origin.clone(), transfer), Ok("balances")) ;
+			assert_eq!(runtime.dispatch_named(origin.clone(), claim), Ok("proof_of_existence")) ;
+			assert_eq!(runtime.balances.balance(&bob), 30) ;
+			assert_eq!(runtime.proof_of_existence.get_claim(&"hello".to_string()), Some(&alice)) ;
+			let revoke = RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim { claim: "hello".to_string() }) ;
+			assert!(runtime.dispatch(origin, revoke).is_ok()) ;
+			assert!(!runtime.proof_of_existence.is_claimed(&"hello".to_string())) ;
+
+			let pallets = Runtime::metadata().pallets.iter().map(|pallet| pallet.name).collect::<Vec<_>>() ;
+			assert_eq!(pallets, vec!["balances", "proof_of_existence"]) ;
+		}
+	}
 }
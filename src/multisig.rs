@@ -0,0 +1,330 @@
+use core::fmt::Debug ;
+use std::collections::{BTreeMap, BTreeSet} ;
+use crate::support::{DispatchError, DispatchResult, Journal} ;
+
+/// The Config trait for the Multisig pallet.
+/// It contains the calls which a multisig account can make, and how they are identified.
+pub trait Config: crate::system::Config {
+    /// The calls of the runtime, which a multisig account makes once enough signatories approve them.
+    type RuntimeCall: Debug + Clone + PartialEq ;
+    /// A type identifying a call, usually the hash of that call.
+    type CallHash: Debug + Ord + Clone ;
+    /// Get the hash identifying "call".
+    fn hash_call(call: &Self::RuntimeCall) -> Self::CallHash ;
+}
+
+/// The information stored about an account controlled by a multisig.
+#[derive(Debug, Clone, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "T::AccountId: serde::Serialize + serde::de::DeserializeOwned"))]
+pub struct MultisigInfo<T: Config> {
+    /// The accounts which can approve calls on behalf of the multisig account.
+    pub signatories: BTreeSet<T::AccountId>,
+    /// The number of signatories which must approve a call before it is made.
+    pub threshold: u32,
+}
+
+/// A call waiting for enough signatories to approve it.
+#[derive(Debug, Clone, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "T::AccountId: serde::Serialize + serde::de::DeserializeOwned, T::RuntimeCall: serde::Serialize + serde::de::DeserializeOwned"))]
+pub struct PendingCall<T: Config> {
+    /// The call to make once it is approved.
+    pub call: T::RuntimeCall,
+    /// The signatories which approved the call so far.
+    pub approvals: BTreeSet<T::AccountId>,
+}
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<T: Config> {
+    /// The account "multisig" is now controlled by its signatories, with some "threshold".
+    Created {
+        multisig: T::AccountId,
+        threshold: u32,
+    },
+    /// Some "signatory" approved the call identified by "call_hash" on behalf of "multisig".
+    Approved {
+        multisig: T::AccountId,
+        signatory: T::AccountId,
+        call_hash: T::CallHash,
+    },
+    /// The call identified by "call_hash" was approved by enough signatories, and is queued up for the
+    /// runtime to make on behalf of "multisig" right after the approval. The call may still fail.
+    Queued {
+        multisig: T::AccountId,
+        call_hash: T::CallHash,
+    },
+}
+
+/// The operations undoing the mutations of the storage of this pallet, as recorded in its journal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Undo<T: Config> {
+    /// Put the previous multisig controlling an account back, or remove it if there was none.
+    Multisig(T::AccountId, Option<MultisigInfo<T>>),
+    /// Put a call waiting for approvals back as it was, or remove it if it was not waiting.
+    Pending((T::AccountId, T::CallHash), Option<PendingCall<T>>),
+}
+
+/// This is the Multisig pallet.
+/// It allows an account to be controlled by a set of signatories, who make calls on behalf of that
+/// account once enough of them approve.
+#[derive(Debug, Clone, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "T::AccountId: serde::Serialize + serde::de::DeserializeOwned, T::RuntimeCall: serde::Serialize + serde::de::DeserializeOwned, T::CallHash: serde::Serialize + serde::de::DeserializeOwned"))]
+pub struct Pallet<T: Config> {
+    /// A map from an account to the multisig controlling it.
+    multisigs: BTreeMap<T::AccountId, MultisigInfo<T>>,
+    /// A map from a multisig account and the hash of a call to that call, while it waits for approvals.
+    /// The keys are not strings, so they are serialized as a list.
+    #[cfg_attr(feature = "serde", serde(with = "crate::support::map_as_seq"))]
+    #[allow(clippy::type_complexity)]
+    pending: BTreeMap<(T::AccountId, T::CallHash), PendingCall<T>>,
+    /// The approved calls, along with the multisig account making each of them, waiting to be taken
+    /// by the runtime.
+    /// They are taken right after the call approving them, so they are never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    queued: Vec<(T::AccountId, T::RuntimeCall)>,
+    /// The events emitted since they were last taken.
+    /// Events are not part of the state, so they are never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    events: Vec<Event<T>>,
+    /// The journal of the mutations made while executing the last block.
+    /// Like events, the journal is not part of the state.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    journal: Journal<Undo<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+    /// Create a new instance of the multisig pallet.
+    pub fn new() -> Self {
+        Self {
+            multisigs: BTreeMap::new(),
+            pending: BTreeMap::new(),
+            queued: Vec::new(),
+            events: Vec::new(),
+            journal: Journal::new(),
+        }
+    }
+
+    /// Get the multisig controlling the account "multisig", if there is one.
+    pub fn multisig(&self, multisig: &T::AccountId) -> Option<&MultisigInfo<T>> {
+        self.multisigs.get(multisig)
+    }
+
+    /// Get the call identified by "call_hash" waiting for approvals on behalf of "multisig", if there
+    /// is one.
+    pub fn pending(&self, multisig: &T::AccountId, call_hash: &T::CallHash) -> Option<&PendingCall<T>> {
+        self.pending.get(&(multisig.clone(), call_hash.clone()))
+    }
+
+    /// Take all the events emitted since they were last taken, leaving no events behind.
+    pub fn take_events(&mut self) -> Vec<Event<T>> {
+        core::mem::take(&mut self.events)
+    }
+
+    /// Record the call waiting for approvals under "key" in the journal, before it is mutated.
+    fn journal_pending(&mut self, key: &(T::AccountId, T::CallHash)) {
+        self.journal.record(|| {
+            let pending = self.pending
+                .get(key)
+                .map(|pending| PendingCall { call: pending.call.clone(), approvals: pending.approvals.clone() }) ;
+            Undo::Pending(key.clone(), pending)
+        }) ;
+    }
+
+    /// Reverse a mutation of the storage, as recorded by "undo" in the journal.
+    fn undo(&mut self, undo: Undo<T>) {
+        match undo {
+            Undo::Multisig(multisig, info) => crate::support::restore_entry(&mut self.multisigs, multisig, info),
+            Undo::Pending(key, pending) => crate::support::restore_entry(&mut self.pending, key, pending),
+        }
+    }
+}
+
+// The mutations of this pallet are journaled while a block is executed, so the runtime can undo them.
+impl<T: Config> crate::support::Journaled for Pallet<T> {
+    fn start_journal(&mut self) {
+        self.journal.start() ;
+    }
+
+    fn stop_journal(&mut self) {
+        self.journal.stop() ;
+    }
+
+    fn undo_journal(&mut self) {
+        for undo in self.journal.take() {
+            self.undo(undo) ;
+        }
+    }
+
+    fn checkpoint_journal(&mut self) -> crate::support::JournalCheckpoint {
+        self.journal.checkpoint()
+    }
+
+    fn commit_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        self.journal.commit(checkpoint) ;
+    }
+
+    fn rollback_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        for undo in self.journal.rollback(checkpoint) {
+            self.undo(undo) ;
+        }
+    }
+}
+
+// Approved calls are made by the runtime, which dispatches them on behalf of the multisig account.
+impl<T: Config> crate::support::QueuedCalls<T::AccountId, T::RuntimeCall> for Pallet<T> {
+    fn take_queued_calls(&mut self) -> Vec<(T::AccountId, T::RuntimeCall)> {
+        core::mem::take(&mut self.queued)
+    }
+}
+
+// The multisig pallet does not need to execute any logic as part of a block.
+impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {}
+
+// Only these functions will be called by the user from this pallet.
+#[macros::call]
+impl<T: Config> Pallet<T> {
+    /// Hand control of the caller's account to some "signatories", of which "threshold" must approve
+    /// a call before it is made on behalf of the caller.
+    /// Any multisig set up before is replaced, and the calls waiting for its approvals are dropped.
+    pub fn create_multisig(
+        &mut self,
+        caller: T::AccountId,
+        signatories: Vec<T::AccountId>,
+        threshold: u32
+    ) -> DispatchResult {
+        let signatories = signatories.into_iter().collect::<BTreeSet<_>>() ;
+        if threshold == 0 || threshold as usize > signatories.len() {
+            return Err(DispatchError::InvalidThreshold) ;
+        }
+        let dropped = self.pending
+            .keys()
+            .filter(|(multisig, _)| *multisig == caller)
+            .cloned()
+            .collect::<Vec<_>>() ;
+        for key in dropped {
+            self.journal_pending(&key) ;
+            self.pending.remove(&key) ;
+        }
+        let previous = self.multisigs.insert(caller.clone(), MultisigInfo { signatories, threshold }) ;
+        self.journal.record(|| Undo::Multisig(caller.clone(), previous)) ;
+
+        self.events.push(Event::Created { multisig: caller, threshold }) ;
+        Ok(())
+    }
+
+    /// Approve making the call "proposal" on behalf of the account "multisig".
+    /// The caller must be a signatory of the multisig. Once enough signatories approved the call, it is
+    /// queued up, and the runtime makes it right away.
+    pub fn approve(
+        &mut self,
+        caller: T::AccountId,
+        multisig: T::AccountId,
+        proposal: Box<T::RuntimeCall>
+    ) -> DispatchResult {
+        let info = self.multisigs.get(&multisig).ok_or(DispatchError::MultisigNotFound) ?;
+        if !info.signatories.contains(&caller) {
+            return Err(DispatchError::NotSignatory) ;
+        }
+        let threshold = info.threshold as usize ;
+
+        // Approving the same call again has no further effect.
+        let call_hash = T::hash_call(&proposal) ;
+        let key = (multisig.clone(), call_hash.clone()) ;
+        self.journal_pending(&key) ;
+        let pending = self.pending
+            .entry(key.clone())
+            .or_insert_with(|| PendingCall { call: *proposal, approvals: BTreeSet::new() }) ;
+        pending.approvals.insert(caller.clone()) ;
+        let approved = pending.approvals.len() >= threshold ;
+        self.events.push(Event::Approved { multisig: multisig.clone(), signatory: caller, call_hash: call_hash.clone() }) ;
+
+        if approved {
+            if let Some(pending) = self.pending.remove(&key) {
+                self.queued.push((multisig.clone(), pending.call)) ;
+            }
+            self.events.push(Event::Queued { multisig, call_hash }) ;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::support::{DispatchError, QueuedCalls} ;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestConfig ;
+    impl crate::system::Config for TestConfig {
+        type AccountId = String ;
+        type BlockNumber = u32 ;
+        type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
+    }
+
+    // Calls are plain strings, which identify themselves.
+    impl super::Config for TestConfig {
+        type RuntimeCall = String ;
+        type CallHash = String ;
+        fn hash_call(call: &String) -> String {
+            call.clone()
+        }
+    }
+
+    #[test]
+    fn create_multisig() {
+        let mut multisig = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let fund = "fund".to_string() ;
+
+        // The threshold must be reachable, by at least one signatory.
+        assert_eq!(multisig.create_multisig(fund.clone(), vec![alice.clone(), bob.clone()], 0), Err(DispatchError::InvalidThreshold)) ;
+        assert_eq!(multisig.create_multisig(fund.clone(), vec![alice.clone(), alice.clone()], 2), Err(DispatchError::InvalidThreshold)) ;
+        assert_eq!(multisig.multisig(&fund), None) ;
+
+        assert_eq!(multisig.create_multisig(fund.clone(), vec![alice.clone(), bob.clone()], 2), Ok(())) ;
+        assert_eq!(multisig.multisig(&fund).map(|info| info.threshold), Some(2)) ;
+    }
+
+    #[test]
+    fn approve_call() {
+        let mut multisig = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let charlie = "charlie".to_string() ;
+        let fund = "fund".to_string() ;
+        let call = "pay charlie".to_string() ;
+
+        assert_eq!(multisig.approve(alice.clone(), fund.clone(), Box::new(call.clone())), Err(DispatchError::MultisigNotFound)) ;
+        let _ = multisig.create_multisig(fund.clone(), vec![alice.clone(), bob.clone(), charlie.clone()], 2) ;
+        assert_eq!(multisig.approve(fund.clone(), fund.clone(), Box::new(call.clone())), Err(DispatchError::NotSignatory)) ;
+
+        // Below the threshold, the call waits for more approvals. Approving twice counts once.
+        assert_eq!(multisig.approve(alice.clone(), fund.clone(), Box::new(call.clone())), Ok(())) ;
+        assert_eq!(multisig.approve(alice.clone(), fund.clone(), Box::new(call.clone())), Ok(())) ;
+        assert_eq!(multisig.pending(&fund, &call).map(|pending| pending.approvals.len()), Some(1)) ;
+        assert_eq!(multisig.take_queued_calls(), vec![]) ;
+
+        // Reaching the threshold queues the call up, to be made by the multisig account.
+        assert_eq!(multisig.approve(bob.clone(), fund.clone(), Box::new(call.clone())), Ok(())) ;
+        assert_eq!(multisig.pending(&fund, &call), None) ;
+        assert_eq!(multisig.take_queued_calls(), vec![(fund.clone(), call.clone())]) ;
+        assert_eq!(multisig.take_queued_calls(), vec![]) ;
+
+        assert_eq!(
+            multisig.take_events().last(),
+            Some(&super::Event::Queued { multisig: fund.clone(), call_hash: call.clone() })
+        ) ;
+    }
+}
@@ -0,0 +1,359 @@
+use core::fmt::Debug ;
+use std::collections::BTreeMap ;
+use crate::support::{DispatchError, DispatchResult, Journal} ;
+
+/// The Config trait for the NFT pallet.
+/// It contains the types identifying unique items, and the metadata which can be attached to them.
+pub trait Config: crate::system::Config {
+    /// A type which identifies a collection of items.
+    type CollectionId: Debug + Ord + Clone ;
+    /// A type which identifies an item within its collection.
+    type ItemId: Debug + Ord + Clone ;
+    /// A type representing the metadata the owner of an item can attach to it.
+    type Metadata: Debug + Clone + PartialEq ;
+}
+
+/// The information stored about an item.
+#[derive(Debug, Clone, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "T::AccountId: serde::Serialize + serde::de::DeserializeOwned, T::Metadata: serde::Serialize + serde::de::DeserializeOwned"))]
+pub struct ItemInfo<T: Config> {
+    /// The owner of the item.
+    pub owner: T::AccountId,
+    /// The metadata attached to the item by its owner, if any.
+    pub metadata: Option<T::Metadata>,
+}
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<T: Config> {
+    /// Some "item" of a "collection" was minted by its "owner".
+    Minted {
+        owner: T::AccountId,
+        collection: T::CollectionId,
+        item: T::ItemId,
+    },
+    /// Some "item" of a "collection" was transferred from one owner to another.
+    Transferred {
+        from: T::AccountId,
+        to: T::AccountId,
+        collection: T::CollectionId,
+        item: T::ItemId,
+    },
+    /// Some "item" of a "collection" was burned by its "owner".
+    Burned {
+        owner: T::AccountId,
+        collection: T::CollectionId,
+        item: T::ItemId,
+    },
+    /// The metadata of some "item" of a "collection" was set by its owner.
+    MetadataSet {
+        collection: T::CollectionId,
+        item: T::ItemId,
+        metadata: T::Metadata,
+    },
+}
+
+/// The operations undoing the mutations of the storage of this pallet, as recorded in its journal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Undo<T: Config> {
+    /// Put the information about an item back, or remove the item if there was none.
+    Item((T::CollectionId, T::ItemId), Option<ItemInfo<T>>),
+    /// Hand an item back to its previous owner.
+    Owner((T::CollectionId, T::ItemId), T::AccountId),
+    /// Set the metadata of an item back to the given one.
+    Metadata((T::CollectionId, T::ItemId), Option<T::Metadata>),
+}
+
+/// This is the NFT pallet.
+/// Like the Proof of Existence pallet, it keeps track of the unique owner of each item. Unlike claims,
+/// items are grouped in collections, and their owners can attach metadata to them.
+#[derive(Debug, Clone, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "T::AccountId: serde::Serialize + serde::de::DeserializeOwned, T::CollectionId: serde::Serialize + serde::de::DeserializeOwned, T::ItemId: serde::Serialize + serde::de::DeserializeOwned, T::Metadata: serde::Serialize + serde::de::DeserializeOwned"))]
+pub struct Pallet<T: Config> {
+    /// A map from a collection and an item within it to the information about that item, including
+    /// its owner.
+    /// The keys are not strings, so they are serialized as a list.
+    #[cfg_attr(feature = "serde", serde(with = "crate::support::map_as_seq"))]
+    #[allow(clippy::type_complexity)]
+    items: BTreeMap<(T::CollectionId, T::ItemId), ItemInfo<T>>,
+    /// The events emitted since they were last taken.
+    /// Events are not part of the state, so they are never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    events: Vec<Event<T>>,
+    /// The journal of the mutations made while executing the last block.
+    /// Like events, the journal is not part of the state.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    journal: Journal<Undo<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+    /// Create a new instance of the NFT pallet.
+    pub fn new() -> Self {
+        Self {
+            items: BTreeMap::new(),
+            events: Vec::new(),
+            journal: Journal::new(),
+        }
+    }
+
+    /// Get the information about some "item" of a "collection", if it has been minted.
+    pub fn item(&self, collection: &T::CollectionId, item: &T::ItemId) -> Option<&ItemInfo<T>> {
+        self.items.get(&(collection.clone(), item.clone()))
+    }
+
+    /// Get the owner of some "item" of a "collection", if it has been minted.
+    pub fn owner(&self, collection: &T::CollectionId, item: &T::ItemId) -> Option<&T::AccountId> {
+        self.item(collection, item).map(|info| &info.owner)
+    }
+
+    /// Get the information about some "item" of a "collection" for a change by "caller".
+    /// This function fails if the item does not exist, or if it is owned by some other account.
+    fn owned_item(
+        &mut self,
+        caller: &T::AccountId,
+        collection: &T::CollectionId,
+        item: &T::ItemId
+    ) -> Result<&mut ItemInfo<T>, DispatchError> {
+        // Get the item to be changed.
+        let info = self.items.get_mut(&(collection.clone(), item.clone())).ok_or(DispatchError::ItemNotFound) ?;
+
+        // Check whether the caller is the owner of the item.
+        if info.owner != *caller {
+            return Err(DispatchError::NotOwner) ;
+        }
+        Ok(info)
+    }
+
+    /// Take all the events emitted since they were last taken, leaving no events behind.
+    pub fn take_events(&mut self) -> Vec<Event<T>> {
+        core::mem::take(&mut self.events)
+    }
+
+    /// Reverse a mutation of the storage, as recorded by "undo" in the journal.
+    fn undo(&mut self, undo: Undo<T>) {
+        match undo {
+            Undo::Item(key, info) => crate::support::restore_entry(&mut self.items, key, info),
+            Undo::Owner(key, owner) => {
+                if let Some(info) = self.items.get_mut(&key) {
+                    info.owner = owner ;
+                }
+            },
+            Undo::Metadata(key, metadata) => {
+                if let Some(info) = self.items.get_mut(&key) {
+                    info.metadata = metadata ;
+                }
+            },
+        }
+    }
+}
+
+// The mutations of this pallet are journaled while a block is executed, so the runtime can undo them.
+impl<T: Config> crate::support::Journaled for Pallet<T> {
+    fn start_journal(&mut self) {
+        self.journal.start() ;
+    }
+
+    fn stop_journal(&mut self) {
+        self.journal.stop() ;
+    }
+
+    fn undo_journal(&mut self) {
+        for undo in self.journal.take() {
+            self.undo(undo) ;
+        }
+    }
+
+    fn checkpoint_journal(&mut self) -> crate::support::JournalCheckpoint {
+        self.journal.checkpoint()
+    }
+
+    fn commit_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        self.journal.commit(checkpoint) ;
+    }
+
+    fn rollback_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        for undo in self.journal.rollback(checkpoint) {
+            self.undo(undo) ;
+        }
+    }
+}
+
+// The NFT pallet does not need to execute any logic as part of a block.
+impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {}
+
+// Only these functions will be called by the user from this pallet.
+#[macros::call]
+impl<T: Config> Pallet<T> {
+    /// Mint some "item" of a "collection", owned by the caller.
+    /// This function fails if the item has already been minted.
+    pub fn mint(
+        &mut self,
+        caller: T::AccountId,
+        collection: T::CollectionId,
+        item: T::ItemId
+    ) -> DispatchResult {
+        let key = (collection.clone(), item.clone()) ;
+        if self.items.contains_key(&key) {
+            return Err(DispatchError::ItemExists) ;
+        }
+        self.items.insert(key.clone(), ItemInfo { owner: caller.clone(), metadata: None }) ;
+        self.journal.record(|| Undo::Item(key, None)) ;
+
+        self.events.push(Event::Minted { owner: caller, collection, item }) ;
+        Ok(())
+    }
+
+    /// Transfer some "item" of a "collection", owned by the caller, to the account "to".
+    /// The metadata of the item is kept.
+    pub fn transfer(
+        &mut self,
+        caller: T::AccountId,
+        collection: T::CollectionId,
+        item: T::ItemId,
+        to: T::AccountId
+    ) -> DispatchResult {
+        let info = self.owned_item(&caller, &collection, &item) ?;
+        let owner = core::mem::replace(&mut info.owner, to.clone()) ;
+        self.journal.record(|| Undo::Owner((collection.clone(), item.clone()), owner)) ;
+
+        self.events.push(Event::Transferred { from: caller, to, collection, item }) ;
+        Ok(())
+    }
+
+    /// Burn some "item" of a "collection", owned by the caller.
+    /// Once burned, the item can be minted again.
+    pub fn burn(
+        &mut self,
+        caller: T::AccountId,
+        collection: T::CollectionId,
+        item: T::ItemId
+    ) -> DispatchResult {
+        self.owned_item(&caller, &collection, &item) ?;
+        let key = (collection.clone(), item.clone()) ;
+        let info = self.items.remove(&key) ;
+        self.journal.record(|| Undo::Item(key, info)) ;
+
+        self.events.push(Event::Burned { owner: caller, collection, item }) ;
+        Ok(())
+    }
+
+    /// Set the "metadata" of some "item" of a "collection", owned by the caller.
+    /// Any metadata set before is replaced.
+    pub fn set_metadata(
+        &mut self,
+        caller: T::AccountId,
+        collection: T::CollectionId,
+        item: T::ItemId,
+        metadata: T::Metadata
+    ) -> DispatchResult {
+        let info = self.owned_item(&caller, &collection, &item) ?;
+        let previous = info.metadata.replace(metadata.clone()) ;
+        self.journal.record(|| Undo::Metadata((collection.clone(), item.clone()), previous)) ;
+
+        self.events.push(Event::MetadataSet { collection, item, metadata }) ;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::support::DispatchError ;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestConfig ;
+    impl crate::system::Config for TestConfig {
+        type AccountId = String ;
+        type BlockNumber = u32 ;
+        type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
+    }
+
+    impl super::Config for TestConfig {
+        type CollectionId = u32 ;
+        type ItemId = u32 ;
+        type Metadata = &'static str ;
+    }
+
+    #[test]
+    fn mint_item() {
+        let mut nft = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+
+        assert_eq!(nft.mint(alice.clone(), 1, 1), Ok(())) ;
+        assert_eq!(nft.owner(&1, &1), Some(&alice)) ;
+        assert_eq!(nft.item(&1, &1).and_then(|info| info.metadata), None) ;
+
+        // Items are unique within their collection, but not across collections.
+        assert_eq!(nft.mint(bob.clone(), 1, 1), Err(DispatchError::ItemExists)) ;
+        assert_eq!(nft.mint(bob.clone(), 2, 1), Ok(())) ;
+        assert_eq!(nft.owner(&1, &1), Some(&alice)) ;
+        assert_eq!(nft.owner(&2, &1), Some(&bob)) ;
+    }
+
+    #[test]
+    fn transfer_item() {
+        let mut nft = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = nft.mint(alice.clone(), 1, 1) ;
+        let _ = nft.set_metadata(alice.clone(), 1, 1, "Hello") ;
+
+        // Only the owner can transfer an item.
+        assert_eq!(nft.transfer(bob.clone(), 1, 1, bob.clone()), Err(DispatchError::NotOwner)) ;
+        assert_eq!(nft.transfer(alice.clone(), 1, 2, bob.clone()), Err(DispatchError::ItemNotFound)) ;
+        assert_eq!(nft.owner(&1, &1), Some(&alice)) ;
+
+        // The metadata moves along with the item.
+        assert_eq!(nft.transfer(alice.clone(), 1, 1, bob.clone()), Ok(())) ;
+        assert_eq!(nft.item(&1, &1), Some(&super::ItemInfo { owner: bob.clone(), metadata: Some("Hello") })) ;
+        assert_eq!(nft.transfer(alice.clone(), 1, 1, alice.clone()), Err(DispatchError::NotOwner)) ;
+    }
+
+    #[test]
+    fn set_item_metadata() {
+        let mut nft = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = nft.mint(alice.clone(), 1, 1) ;
+
+        assert_eq!(nft.set_metadata(bob.clone(), 1, 1, "World"), Err(DispatchError::NotOwner)) ;
+        assert_eq!(nft.set_metadata(alice.clone(), 1, 1, "Hello"), Ok(())) ;
+        assert_eq!(nft.set_metadata(alice.clone(), 1, 1, "World"), Ok(())) ;
+        assert_eq!(nft.item(&1, &1).and_then(|info| info.metadata), Some("World")) ;
+    }
+
+    #[test]
+    fn burn_item() {
+        let mut nft = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = nft.mint(alice.clone(), 1, 1) ;
+
+        assert_eq!(nft.burn(bob.clone(), 1, 1), Err(DispatchError::NotOwner)) ;
+        assert_eq!(nft.burn(alice.clone(), 1, 1), Ok(())) ;
+        assert_eq!(nft.owner(&1, &1), None) ;
+        assert_eq!(nft.burn(alice.clone(), 1, 1), Err(DispatchError::ItemNotFound)) ;
+
+        // Burned items can be minted again.
+        assert_eq!(nft.mint(bob.clone(), 1, 1), Ok(())) ;
+
+        assert_eq!(
+            nft.take_events(),
+            vec![
+                super::Event::Minted { owner: alice.clone(), collection: 1, item: 1 },
+                super::Event::Burned { owner: alice.clone(), collection: 1, item: 1 },
+                super::Event::Minted { owner: bob.clone(), collection: 1, item: 1 },
+            ]
+        ) ;
+    }
+}
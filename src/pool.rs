@@ -0,0 +1,164 @@
+use std::collections::BTreeMap ;
+use num::traits::{CheckedAdd, CheckedSub, One} ;
+use crate::support::{DispatchError, DispatchResult, Encode, SignedExtrinsic, Verify} ;
+use crate::system::Config ;
+
+/// The signed extrinsics of a runtime configured with "T", making calls of type "Call".
+type ExtrinsicOf<T, Call, Signature> = SignedExtrinsic<<T as Config>::AccountId, Call, <T as Config>::Nonce, Signature> ;
+
+/// This is the Transaction Pool.
+/// Like the mempool of a node, it holds the extrinsics submitted by accounts until they can be put
+/// into a block. An extrinsic is ready once every extrinsic of its caller with a lower nonce is
+/// either executed or ready, so extrinsics can be submitted out of order.
+pub struct TransactionPool<T: Config, Call, Signature> {
+    /// A map from an account to the extrinsics it submitted, in the order they were submitted.
+    queued: BTreeMap<T::AccountId, Vec<ExtrinsicOf<T, Call, Signature>>>,
+}
+
+impl<T: Config, Call: Encode, Signature: Verify<T::AccountId>> TransactionPool<T, Call, Signature>
+where
+    T::Nonce: Encode,
+{
+    /// Create a new, empty transaction pool.
+    pub fn new() -> Self {
+        Self { queued: BTreeMap::new() }
+    }
+
+    /// The number of extrinsics waiting in the pool, whether they are ready or not.
+    pub fn len(&self) -> usize {
+        self.queued.values().map(Vec::len).sum()
+    }
+
+    /// Check whether no extrinsic is waiting in the pool.
+    pub fn is_empty(&self) -> bool {
+        self.queued.is_empty()
+    }
+
+    /// Submit an "extrinsic" to the pool, checking it against the current nonces in "system".
+    /// This function fails if the extrinsic was not signed by its caller, if its nonce was used
+    /// already, or if an extrinsic of the same caller with the same nonce is waiting in the pool.
+    pub fn submit(
+        &mut self,
+        system: &crate::system::Pallet<T>,
+        extrinsic: ExtrinsicOf<T, Call, Signature>
+    ) -> DispatchResult {
+        if !extrinsic.verify() {
+            return Err(DispatchError::InvalidSignature) ;
+        }
+        // Nonces are unsigned, so a nonce lower than the current one cannot have the current one
+        // subtracted from it.
+        if extrinsic.nonce.checked_sub(&system.nonce(&extrinsic.caller)).is_none() {
+            return Err(DispatchError::InvalidNonce) ;
+        }
+        let queued = self.queued.entry(extrinsic.caller.clone()).or_default() ;
+        if queued.iter().any(|other| other.nonce == extrinsic.nonce) {
+            return Err(DispatchError::AlreadyInPool) ;
+        }
+        queued.push(extrinsic) ;
+        Ok(())
+    }
+
+    /// Take the extrinsics which are ready to be executed on top of the current nonces in "system",
+    /// ordered by caller, and for every caller in ascending nonce order with no gaps.
+    /// The extrinsics after a gap stay in the pool until the gap is filled, while the extrinsics
+    /// whose nonce was used in the meantime are dropped.
+    pub fn ready(&mut self, system: &crate::system::Pallet<T>) -> Vec<ExtrinsicOf<T, Call, Signature>> {
+        let mut ready = Vec::new() ;
+        for (caller, queued) in self.queued.iter_mut() {
+            let nonce = system.nonce(caller) ;
+            queued.retain(|extrinsic| extrinsic.nonce.checked_sub(&nonce).is_some()) ;
+
+            // Like "inc_nonce", the nonce stops at its maximum value.
+            let mut next = Some(nonce) ;
+            while let Some(nonce) = next {
+                let Some(index) = queued.iter().position(|extrinsic| extrinsic.nonce == nonce) else {
+                    break ;
+                } ;
+                ready.push(queued.remove(index)) ;
+                next = nonce.checked_add(&T::Nonce::one()) ;
+            }
+        }
+        self.queued.retain(|_, queued| !queued.is_empty()) ;
+        ready
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::support::{DispatchError, Extrinsic} ;
+
+    struct TestConfig ;
+    impl crate::system::Config for TestConfig {
+        type AccountId = String ;
+        type BlockNumber = u32 ;
+        type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
+    }
+
+    type TransactionPool = super::TransactionPool<TestConfig, &'static str, crate::support::Signature> ;
+
+    fn extrinsic(caller: &str, nonce: u32) -> super::ExtrinsicOf<TestConfig, &'static str, crate::support::Signature> {
+        Extrinsic { caller: caller.to_string(), call: "call", nonce }.sign()
+    }
+
+    // The caller and nonce of every extrinsic, since signed extrinsics cannot be compared.
+    fn order<'a>(ready: &'a [super::ExtrinsicOf<TestConfig, &'static str, crate::support::Signature>]) -> Vec<(&'a str, u32)> {
+        ready.iter().map(|extrinsic| (extrinsic.caller.as_str(), extrinsic.nonce)).collect()
+    }
+
+    #[test]
+    fn ready_in_nonce_order() {
+        let mut system = crate::system::Pallet::<TestConfig>::new() ;
+        let mut pool = TransactionPool::new() ;
+        system.inc_nonce(&"bob".to_string()) ;
+
+        // Extrinsics can be submitted in any order, as long as their nonce was not used already.
+        for (caller, nonce) in [("bob", 2), ("alice", 1), ("bob", 1), ("alice", 0), ("alice", 2)] {
+            assert_eq!(pool.submit(&system, extrinsic(caller, nonce)), Ok(())) ;
+        }
+        assert_eq!(pool.submit(&system, extrinsic("alice", 1)), Err(DispatchError::AlreadyInPool)) ;
+        assert_eq!(pool.submit(&system, extrinsic("bob", 0)), Err(DispatchError::InvalidNonce)) ;
+        let mut forged = extrinsic("alice", 3) ;
+        forged.caller = "bob".to_string() ;
+        assert_eq!(pool.submit(&system, forged), Err(DispatchError::InvalidSignature)) ;
+        assert_eq!(pool.len(), 5) ;
+
+        let ready = pool.ready(&system) ;
+        assert_eq!(order(&ready), vec![("alice", 0), ("alice", 1), ("alice", 2), ("bob", 1), ("bob", 2)]) ;
+        assert!(pool.is_empty()) ;
+        assert!(pool.ready(&system).is_empty()) ;
+    }
+
+    #[test]
+    fn gapped_nonces_stay_queued() {
+        let mut system = crate::system::Pallet::<TestConfig>::new() ;
+        let mut pool = TransactionPool::new() ;
+        let alice = "alice".to_string() ;
+
+        // The extrinsic with nonce 2 waits for the one with nonce 1.
+        assert_eq!(pool.submit(&system, extrinsic("alice", 0)), Ok(())) ;
+        assert_eq!(pool.submit(&system, extrinsic("alice", 2)), Ok(())) ;
+        assert_eq!(order(&pool.ready(&system)), vec![("alice", 0)]) ;
+        assert_eq!(pool.len(), 1) ;
+        assert!(pool.ready(&system).is_empty()) ;
+
+        // Once the gap is filled, both come out in order.
+        system.inc_nonce(&alice) ;
+        assert_eq!(pool.submit(&system, extrinsic("alice", 1)), Ok(())) ;
+        assert_eq!(order(&pool.ready(&system)), vec![("alice", 1), ("alice", 2)]) ;
+        assert!(pool.is_empty()) ;
+
+        // An extrinsic whose nonce was used by an extrinsic from elsewhere is dropped.
+        assert_eq!(pool.submit(&system, extrinsic("alice", 3)), Ok(())) ;
+        for _ in 0..4 {
+            system.inc_nonce(&alice) ;
+        }
+        assert!(pool.ready(&system).is_empty()) ;
+        assert!(pool.is_empty()) ;
+    }
+}
@@ -1,74 +1,546 @@
 use core::fmt::Debug ;
 use std::collections::BTreeMap ;
-use crate::support::DispatchResult ;
+use num::traits::Zero ;
+use crate::support::{DispatchError, DispatchResult, Journal, Journaled, ReservableCurrency} ;
 
 /// The Config trait for our Proof of Existence pallet.
 /// It contains the types AccountId & Content of a user.
-pub trait Config: crate::system::Config {
+pub trait Config: crate::balances::Config {
     /// A type representing the content that can be claimed using this pallet.
     /// The content could be bytes or hash of that content. It's upto the Runtime developer.
-    type Content: Debug + Ord ;
+    type Content: Debug + Ord + Clone + ContentLen ;
+    /// The maximum number of claims a single account can own.
+    const MAX_CLAIMS: u32 ;
+    /// The maximum length of the content of a claim.
+    const MAX_CONTENT_LEN: u32 ;
+    /// The currency in which claim deposits are paid. The deposit of a claim is reserved in this
+    /// currency, so it cannot be transferred while the claim exists. Its journal lets a batch of claims
+    /// roll back the deposits it reserved.
+    type Currency: ReservableCurrency<Self::AccountId, Balance = Self::Balance> + Journaled ;
+    /// The deposit reserved from the owner of every claim, which discourages squatting on content.
+    const CLAIM_DEPOSIT: Self::Balance ;
+}
+
+/// A trait to measure the length of some content, so that the size of claims can be bounded.
+pub trait ContentLen {
+    /// The length of the content, e.g. the number of bytes.
+    fn len(&self) -> usize ;
+
+    /// Whether the content is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ContentLen for &str {
+    fn len(&self) -> usize {
+        str::len(self)
+    }
+}
+
+impl ContentLen for String {
+    fn len(&self) -> usize {
+        String::len(self)
+    }
+}
+
+impl ContentLen for Vec<u8> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+}
+
+/// The information stored about a claim.
+#[derive(Debug, Clone, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "T::AccountId: serde::Serialize + serde::de::DeserializeOwned, T::BlockNumber: serde::Serialize + serde::de::DeserializeOwned, T::Content: serde::Serialize + serde::de::DeserializeOwned"))]
+pub struct ClaimInfo<T: Config> {
+    /// The owner of the claim.
+    pub owner: T::AccountId,
+    /// The block number at which the claim was created.
+    pub block: T::BlockNumber,
+    /// An optional note attached to the claim by its creator.
+    pub note: Option<T::Content>,
+}
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<T: Config> {
+    /// Some "claim" was created by its "owner".
+    Created {
+        owner: T::AccountId,
+        claim: T::Content,
+    },
+    /// Some "claim" was revoked by its "owner".
+    Revoked {
+        owner: T::AccountId,
+        claim: T::Content,
+    },
+    /// Some "claim" of its "owner" expired, and was removed.
+    Expired {
+        owner: T::AccountId,
+        claim: T::Content,
+    },
+    /// Some "claim" was transferred from one owner to another.
+    Transferred {
+        from: T::AccountId,
+        to: T::AccountId,
+        claim: T::Content,
+    },
+}
+
+/// The operations undoing the mutations of the storage of this pallet, as recorded in its journal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Undo<T: Config> {
+    /// Put the information about a claim back, or remove the claim if there was none.
+    Claim(T::Content, Option<ClaimInfo<T>>),
+    /// Hand a claim back to its previous owner.
+    Owner(T::Content, T::AccountId),
+    /// Set the number of claims owned by an account back to the given one, or remove it if there was none.
+    ClaimCount(T::AccountId, Option<u32>),
+    /// Set the expiry of a claim back to the given block, or remove it if there was none.
+    Expiry(T::Content, Option<T::BlockNumber>),
+    /// Set the current block number back to the given one.
+    BlockNumber(T::BlockNumber),
+    /// Take the last owner off the ownership history of a claim, removing the history once it is empty.
+    History(T::Content),
 }
 
 /// This is the Proof of Existence pallet.
 /// It is a simple pallet that allows accounts to claim existence of some data.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "T::AccountId: serde::Serialize + serde::de::DeserializeOwned, T::BlockNumber: serde::Serialize + serde::de::DeserializeOwned, T::Content: serde::Serialize + serde::de::DeserializeOwned"))]
 pub struct Pallet<T: Config> {
-    /// A simple storage map from content to the owner of that content.
+    /// A simple storage map from content to the information about that claim, including its owner.
     /// Accounts can make multiple claims, but a claim can only be owned by a particular owner.
-    claims: BTreeMap<T::Content, T::AccountId> 
+    claims: BTreeMap<T::Content, ClaimInfo<T>>,
+    /// A map from an account to the number of claims it owns.
+    claim_count: BTreeMap<T::AccountId, u32>,
+    /// A map from content to the block number at which the claim on that content expires.
+    /// Claims without an entry here never expire.
+    expiry: BTreeMap<T::Content, T::BlockNumber>,
+    /// A map from content to every account which has owned a claim on it, oldest first.
+    /// The history outlives the claim itself, so it keeps growing when a revoked or expired claim is
+    /// created again, possibly by someone else.
+    history: BTreeMap<T::Content, Vec<T::AccountId>>,
+    /// The current block number, as last seen through "on_initialize".
+    block_number: T::BlockNumber,
+    /// The events emitted since they were last taken.
+    /// Events are not part of the state, so they are never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    events: Vec<Event<T>>,
+    /// The journal of the mutations made while executing the last block.
+    /// Like events, the journal is not part of the state.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    journal: Journal<Undo<T>>,
 }
 
 impl<T:Config> Pallet<T> {
     /// Create a new instance of out POE pallet.
     pub fn new() -> Self {
         Self {
-            claims: BTreeMap::new()
+            claims: BTreeMap::new(),
+            claim_count: BTreeMap::new(),
+            expiry: BTreeMap::new(),
+            history: BTreeMap::new(),
+            block_number: T::BlockNumber::zero(),
+            events: Vec::new(),
+            journal: Journal::new(),
         }
     }
 
     /// Get the owner(if any) of a claim.
     pub fn get_claim(&self, claim: &T::Content) -> Option<&T::AccountId> {
+        self.claims.get(claim).map(|info| &info.owner)
+    }
+
+    /// Get all the information(if any) about a claim.
+    pub fn get_claim_info(&self, claim: &T::Content) -> Option<&ClaimInfo<T>> {
         self.claims.get(claim)
     }
+
+    /// Check whether some content is claimed by anyone, like "get_claim(claim).is_some()".
+    /// An expired claim still counts until it is removed at the end of its block.
+    pub fn is_claimed(&self, claim: &T::Content) -> bool {
+        self.claims.contains_key(claim)
+    }
+
+    /// Get the number of claims made by every account together.
+    pub fn total_claims(&self) -> usize {
+        self.claims.len()
+    }
+
+    /// Get all the content claimed by "who", in sorted order.
+    pub fn claims_by_owner(&self, who: &T::AccountId) -> Vec<&T::Content> {
+        self.claims
+            .iter()
+            .filter(|(_, info)| info.owner == *who)
+            .map(|(claim, _)| claim)
+            .collect()
+    }
+
+    /// Get the number of claims owned by "who".
+    pub fn claims_owned(&self, who: &T::AccountId) -> u32 {
+        *self.claim_count.get(who).unwrap_or(&0)
+    }
+
+    /// Get every account which has owned a claim on some content, oldest first.
+    /// An account is listed again each time it gets the claim back.
+    pub fn ownership_history(&self, claim: &T::Content) -> &[T::AccountId] {
+        self.history.get(claim).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Check whether the claim on some content has expired.
+    /// A claim expiring at some block can be taken over by anyone from that block onwards.
+    pub fn is_expired(&self, claim: &T::Content) -> bool {
+        self.expiry.get(claim).is_some_and(|expires_at| *expires_at <= self.block_number)
+    }
+
+    /// Remove every claim expiring at or before block "now", returning how many were removed.
+    /// Anyone can sweep the expired claims, on top of them being swept at the end of every block.
+    /// Sweeping cannot reach the currency, so the deposit of a swept claim stays reserved.
+    pub fn sweep_expired(&mut self, now: T::BlockNumber) -> usize {
+        // Collect the claims to remove first, since we cannot remove them while iterating the map.
+        let expired = self.expiry
+            .iter()
+            .filter(|(_, expires_at)| **expires_at <= now)
+            .map(|(claim, _)| claim.clone())
+            .collect::<Vec<_>>() ;
+
+        for claim in &expired {
+            if let Some(info) = self.claims.get(claim) {
+                self.events.push(Event::Expired { owner: info.owner.clone(), claim: claim.clone() }) ;
+            }
+            self.remove_claim(claim) ;
+        }
+        expired.len()
+    }
+
+    /// Take all the events emitted since they were last taken, leaving no events behind.
+    pub fn take_events(&mut self) -> Vec<Event<T>> {
+        core::mem::take(&mut self.events)
+    }
+
+    /// Remove the claim on some content, along with everything stored about it.
+    fn remove_claim(&mut self, claim: &T::Content) {
+        if let Some(info) = self.claims.remove(claim) {
+            self.dec_claims(&info.owner) ;
+            self.journal.record(|| Undo::Claim(claim.clone(), Some(info))) ;
+        }
+        self.set_expiry(claim.clone(), None) ;
+    }
+
+    /// Remove the claim on some content like "remove_claim", returning the deposit of its owner.
+    fn remove_claim_with_deposit(&mut self, currency: &mut T::Currency, claim: &T::Content) -> DispatchResult {
+        if let Some(info) = self.claims.get(claim) {
+            currency.unreserve(&info.owner, T::CLAIM_DEPOSIT) ?;
+        }
+        self.remove_claim(claim) ;
+        Ok(())
+    }
+
+    /// Reserve the deposit of a claim from "who".
+    /// This fails with "CannotAffordDeposit" if "who" cannot afford it, in which case nothing is reserved.
+    fn reserve_deposit(currency: &mut T::Currency, who: &T::AccountId) -> DispatchResult {
+        currency.reserve(who, T::CLAIM_DEPOSIT).map_err(|_| DispatchError::CannotAffordDeposit)
+    }
+
+    /// Record that "who" owns one more claim.
+    /// This fails if "who" already owns the maximum number of claims.
+    fn inc_claims(&mut self, who: &T::AccountId) -> DispatchResult {
+        let count = self.claims_owned(who) ;
+        if count >= T::MAX_CLAIMS {
+            return Err(DispatchError::TooManyClaims);
+        }
+        self.journal.record(|| Undo::ClaimCount(who.clone(), self.claim_count.get(who).copied())) ;
+        self.claim_count.insert(who.clone(), count + 1) ;
+        Ok(())
+    }
+
+    /// Record that "who" owns one less claim.
+    fn dec_claims(&mut self, who: &T::AccountId) {
+        self.journal.record(|| Undo::ClaimCount(who.clone(), self.claim_count.get(who).copied())) ;
+        match self.claims_owned(who) {
+            0 | 1 => { self.claim_count.remove(who) ; },
+            count => { self.claim_count.insert(who.clone(), count - 1) ; },
+        }
+    }
+
+    /// Add "who" to the ownership history of the claim on some content, as its latest owner.
+    fn push_owner(&mut self, claim: &T::Content, who: &T::AccountId) {
+        self.history.entry(claim.clone()).or_default().push(who.clone()) ;
+        self.journal.record(|| Undo::History(claim.clone())) ;
+    }
+
+    /// Set the block at which the claim on some content expires, or remove its expiry if "expires_at"
+    /// is "None".
+    fn set_expiry(&mut self, claim: T::Content, expires_at: Option<T::BlockNumber>) {
+        self.journal.record(|| Undo::Expiry(claim.clone(), self.expiry.get(&claim).copied())) ;
+        crate::support::restore_entry(&mut self.expiry, claim, expires_at) ;
+    }
+
+    /// Reverse a mutation of the storage, as recorded by "undo" in the journal.
+    fn undo(&mut self, undo: Undo<T>) {
+        match undo {
+            Undo::Claim(claim, info) => crate::support::restore_entry(&mut self.claims, claim, info),
+            Undo::Owner(claim, owner) => {
+                if let Some(info) = self.claims.get_mut(&claim) {
+                    info.owner = owner ;
+                }
+            },
+            Undo::ClaimCount(who, count) => crate::support::restore_entry(&mut self.claim_count, who, count),
+            Undo::Expiry(claim, expires_at) => crate::support::restore_entry(&mut self.expiry, claim, expires_at),
+            Undo::BlockNumber(block_number) => self.block_number = block_number,
+            Undo::History(claim) => {
+                if let Some(history) = self.history.get_mut(&claim) {
+                    history.pop() ;
+                    if history.is_empty() {
+                        self.history.remove(&claim) ;
+                    }
+                }
+            },
+        }
+    }
+}
+
+// The mutations of this pallet are journaled while a block is executed, so the runtime can undo them.
+impl<T: Config> crate::support::Journaled for Pallet<T> {
+    fn start_journal(&mut self) {
+        self.journal.start() ;
+    }
+
+    fn stop_journal(&mut self) {
+        self.journal.stop() ;
+    }
+
+    fn undo_journal(&mut self) {
+        for undo in self.journal.take() {
+            self.undo(undo) ;
+        }
+    }
+
+    fn checkpoint_journal(&mut self) -> crate::support::JournalCheckpoint {
+        self.journal.checkpoint()
+    }
+
+    fn commit_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        self.journal.commit(checkpoint) ;
+    }
+
+    fn rollback_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        for undo in self.journal.rollback(checkpoint) {
+            self.undo(undo) ;
+        }
+    }
+}
+
+// The POE pallet keeps track of the current block number, so it can be recorded in new claims.
+// At the end of every block, it removes the claims which have expired.
+impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {
+    fn on_initialize(&mut self, block_number: T::BlockNumber) {
+        self.journal.record(|| Undo::BlockNumber(self.block_number)) ;
+        self.block_number = block_number ;
+    }
+
+    fn on_finalize(&mut self, block_number: T::BlockNumber) {
+        self.sweep_expired(block_number) ;
+    }
 }
 
 // Only these function will be called by the user from this pallet, so we will separate these from the other 
 // pallet functions and only add rust macro to this implementation of our Pallet.
-#[macros::call]
+// Every claim reserves a deposit from its owner, so the calls are given the currency of the runtime.
+#[macros::call(context = T::Currency)]
 impl<T: Config> Pallet<T> {
-    /// Create a claim on behalf of the 'caller'.
-    /// If the content is already claimed by some other user, the function will return an error.
+    /// Create a claim on behalf of the 'caller', reserving the claim deposit from them.
+    /// If the content is already claimed by some other user, or the caller cannot afford the deposit,
+    /// the function will return an error.
+    #[weight(5)]
     pub fn create_claim(
         &mut self, 
         caller: T::AccountId, 
+        currency: &mut T::Currency,
         claim: T::Content
     ) -> DispatchResult {
-        if self.claims.contains_key(&claim) {
-            return Err("This content is already been claimed.");
+        self.create_claim_with_note(caller, currency, claim, None)
+    }
+
+    /// Create a claim on behalf of the 'caller', with an optional note attached to it.
+    /// If the content is already claimed by some other user, or the caller cannot afford the deposit,
+    /// the function will return an error. Taking over an expired claim returns the deposit of its owner.
+    pub fn create_claim_with_note(
+        &mut self,
+        caller: T::AccountId,
+        currency: &mut T::Currency,
+        claim: T::Content,
+        note: Option<T::Content>
+    ) -> DispatchResult {
+        // Unbounded content would let a single claim bloat our storage.
+        let too_large = |content: &T::Content| content.len() > T::MAX_CONTENT_LEN as usize ;
+        if too_large(&claim) || note.as_ref().is_some_and(too_large) {
+            return Err(DispatchError::ContentTooLarge);
         }
-        self.claims.insert(claim, caller) ;
+
+        // An expired claim is treated as if it does not exist, so anyone can take it over.
+        if self.claims.contains_key(&claim) && !self.is_expired(&claim) {
+            return Err(DispatchError::AlreadyClaimed);
+        }
+        // The deposit is only reserved once nothing else can fail.
+        if self.claims_owned(&caller) >= T::MAX_CLAIMS {
+            return Err(DispatchError::TooManyClaims);
+        }
+        Self::reserve_deposit(currency, &caller) ?;
+        self.inc_claims(&caller) ?;
+        self.remove_claim_with_deposit(currency, &claim) ?;
+        // Any previous claim was removed above, so there is no information to put back when undoing this.
+        self.claims.insert(claim.clone(), ClaimInfo { owner: caller.clone(), block: self.block_number, note }) ;
+        self.journal.record(|| Undo::Claim(claim.clone(), None)) ;
+        self.push_owner(&claim, &caller) ;
+        self.events.push(Event::Created { owner: caller, claim }) ;
+        Ok(())
+    }
+
+    /// Create a claim on every one of the "claims" on behalf of the 'caller', all at once.
+    /// If any of the content is already claimed, appears twice, or cannot be claimed by the caller for
+    /// any other reason, the function will return an error and none of the claims are created.
+    #[weight((claims.len() as u64).saturating_mul(5))]
+    pub fn create_claims(
+        &mut self,
+        caller: T::AccountId,
+        currency: &mut T::Currency,
+        claims: Vec<T::Content>
+    ) -> DispatchResult {
+        // Every claim is checked before any of them is created, so that a failure leaves no claims behind.
+        let mut seen = std::collections::BTreeSet::new() ;
+        for claim in &claims {
+            if claim.len() > T::MAX_CONTENT_LEN as usize {
+                return Err(DispatchError::ContentTooLarge);
+            }
+            if !seen.insert(claim) || (self.claims.contains_key(claim) && !self.is_expired(claim)) {
+                return Err(DispatchError::AlreadyClaimed);
+            }
+        }
+        if self.claims_owned(&caller) as usize + claims.len() > T::MAX_CLAIMS as usize {
+            return Err(DispatchError::TooManyClaims);
+        }
+
+        // Only the deposits can fail now, once the caller cannot afford one more of them. The claims are
+        // created under a checkpoint of both journals, so that such a failure rolls back every claim
+        // created before it, along with its deposit.
+        let checkpoint = self.journal.checkpoint() ;
+        let currency_checkpoint = currency.checkpoint_journal() ;
+        let events = self.events.len() ;
+        for claim in claims {
+            if let Err(e) = self.create_claim_with_note(caller.clone(), currency, claim, None) {
+                for undo in self.journal.rollback(checkpoint) {
+                    self.undo(undo) ;
+                }
+                currency.rollback_journal(currency_checkpoint) ;
+                self.events.truncate(events) ;
+                return Err(e) ;
+            }
+        }
+        self.journal.commit(checkpoint) ;
+        currency.commit_journal(currency_checkpoint) ;
         Ok(())
     }
 
-    /// Revoke an existing claim on some content.
+    /// Create a claim on behalf of the 'caller', which expires at block "expires_at".
+    /// If the content is already claimed by some other user, or the expiry is not in the future,
+    /// the function will return an error.
+    pub fn create_claim_with_expiry(
+        &mut self,
+        caller: T::AccountId,
+        currency: &mut T::Currency,
+        claim: T::Content,
+        expires_at: T::BlockNumber
+    ) -> DispatchResult {
+        if expires_at <= self.block_number {
+            return Err(DispatchError::ExpiryInPast);
+        }
+        self.create_claim(caller, currency, claim.clone()) ?;
+        self.set_expiry(claim, Some(expires_at)) ;
+        Ok(())
+    }
+
+    /// Revoke an existing claim on some content, returning its deposit to the caller.
     /// This function should only succeed if the caller is owner of an existing claim.
     /// This function will result into an error if the claim does not exist, or if the caller is not the owner of the claim.
     pub fn revoke_claim(
         &mut self,
         caller: T::AccountId,
+        currency: &mut T::Currency,
         claim: T::Content
     ) -> DispatchResult {
         // Get the owner of the claim to be revoked.
-        let owner = self.get_claim(&claim).ok_or("Claim does not exist.") ?;
+        let owner = self.get_claim(&claim).ok_or(DispatchError::ClaimNotFound) ?;
 
         // Check whether the caller is the owner of the claim.
         if *owner != caller{
-            return Err("This content is owned by some other user.");
+            return Err(DispatchError::NotOwner);
         }
 
         // Remove the claim if above check passes.
-        self.claims.remove(&claim) ;
+        self.remove_claim_with_deposit(currency, &claim) ?;
+        self.events.push(Event::Revoked { owner: caller, claim }) ;
+        Ok(())
+    }
+
+    /// Revoke every claim owned by the caller, returning their deposits.
+    pub fn revoke_all(&mut self, caller: T::AccountId, currency: &mut T::Currency) -> DispatchResult {
+        // Collect the claims to remove first, since we cannot remove them while iterating the map.
+        let owned = self.claims
+            .iter()
+            .filter(|(_, info)| info.owner == caller)
+            .map(|(claim, _)| claim.clone())
+            .collect::<Vec<_>>() ;
+
+        for claim in owned {
+            self.remove_claim_with_deposit(currency, &claim) ?;
+            self.events.push(Event::Revoked { owner: caller.clone(), claim }) ;
+        }
+        Ok(())
+    }
+
+    /// Transfer an existing claim on some content to a "new_owner", who takes over its deposit.
+    /// The deposit is reserved from the new owner and returned to the caller.
+    /// This function will result into an error if the claim does not exist, if the caller is not the owner of the claim,
+    /// or if the new owner cannot afford the deposit.
+    pub fn transfer_claim(
+        &mut self,
+        caller: T::AccountId,
+        currency: &mut T::Currency,
+        claim: T::Content,
+        new_owner: T::AccountId
+    ) -> DispatchResult {
+        // Get the owner of the claim to be transferred.
+        let owner = self.get_claim(&claim).ok_or(DispatchError::ClaimNotFound) ?;
+
+        // Check whether the caller is the owner of the claim.
+        if *owner != caller {
+            return Err(DispatchError::NotOwner);
+        }
+
+        // Hand the claim over to the new owner if above check passes, moving the claim count along.
+        // A claim transferred to its own owner does not change hands, so its history stays the same.
+        if new_owner != caller {
+            if self.claims_owned(&new_owner) >= T::MAX_CLAIMS {
+                return Err(DispatchError::TooManyClaims);
+            }
+            Self::reserve_deposit(currency, &new_owner) ?;
+            currency.unreserve(&caller, T::CLAIM_DEPOSIT) ?;
+            self.inc_claims(&new_owner) ?;
+            self.dec_claims(&caller) ;
+            self.push_owner(&claim, &new_owner) ;
+        }
+        if let Some(info) = self.claims.get_mut(&claim) {
+            let owner = core::mem::replace(&mut info.owner, new_owner.clone()) ;
+            self.journal.record(|| Undo::Owner(claim.clone(), owner)) ;
+        }
+        self.events.push(Event::Transferred { from: caller, to: new_owner, claim }) ;
         Ok(())
     }
 }
@@ -108,20 +580,46 @@ impl<T: Config> Pallet<T> {
 
 #[cfg(test)]
 mod test {
+    use crate::support::{DispatchError, Hooks} ;
+
+    // Claims are free, so that only the tests of the claim deposit need to give accounts a balance.
+    #[derive(Debug, Clone, PartialEq)]
     struct TestConfig ;
     impl crate::proof_of_existence::Config for TestConfig {
         type Content = &'static str ;
+        const MAX_CLAIMS: u32 = 3 ;
+        const MAX_CONTENT_LEN: u32 = 8 ;
+        type Currency = crate::balances::Pallet<TestConfig> ;
+        const CLAIM_DEPOSIT: u128 = 0 ;
     }
 
     impl crate::system::Config for TestConfig {
         type AccountId = String ;
         type BlockNumber = u32 ;
         type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
+    }
+
+    impl crate::balances::Config for TestConfig {
+        type Balance = u128 ;
+        const EXISTENTIAL_DEPOSIT: u128 = 1 ;
+        const TRANSFER_FEE: u128 = 0 ;
+        const MINIMUM_TRANSFER: u128 = 0 ;
+        fn fee_account() -> String {
+            "fees".to_string()
+        }
+        type AccountStore = std::collections::BTreeMap<String, crate::balances::AccountData<u128>> ;
     }
 
     #[test]
     fn init_proof_of_existence() {
         let mut proof_of_existence = crate::proof_of_existence::Pallet::<TestConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
         
         let alice = "alice".to_string() ;
         let bob = "bob".to_string() ;
@@ -130,26 +628,558 @@ mod test {
         assert_eq!(proof_of_existence.get_claim(&"hello"), None) ;
         
         // Creating claim for 'alice'.
-        let _ = proof_of_existence.create_claim(alice.clone(), "hello");
+        let _ = proof_of_existence.create_claim(alice.clone(), &mut balances, "hello");
         assert_eq!(proof_of_existence.get_claim(&"hello"), Some(&alice)) ;
 
         // Since alice is owner of claim, "hello", bob cannot claim this content.
         assert_eq!(
-            proof_of_existence.create_claim(bob.clone(), "hello"),
-            Err("This content is already been claimed.")
+            proof_of_existence.create_claim(bob.clone(), &mut balances, "hello"),
+            Err(DispatchError::AlreadyClaimed)
         ) ;
 
         // Since alice is owner of claim, "hello", bob cannot revoke this claim.
         assert_eq!(
-            proof_of_existence.revoke_claim(bob.clone(), "hello"),
-            Err("This content is owned by some other user.")
+            proof_of_existence.revoke_claim(bob.clone(), &mut balances, "hello"),
+            Err(DispatchError::NotOwner)
         ) ;
         
         // Revoke claim "hello" for alice.
-        let _ = proof_of_existence.revoke_claim(alice, "hello") ;
+        let _ = proof_of_existence.revoke_claim(alice, &mut balances, "hello") ;
         
         // Now, bob can claim "hello".
-        let _ = proof_of_existence.create_claim(bob.clone(), "hello");
+        let _ = proof_of_existence.create_claim(bob.clone(), &mut balances, "hello");
         assert_eq!(proof_of_existence.get_claim(&"hello"), Some(&bob)) ;
     }
-}
\ No newline at end of file
+
+    // The same pallet, but claiming owned bytes rather than static strings.
+    #[derive(Debug, PartialEq)]
+    struct BytesConfig ;
+    impl crate::proof_of_existence::Config for BytesConfig {
+        type Content = Vec<u8> ;
+        const MAX_CLAIMS: u32 = 3 ;
+        const MAX_CONTENT_LEN: u32 = 8 ;
+        type Currency = crate::balances::Pallet<BytesConfig> ;
+        const CLAIM_DEPOSIT: u128 = 0 ;
+    }
+
+    impl crate::system::Config for BytesConfig {
+        type AccountId = String ;
+        type BlockNumber = u32 ;
+        type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
+    }
+
+    impl crate::balances::Config for BytesConfig {
+        type Balance = u128 ;
+        const EXISTENTIAL_DEPOSIT: u128 = 1 ;
+        const TRANSFER_FEE: u128 = 0 ;
+        const MINIMUM_TRANSFER: u128 = 0 ;
+        fn fee_account() -> String {
+            "fees".to_string()
+        }
+        type AccountStore = std::collections::BTreeMap<String, crate::balances::AccountData<u128>> ;
+    }
+
+    #[test]
+    fn owned_content() {
+        let mut proof_of_existence = crate::proof_of_existence::Pallet::<BytesConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<BytesConfig>::new() ;
+
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        // The content is only known at runtime, like the bytes of an uploaded document.
+        let document = format!("doc-{}", 42).into_bytes() ;
+
+        assert_eq!(proof_of_existence.create_claim(alice.clone(), &mut balances, document.clone()), Ok(())) ;
+        assert_eq!(proof_of_existence.get_claim(&document), Some(&alice)) ;
+        assert_eq!(proof_of_existence.get_claim(&b"doc-42".to_vec()), Some(&alice)) ;
+        assert_eq!(proof_of_existence.revoke_claim(bob.clone(), &mut balances, document.clone()), Err(DispatchError::NotOwner)) ;
+
+        assert_eq!(proof_of_existence.revoke_claim(alice.clone(), &mut balances, document.clone()), Ok(())) ;
+        assert_eq!(proof_of_existence.get_claim(&document), None) ;
+        assert_eq!(
+            proof_of_existence.take_events(),
+            vec![
+                super::Event::Created { owner: alice.clone(), claim: document.clone() },
+                super::Event::Revoked { owner: alice.clone(), claim: document.clone() },
+            ]
+        ) ;
+        assert_eq!(proof_of_existence.ownership_history(&document), &[alice]) ;
+    }
+
+    // Every claim reserves a deposit of 10 from its owner.
+    #[derive(Debug, Clone, PartialEq)]
+    struct DepositConfig ;
+    impl crate::proof_of_existence::Config for DepositConfig {
+        type Content = &'static str ;
+        const MAX_CLAIMS: u32 = 3 ;
+        const MAX_CONTENT_LEN: u32 = 8 ;
+        type Currency = crate::balances::Pallet<DepositConfig> ;
+        const CLAIM_DEPOSIT: u128 = 10 ;
+    }
+
+    impl crate::system::Config for DepositConfig {
+        type AccountId = String ;
+        type BlockNumber = u32 ;
+        type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
+    }
+
+    impl crate::balances::Config for DepositConfig {
+        type Balance = u128 ;
+        const EXISTENTIAL_DEPOSIT: u128 = 1 ;
+        const TRANSFER_FEE: u128 = 0 ;
+        const MINIMUM_TRANSFER: u128 = 0 ;
+        fn fee_account() -> String {
+            "fees".to_string()
+        }
+        type AccountStore = std::collections::BTreeMap<String, crate::balances::AccountData<u128>> ;
+    }
+
+    #[test]
+    fn claim_deposit() {
+        let mut proof_of_existence = crate::proof_of_existence::Pallet::<DepositConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<DepositConfig>::new() ;
+
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 25) ;
+        let _ = balances.mint(&bob, 5) ;
+
+        // An account which cannot afford the deposit cannot claim anything.
+        assert_eq!(
+            proof_of_existence.create_claim(bob.clone(), &mut balances, "hello"),
+            Err(DispatchError::CannotAffordDeposit)
+        ) ;
+        assert_eq!(proof_of_existence.get_claim(&"hello"), None) ;
+        assert_eq!(balances.balance(&bob), 5) ;
+
+        // Every claim reserves the deposit from its owner.
+        assert_eq!(proof_of_existence.create_claim(alice.clone(), &mut balances, "hello"), Ok(())) ;
+        assert_eq!(balances.balance(&alice), 15) ;
+        assert_eq!(balances.reserved_balance(&alice), 10) ;
+
+        // Either every deposit of a batch of claims can be afforded, or none of the claims are created.
+        assert_eq!(
+            proof_of_existence.create_claims(alice.clone(), &mut balances, vec!["a", "b"]),
+            Err(DispatchError::CannotAffordDeposit)
+        ) ;
+        assert_eq!(proof_of_existence.total_claims(), 1) ;
+        assert!(!proof_of_existence.is_claimed(&"a")) ;
+        assert_eq!(balances.balance(&alice), 15) ;
+        assert_eq!(balances.reserved_balance(&alice), 10) ;
+        assert_eq!(proof_of_existence.take_events(), vec![super::Event::Created { owner: alice.clone(), claim: "hello" }]) ;
+
+        // The new owner of a claim takes over its deposit, if they can afford it.
+        assert_eq!(
+            proof_of_existence.transfer_claim(alice.clone(), &mut balances, "hello", bob.clone()),
+            Err(DispatchError::CannotAffordDeposit)
+        ) ;
+        let _ = balances.mint(&bob, 5) ;
+        assert_eq!(proof_of_existence.transfer_claim(alice.clone(), &mut balances, "hello", bob.clone()), Ok(())) ;
+        assert_eq!(balances.reserved_balance(&alice), 0) ;
+        assert_eq!(balances.balance(&alice), 25) ;
+        assert_eq!(balances.reserved_balance(&bob), 10) ;
+
+        // Revoking a claim refunds its deposit.
+        assert_eq!(proof_of_existence.revoke_claim(bob.clone(), &mut balances, "hello"), Ok(())) ;
+        assert_eq!(balances.reserved_balance(&bob), 0) ;
+        assert_eq!(balances.balance(&bob), 10) ;
+        assert_eq!(balances.total_issuance(), 35) ;
+    }
+
+    #[test]
+    fn expired_claim_deposit() {
+        let mut proof_of_existence = crate::proof_of_existence::Pallet::<DepositConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<DepositConfig>::new() ;
+
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 50) ;
+        let _ = balances.mint(&bob, 50) ;
+
+        // Taking over an expired claim returns the deposit of its previous owner right away.
+        let _ = proof_of_existence.create_claim_with_expiry(alice.clone(), &mut balances, "a", 2) ;
+        proof_of_existence.on_initialize(2) ;
+        assert_eq!(balances.reserved_balance(&alice), 10) ;
+        assert_eq!(proof_of_existence.create_claim(bob.clone(), &mut balances, "a"), Ok(())) ;
+        assert_eq!(balances.reserved_balance(&alice), 0) ;
+        assert_eq!(balances.balance(&alice), 50) ;
+        assert_eq!(balances.reserved_balance(&bob), 10) ;
+    }
+
+    #[test]
+    fn transfer_claim() {
+        let mut proof_of_existence = crate::proof_of_existence::Pallet::<TestConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+
+        // A claim which does not exist cannot be transferred.
+        assert_eq!(
+            proof_of_existence.transfer_claim(alice.clone(), &mut balances, "hello", bob.clone()),
+            Err(DispatchError::ClaimNotFound)
+        ) ;
+
+        let _ = proof_of_existence.create_claim(alice.clone(), &mut balances, "hello") ;
+
+        // Only the owner of the claim can transfer it.
+        assert_eq!(
+            proof_of_existence.transfer_claim(bob.clone(), &mut balances, "hello", bob.clone()),
+            Err(DispatchError::NotOwner)
+        ) ;
+        assert_eq!(proof_of_existence.get_claim(&"hello"), Some(&alice)) ;
+
+        // Alice transfers her claim to bob.
+        assert_eq!(proof_of_existence.transfer_claim(alice.clone(), &mut balances, "hello", bob.clone()), Ok(())) ;
+        assert_eq!(proof_of_existence.get_claim(&"hello"), Some(&bob)) ;
+    }
+
+    #[test]
+    fn ownership_history() {
+        let mut proof_of_existence = crate::proof_of_existence::Pallet::<TestConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let charlie = "charlie".to_string() ;
+
+        assert_eq!(proof_of_existence.ownership_history(&"hello"), &[] as &[String]) ;
+        let _ = proof_of_existence.create_claim(alice.clone(), &mut balances, "hello") ;
+        let _ = proof_of_existence.transfer_claim(alice.clone(), &mut balances, "hello", bob.clone()) ;
+        assert_eq!(proof_of_existence.ownership_history(&"hello"), &[alice.clone(), bob.clone()]) ;
+
+        // Transferring a claim to its own owner, or failing to transfer it, leaves the history alone.
+        let _ = proof_of_existence.transfer_claim(bob.clone(), &mut balances, "hello", bob.clone()) ;
+        let _ = proof_of_existence.transfer_claim(alice.clone(), &mut balances, "hello", charlie.clone()) ;
+        assert_eq!(proof_of_existence.ownership_history(&"hello"), &[alice.clone(), bob.clone()]) ;
+
+        // The history continues after the claim is revoked and created again.
+        let _ = proof_of_existence.revoke_claim(bob.clone(), &mut balances, "hello") ;
+        assert_eq!(proof_of_existence.ownership_history(&"hello"), &[alice.clone(), bob.clone()]) ;
+        let _ = proof_of_existence.create_claim(charlie.clone(), &mut balances, "hello") ;
+        assert_eq!(proof_of_existence.ownership_history(&"hello"), &[alice, bob, charlie]) ;
+    }
+
+    #[test]
+    fn total_claims() {
+        let mut proof_of_existence = crate::proof_of_existence::Pallet::<TestConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+
+        assert_eq!(proof_of_existence.total_claims(), 0) ;
+        let _ = proof_of_existence.create_claim(alice.clone(), &mut balances, "hello") ;
+        let _ = proof_of_existence.create_claim(bob.clone(), &mut balances, "world") ;
+        assert_eq!(proof_of_existence.total_claims(), 2) ;
+
+        // A failed claim is not counted.
+        let _ = proof_of_existence.create_claim(bob.clone(), &mut balances, "hello") ;
+        assert_eq!(proof_of_existence.total_claims(), 2) ;
+
+        let _ = proof_of_existence.revoke_claim(alice.clone(), &mut balances, "hello") ;
+        assert_eq!(proof_of_existence.total_claims(), 1) ;
+
+        // "is_claimed" agrees with "get_claim".
+        for claim in ["hello", "world", "other"] {
+            assert_eq!(proof_of_existence.is_claimed(&claim), proof_of_existence.get_claim(&claim).is_some()) ;
+        }
+        assert!(proof_of_existence.is_claimed(&"world")) ;
+        assert!(!proof_of_existence.is_claimed(&"hello")) ;
+    }
+
+    #[test]
+    fn create_claims() {
+        let mut proof_of_existence = crate::proof_of_existence::Pallet::<TestConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+
+        assert_eq!(proof_of_existence.create_claims(alice.clone(), &mut balances, vec!["a", "b"]), Ok(())) ;
+        assert_eq!(proof_of_existence.claims_by_owner(&alice), vec![&"a", &"b"]) ;
+        assert_eq!(proof_of_existence.claims_owned(&alice), 2) ;
+
+        // A single conflict fails the whole batch, leaving the claims as they were.
+        let before = proof_of_existence.clone() ;
+        assert_eq!(proof_of_existence.create_claims(bob.clone(), &mut balances, vec!["c", "b", "d"]), Err(DispatchError::AlreadyClaimed)) ;
+        assert_eq!(proof_of_existence.create_claims(bob.clone(), &mut balances, vec!["c", "c"]), Err(DispatchError::AlreadyClaimed)) ;
+        assert_eq!(
+            proof_of_existence.create_claims(bob.clone(), &mut balances, vec!["c", "too large"]),
+            Err(DispatchError::ContentTooLarge)
+        ) ;
+        assert_eq!(proof_of_existence.create_claims(alice.clone(), &mut balances, vec!["c", "d"]), Err(DispatchError::TooManyClaims)) ;
+        assert_eq!(proof_of_existence, before) ;
+        assert!(!proof_of_existence.is_claimed(&"c")) ;
+
+        // Creating the claims weighs as much as creating every one of them on its own.
+        assert_eq!(super::Call::<TestConfig>::create_claims { claims: vec!["c", "d"] }.weight(), crate::support::Weight(10)) ;
+        assert_eq!(super::Call::<TestConfig>::create_claim { claim: "c" }.weight(), crate::support::Weight(5)) ;
+    }
+
+    #[test]
+    fn max_claims() {
+        let mut proof_of_existence = crate::proof_of_existence::Pallet::<TestConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+
+        // Alice can create claims up to the maximum.
+        let _ = proof_of_existence.create_claim(alice.clone(), &mut balances, "hello") ;
+        let _ = proof_of_existence.create_claim(alice.clone(), &mut balances, "world") ;
+        let _ = proof_of_existence.create_claim(alice.clone(), &mut balances, "foo") ;
+        assert_eq!(proof_of_existence.claims_owned(&alice), 3) ;
+        assert_eq!(proof_of_existence.create_claim(alice.clone(), &mut balances, "again"), Err(DispatchError::TooManyClaims)) ;
+        assert_eq!(proof_of_existence.get_claim(&"again"), None) ;
+
+        // Revoking a claim frees up room for a new one.
+        let _ = proof_of_existence.revoke_claim(alice.clone(), &mut balances, "hello") ;
+        assert_eq!(proof_of_existence.claims_owned(&alice), 2) ;
+        assert_eq!(proof_of_existence.create_claim(alice.clone(), &mut balances, "again"), Ok(())) ;
+
+        // Transferring a claim moves the count to the new owner.
+        let _ = proof_of_existence.transfer_claim(alice.clone(), &mut balances, "world", bob.clone()) ;
+        assert_eq!(proof_of_existence.claims_owned(&alice), 2) ;
+        assert_eq!(proof_of_existence.claims_owned(&bob), 1) ;
+    }
+
+    #[test]
+    fn claim_info() {
+        let mut proof_of_existence = crate::proof_of_existence::Pallet::<TestConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+
+        let alice = "alice".to_string() ;
+
+        // The block at which a claim is created is recorded, along with the note.
+        proof_of_existence.on_initialize(3) ;
+        let _ = proof_of_existence.create_claim_with_note(alice.clone(), &mut balances, "hello", Some("my note")) ;
+        assert_eq!(
+            proof_of_existence.get_claim_info(&"hello"),
+            Some(&crate::proof_of_existence::ClaimInfo { owner: alice.clone(), block: 3, note: Some("my note") })
+        ) ;
+        assert_eq!(proof_of_existence.get_claim(&"hello"), Some(&alice)) ;
+
+        // Claims created without a note have none.
+        proof_of_existence.on_initialize(4) ;
+        let _ = proof_of_existence.create_claim(alice.clone(), &mut balances, "world") ;
+        let info = proof_of_existence.get_claim_info(&"world").unwrap() ;
+        assert_eq!(info.block, 4) ;
+        assert_eq!(info.note, None) ;
+    }
+
+    #[test]
+    fn claim_expiry() {
+        let mut proof_of_existence = crate::proof_of_existence::Pallet::<TestConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+
+        // Claims cannot expire in the past.
+        proof_of_existence.on_initialize(1) ;
+        assert_eq!(
+            proof_of_existence.create_claim_with_expiry(alice.clone(), &mut balances, "hello", 1),
+            Err(DispatchError::ExpiryInPast)
+        ) ;
+
+        // Alice creates a claim which expires at block 5.
+        assert_eq!(proof_of_existence.create_claim_with_expiry(alice.clone(), &mut balances, "hello", 5), Ok(())) ;
+
+        // Before the claim expires, bob cannot take it over.
+        proof_of_existence.on_initialize(4) ;
+        assert_eq!(
+            proof_of_existence.create_claim(bob.clone(), &mut balances, "hello"),
+            Err(DispatchError::AlreadyClaimed)
+        ) ;
+
+        // After the claim expires, bob can claim the same content.
+        proof_of_existence.on_initialize(6) ;
+        assert!(proof_of_existence.is_expired(&"hello")) ;
+        assert_eq!(proof_of_existence.create_claim(bob.clone(), &mut balances, "hello"), Ok(())) ;
+        assert_eq!(proof_of_existence.get_claim(&"hello"), Some(&bob)) ;
+        assert!(!proof_of_existence.is_expired(&"hello")) ;
+        assert_eq!(proof_of_existence.claims_owned(&alice), 0) ;
+    }
+
+    #[test]
+    fn revoke_all() {
+        let mut proof_of_existence = crate::proof_of_existence::Pallet::<TestConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+
+        let _ = proof_of_existence.create_claim(alice.clone(), &mut balances, "a") ;
+        let _ = proof_of_existence.create_claim(bob.clone(), &mut balances, "b") ;
+        let _ = proof_of_existence.create_claim(alice.clone(), &mut balances, "c") ;
+        let _ = proof_of_existence.create_claim(alice.clone(), &mut balances, "d") ;
+
+        // Revoking all of alice's claims leaves only bob's claims behind.
+        assert_eq!(proof_of_existence.revoke_all(alice.clone(), &mut balances), Ok(())) ;
+        assert_eq!(proof_of_existence.claims.keys().collect::<Vec<_>>(), vec![&"b"]) ;
+        assert_eq!(proof_of_existence.get_claim(&"b"), Some(&bob)) ;
+        assert_eq!(proof_of_existence.claims_owned(&alice), 0) ;
+    }
+
+    #[test]
+    fn max_content_len() {
+        let mut proof_of_existence = crate::proof_of_existence::Pallet::<TestConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+
+        let alice = "alice".to_string() ;
+
+        // Content of exactly the maximum length can be claimed.
+        assert_eq!(proof_of_existence.create_claim(alice.clone(), &mut balances, "12345678"), Ok(())) ;
+
+        // Content just over the maximum length cannot be claimed, and neither can such a note.
+        assert_eq!(proof_of_existence.create_claim(alice.clone(), &mut balances, "123456789"), Err(DispatchError::ContentTooLarge)) ;
+        assert_eq!(
+            proof_of_existence.create_claim_with_note(alice.clone(), &mut balances, "hello", Some("123456789")),
+            Err(DispatchError::ContentTooLarge)
+        ) ;
+        assert_eq!(proof_of_existence.claims_owned(&alice), 1) ;
+    }
+
+    #[test]
+    fn claims_by_owner() {
+        let mut proof_of_existence = crate::proof_of_existence::Pallet::<TestConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+
+        let _ = proof_of_existence.create_claim(alice.clone(), &mut balances, "d") ;
+        let _ = proof_of_existence.create_claim(bob.clone(), &mut balances, "c") ;
+        let _ = proof_of_existence.create_claim(alice.clone(), &mut balances, "b") ;
+        let _ = proof_of_existence.create_claim(bob.clone(), &mut balances, "a") ;
+
+        // Each owner gets back their own claims, sorted by content.
+        assert_eq!(proof_of_existence.claims_by_owner(&alice), vec![&"b", &"d"]) ;
+        assert_eq!(proof_of_existence.claims_by_owner(&bob), vec![&"a", &"c"]) ;
+        assert_eq!(proof_of_existence.claims_by_owner(&"charlie".to_string()), Vec::<&&str>::new()) ;
+    }
+
+    #[test]
+    fn claim_events() {
+        let mut proof_of_existence = crate::proof_of_existence::Pallet::<TestConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+
+        // Failed calls emit no events.
+        let _ = proof_of_existence.revoke_claim(alice.clone(), &mut balances, "hello") ;
+
+        let _ = proof_of_existence.create_claim(alice.clone(), &mut balances, "hello") ;
+        let _ = proof_of_existence.transfer_claim(alice.clone(), &mut balances, "hello", bob.clone()) ;
+        let _ = proof_of_existence.revoke_claim(bob.clone(), &mut balances, "hello") ;
+        assert_eq!(
+            proof_of_existence.take_events(),
+            vec![
+                super::Event::Created { owner: alice.clone(), claim: "hello" },
+                super::Event::Transferred { from: alice.clone(), to: bob.clone(), claim: "hello" },
+                super::Event::Revoked { owner: bob.clone(), claim: "hello" },
+            ]
+        ) ;
+
+        // Taking the events empties the buffer.
+        assert_eq!(proof_of_existence.take_events(), vec![]) ;
+    }
+
+    #[test]
+    fn sweep_expired_claims() {
+        let mut proof_of_existence = crate::proof_of_existence::Pallet::<TestConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+
+        let alice = "alice".to_string() ;
+
+        proof_of_existence.on_initialize(1) ;
+        let _ = proof_of_existence.create_claim_with_expiry(alice.clone(), &mut balances, "hello", 3) ;
+        let _ = proof_of_existence.create_claim(alice.clone(), &mut balances, "world") ;
+        let _ = proof_of_existence.take_events() ;
+
+        // Claims which have not expired yet are kept.
+        proof_of_existence.on_finalize(2) ;
+        assert_eq!(proof_of_existence.get_claim(&"hello"), Some(&alice)) ;
+        assert_eq!(proof_of_existence.take_events(), vec![]) ;
+
+        // At the end of the block in which it expires, the claim is removed entirely.
+        proof_of_existence.on_finalize(3) ;
+        assert_eq!(proof_of_existence.get_claim(&"hello"), None) ;
+        assert!(!proof_of_existence.is_expired(&"hello")) ;
+        assert_eq!(proof_of_existence.get_claim(&"world"), Some(&alice)) ;
+        assert_eq!(proof_of_existence.claims_owned(&alice), 1) ;
+        assert_eq!(
+            proof_of_existence.take_events(),
+            vec![super::Event::Expired { owner: alice.clone(), claim: "hello" }]
+        ) ;
+    }
+
+    #[test]
+    fn sweep_expired() {
+        let mut proof_of_existence = crate::proof_of_existence::Pallet::<TestConfig>::new() ;
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+
+        let _ = proof_of_existence.create_claim_with_expiry(alice.clone(), &mut balances, "a", 2) ;
+        let _ = proof_of_existence.create_claim_with_expiry(bob.clone(), &mut balances, "b", 4) ;
+        let _ = proof_of_existence.create_claim_with_expiry(alice.clone(), &mut balances, "c", 5) ;
+        let _ = proof_of_existence.create_claim(bob.clone(), &mut balances, "d") ;
+        let _ = proof_of_existence.take_events() ;
+
+        // Only the claims expiring at or before the given block are removed.
+        assert_eq!(proof_of_existence.sweep_expired(1), 0) ;
+        assert_eq!(proof_of_existence.sweep_expired(4), 2) ;
+        assert_eq!(proof_of_existence.claims_by_owner(&alice), vec![&"c"]) ;
+        assert_eq!(proof_of_existence.claims_by_owner(&bob), vec![&"d"]) ;
+        assert_eq!(proof_of_existence.claims_owned(&bob), 1) ;
+        assert_eq!(
+            proof_of_existence.take_events(),
+            vec![
+                super::Event::Expired { owner: alice.clone(), claim: "a" },
+                super::Event::Expired { owner: bob.clone(), claim: "b" },
+            ]
+        ) ;
+
+        // Swept claims are gone, so sweeping again removes nothing.
+        assert_eq!(proof_of_existence.sweep_expired(4), 0) ;
+        assert_eq!(proof_of_existence.total_claims(), 2) ;
+    }
+
+    #[test]
+    fn call_names() {
+        // Every call is named after the function it is routed to.
+        let call = crate::proof_of_existence::Call::<TestConfig>::revoke_claim { claim: "hello" } ;
+        assert_eq!(call.name(), "revoke_claim") ;
+        let call = crate::proof_of_existence::Call::<TestConfig>::create_claim { claim: "hello" } ;
+        assert_eq!(call.name(), "create_claim") ;
+    }
+
+    #[test]
+    fn call_weights() {
+        use crate::support::{DispatchWith, Weight} ;
+
+        // Calls weigh what their function is annotated with, or 1 without an annotation.
+        let proof_of_existence = crate::proof_of_existence::Pallet::<TestConfig>::new() ;
+        let call = crate::proof_of_existence::Call::<TestConfig>::create_claim { claim: "hello" } ;
+        assert_eq!(call.weight(), Weight(5)) ;
+        assert_eq!(proof_of_existence.weight(&call), Weight(5)) ;
+        let call = crate::proof_of_existence::Call::<TestConfig>::revoke_claim { claim: "hello" } ;
+        assert_eq!(call.weight(), Weight(1)) ;
+    }
+}
@@ -0,0 +1,224 @@
+use core::fmt::Debug ;
+use std::collections::BTreeMap ;
+use num::traits::Zero ;
+use crate::support::{DispatchError, DispatchResult, Journal} ;
+
+/// The Config trait for the Scheduler pallet.
+/// It contains the calls which can be scheduled.
+pub trait Config: crate::system::Config {
+    /// The calls of the runtime, which can be scheduled to be made at some future block.
+    type RuntimeCall: Debug + Clone + PartialEq ;
+}
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<T: Config> {
+    /// Some call was scheduled by "who", to be made at block "at".
+    Scheduled {
+        who: T::AccountId,
+        at: T::BlockNumber,
+    },
+}
+
+/// The operations undoing the mutations of the storage of this pallet, as recorded in its journal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Undo<T: Config> {
+    /// Put the calls scheduled for a block back, or remove them if there were none.
+    #[allow(clippy::type_complexity)]
+    Agenda(T::BlockNumber, Option<Vec<(T::AccountId, T::RuntimeCall)>>),
+    /// Remove the call scheduled last for a block.
+    Scheduled(T::BlockNumber),
+    /// Set the current block number back to the given one.
+    BlockNumber(T::BlockNumber),
+}
+
+/// This is the Scheduler pallet.
+/// It keeps calls until the block they are scheduled for, at which point the runtime makes them on
+/// behalf of the account which scheduled them.
+#[derive(Debug, Clone, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "T::AccountId: serde::Serialize + serde::de::DeserializeOwned, T::BlockNumber: serde::Serialize + serde::de::DeserializeOwned, T::RuntimeCall: serde::Serialize + serde::de::DeserializeOwned"))]
+pub struct Pallet<T: Config> {
+    /// A map from a block number to the calls scheduled for that block, along with the account which
+    /// scheduled each of them, in the order they were scheduled.
+    #[allow(clippy::type_complexity)]
+    agenda: BTreeMap<T::BlockNumber, Vec<(T::AccountId, T::RuntimeCall)>>,
+    /// The current block number, as last seen through "on_initialize".
+    block_number: T::BlockNumber,
+    /// The calls of the current block, waiting to be taken by the runtime.
+    /// They are taken right after "on_initialize", so they are never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    queued: Vec<(T::AccountId, T::RuntimeCall)>,
+    /// The events emitted since they were last taken.
+    /// Events are not part of the state, so they are never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    events: Vec<Event<T>>,
+    /// The journal of the mutations made while executing the last block.
+    /// Like events, the journal is not part of the state.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    journal: Journal<Undo<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+    /// Create a new instance of the scheduler pallet.
+    pub fn new() -> Self {
+        Self {
+            agenda: BTreeMap::new(),
+            block_number: T::BlockNumber::zero(),
+            queued: Vec::new(),
+            events: Vec::new(),
+            journal: Journal::new(),
+        }
+    }
+
+    /// Get the calls scheduled for block "at", along with the account which scheduled each of them.
+    pub fn agenda(&self, at: T::BlockNumber) -> &[(T::AccountId, T::RuntimeCall)] {
+        self.agenda.get(&at).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Take all the events emitted since they were last taken, leaving no events behind.
+    pub fn take_events(&mut self) -> Vec<Event<T>> {
+        core::mem::take(&mut self.events)
+    }
+
+    /// Reverse a mutation of the storage, as recorded by "undo" in the journal.
+    fn undo(&mut self, undo: Undo<T>) {
+        match undo {
+            Undo::Agenda(at, calls) => crate::support::restore_entry(&mut self.agenda, at, calls),
+            Undo::Scheduled(at) => {
+                if let Some(calls) = self.agenda.get_mut(&at) {
+                    calls.pop() ;
+                    if calls.is_empty() {
+                        self.agenda.remove(&at) ;
+                    }
+                }
+            },
+            Undo::BlockNumber(block_number) => self.block_number = block_number,
+        }
+    }
+}
+
+// The calls scheduled for a block are made by the runtime, on behalf of the accounts which scheduled
+// them.
+impl<T: Config> crate::support::QueuedCalls<T::AccountId, T::RuntimeCall> for Pallet<T> {
+    fn take_queued_calls(&mut self) -> Vec<(T::AccountId, T::RuntimeCall)> {
+        core::mem::take(&mut self.queued)
+    }
+}
+
+// The mutations of this pallet are journaled while a block is executed, so the runtime can undo them.
+impl<T: Config> crate::support::Journaled for Pallet<T> {
+    fn start_journal(&mut self) {
+        self.journal.start() ;
+    }
+
+    fn stop_journal(&mut self) {
+        self.journal.stop() ;
+    }
+
+    fn undo_journal(&mut self) {
+        for undo in self.journal.take() {
+            self.undo(undo) ;
+        }
+    }
+
+    fn checkpoint_journal(&mut self) -> crate::support::JournalCheckpoint {
+        self.journal.checkpoint()
+    }
+
+    fn commit_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        self.journal.commit(checkpoint) ;
+    }
+
+    fn rollback_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        for undo in self.journal.rollback(checkpoint) {
+            self.undo(undo) ;
+        }
+    }
+}
+
+// At the start of every block, the calls scheduled for that block are queued up for the runtime.
+impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {
+    fn on_initialize(&mut self, block_number: T::BlockNumber) {
+        self.journal.record(|| Undo::BlockNumber(self.block_number)) ;
+        self.block_number = block_number ;
+        self.journal.record(|| Undo::Agenda(block_number, self.agenda.get(&block_number).cloned())) ;
+        if let Some(calls) = self.agenda.remove(&block_number) {
+            self.queued.extend(calls) ;
+        }
+    }
+}
+
+// Only these functions will be called by the user from this pallet.
+#[macros::call]
+impl<T: Config> Pallet<T> {
+    /// Schedule the call "task" to be made on behalf of the caller at the start of block "at".
+    /// This function fails if block "at" is not in the future.
+    // The call is boxed, since it is a call of the runtime, which can itself be a call to this pallet.
+    #[allow(clippy::boxed_local)]
+    pub fn schedule(
+        &mut self,
+        caller: T::AccountId,
+        at: T::BlockNumber,
+        task: Box<T::RuntimeCall>
+    ) -> DispatchResult {
+        if at <= self.block_number {
+            return Err(DispatchError::ScheduledInPast) ;
+        }
+        self.agenda.entry(at).or_default().push((caller.clone(), *task)) ;
+        self.journal.record(|| Undo::Scheduled(at)) ;
+
+        self.events.push(Event::Scheduled { who: caller, at }) ;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::support::{DispatchError, Hooks, QueuedCalls} ;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestConfig ;
+    impl crate::system::Config for TestConfig {
+        type AccountId = String ;
+        type BlockNumber = u32 ;
+        type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
+    }
+
+    impl super::Config for TestConfig {
+        type RuntimeCall = &'static str ;
+    }
+
+    #[test]
+    fn schedule_calls() {
+        let mut scheduler = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        scheduler.on_initialize(1) ;
+
+        // Calls can only be scheduled for future blocks.
+        assert_eq!(scheduler.schedule(alice.clone(), 1, Box::new("now")), Err(DispatchError::ScheduledInPast)) ;
+        assert_eq!(scheduler.schedule(alice.clone(), 3, Box::new("first")), Ok(())) ;
+        assert_eq!(scheduler.schedule(bob.clone(), 3, Box::new("second")), Ok(())) ;
+        assert_eq!(scheduler.agenda(3), &[(alice.clone(), "first"), (bob.clone(), "second")]) ;
+
+        // Nothing is queued up before block 3.
+        scheduler.on_initialize(2) ;
+        assert_eq!(scheduler.take_queued_calls(), vec![]) ;
+
+        // At block 3, its calls are queued up in the order they were scheduled, and leave the agenda.
+        scheduler.on_initialize(3) ;
+        assert_eq!(scheduler.take_queued_calls(), vec![(alice.clone(), "first"), (bob.clone(), "second")]) ;
+        assert_eq!(scheduler.agenda(3), &[]) ;
+        assert_eq!(scheduler.schedule(alice.clone(), 3, Box::new("late")), Err(DispatchError::ScheduledInPast)) ;
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::BTreeMap ;
+use num::traits::{CheckedAdd, CheckedSub, Zero} ;
+use crate::support::{DispatchError, DispatchResult, Currency, Journal, ReservableCurrency} ;
+
+/// The Config trait for the Staking module.
+/// It contains the currency in which accounts bond their stake.
+pub trait Config: crate::balances::Config {
+    /// The currency in which stake is bonded. Bonded stake is reserved in this currency, so it
+    /// cannot be transferred.
+    type Currency: ReservableCurrency<Self::AccountId, Balance = Self::Balance> ;
+}
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<T: Config> {
+    /// Some "amount" was bonded by "who".
+    Bonded {
+        who: T::AccountId,
+        amount: T::Balance,
+    },
+    /// Some "amount" was unbonded by "who".
+    Unbonded {
+        who: T::AccountId,
+        amount: T::Balance,
+    },
+    /// Some "amount" was rewarded to "who", and added to their bonded stake.
+    Rewarded {
+        who: T::AccountId,
+        amount: T::Balance,
+    },
+}
+
+/// The operations undoing the mutations of the storage of this pallet, as recorded in its journal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Undo<T: Config> {
+    /// Set the amount bonded by an account back to the given one, or remove it if there was none.
+    Bonded(T::AccountId, Option<T::Balance>),
+}
+
+/// This is the Staking module.
+/// It keeps track of how much balance each account has bonded as stake.
+#[derive(Debug, Clone, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "T::AccountId: serde::Serialize + serde::de::DeserializeOwned, T::Balance: serde::Serialize + serde::de::DeserializeOwned"))]
+pub struct Pallet<T: Config> {
+    /// A map from an account to the amount it has bonded.
+    bonded: BTreeMap<T::AccountId, T::Balance>,
+    /// The events emitted since they were last taken.
+    /// Events are not part of the state, so they are never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    events: Vec<Event<T>>,
+    /// The journal of the mutations made while executing the last block.
+    /// Like events, the journal is not part of the state.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    journal: Journal<Undo<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+    /// Create a new instance of the staking module.
+    pub fn new() -> Self {
+        Self {
+            bonded: BTreeMap::new(),
+            events: Vec::new(),
+            journal: Journal::new(),
+        }
+    }
+
+    /// Get the amount bonded by "who".
+    /// If the account has not bonded anything, we return zero.
+    pub fn bonded(&self, who: &T::AccountId) -> T::Balance {
+        self.bonded.get(who).copied().unwrap_or(T::Balance::zero())
+    }
+
+    /// Reward "who" with some newly minted "amount", which is bonded right away.
+    pub fn reward(&mut self, currency: &mut T::Currency, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+        let bonded = self.bonded(who).checked_add(&amount).ok_or(DispatchError::Overflow) ?;
+        currency.deposit(who, amount) ?;
+        currency.reserve(who, amount) ?;
+        self.journal_bonded(who) ;
+        self.bonded.insert(who.clone(), bonded) ;
+
+        self.events.push(Event::Rewarded { who: who.clone(), amount }) ;
+        Ok(())
+    }
+
+    /// Take all the events emitted since they were last taken, leaving no events behind.
+    pub fn take_events(&mut self) -> Vec<Event<T>> {
+        core::mem::take(&mut self.events)
+    }
+
+    /// Record the amount bonded by "who" in the journal, before it is mutated.
+    fn journal_bonded(&mut self, who: &T::AccountId) {
+        self.journal.record(|| Undo::Bonded(who.clone(), self.bonded.get(who).copied())) ;
+    }
+
+    /// Reverse a mutation of the storage, as recorded by "undo" in the journal.
+    fn undo(&mut self, undo: Undo<T>) {
+        match undo {
+            Undo::Bonded(who, bonded) => crate::support::restore_entry(&mut self.bonded, who, bonded),
+        }
+    }
+}
+
+// The mutations of this pallet are journaled while a block is executed, so the runtime can undo them.
+impl<T: Config> crate::support::Journaled for Pallet<T> {
+    fn start_journal(&mut self) {
+        self.journal.start() ;
+    }
+
+    fn stop_journal(&mut self) {
+        self.journal.stop() ;
+    }
+
+    fn undo_journal(&mut self) {
+        for undo in self.journal.take() {
+            self.undo(undo) ;
+        }
+    }
+
+    fn checkpoint_journal(&mut self) -> crate::support::JournalCheckpoint {
+        self.journal.checkpoint()
+    }
+
+    fn commit_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        self.journal.commit(checkpoint) ;
+    }
+
+    fn rollback_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        for undo in self.journal.rollback(checkpoint) {
+            self.undo(undo) ;
+        }
+    }
+}
+
+// The staking pallet does not need to execute any logic as part of a block.
+impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {}
+
+// Only these functions will be called by the user from this pallet. They are given the currency of
+// the runtime, so they can reserve the bonded stake.
+#[macros::call(context = T::Currency)]
+impl<T: Config> Pallet<T> {
+    /// Bond some "amount" of the caller's free balance as stake.
+    /// The bonded balance is reserved, so it can no longer be transferred.
+    pub fn bond(
+        &mut self,
+        caller: T::AccountId,
+        currency: &mut T::Currency,
+        amount: T::Balance
+    ) -> DispatchResult {
+        let bonded = self.bonded(&caller).checked_add(&amount).ok_or(DispatchError::Overflow) ?;
+        currency.reserve(&caller, amount) ?;
+        self.journal_bonded(&caller) ;
+        self.bonded.insert(caller.clone(), bonded) ;
+
+        self.events.push(Event::Bonded { who: caller, amount }) ;
+        Ok(())
+    }
+
+    /// Unbond some "amount" of the caller's stake, returning it to their free balance.
+    /// This function fails if the caller has bonded less than "amount".
+    pub fn unbond(
+        &mut self,
+        caller: T::AccountId,
+        currency: &mut T::Currency,
+        amount: T::Balance
+    ) -> DispatchResult {
+        let bonded = self.bonded(&caller).checked_sub(&amount).ok_or(DispatchError::InsufficientBond) ?;
+        currency.unreserve(&caller, amount) ?;
+        self.journal_bonded(&caller) ;
+        if bonded.is_zero() {
+            self.bonded.remove(&caller) ;
+        } else {
+            self.bonded.insert(caller.clone(), bonded) ;
+        }
+
+        self.events.push(Event::Unbonded { who: caller, amount }) ;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::support::DispatchError ;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestConfig ;
+    impl crate::system::Config for TestConfig {
+        type AccountId = String ;
+        type BlockNumber = u32 ;
+        type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
+    }
+
+    impl crate::balances::Config for TestConfig {
+        type Balance = u128 ;
+        const EXISTENTIAL_DEPOSIT: u128 = 1 ;
+        const TRANSFER_FEE: u128 = 0 ;
+        const MINIMUM_TRANSFER: u128 = 0 ;
+        fn fee_account() -> String {
+            "fees".to_string()
+        }
+        type AccountStore = std::collections::BTreeMap<String, crate::balances::AccountData<u128>> ;
+    }
+
+    impl super::Config for TestConfig {
+        type Currency = crate::balances::Pallet<TestConfig> ;
+    }
+
+    #[test]
+    fn bond_and_unbond() {
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+        let mut staking = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+
+        // Bonding reserves the stake.
+        assert_eq!(staking.bond(alice.clone(), &mut balances, 60), Ok(())) ;
+        assert_eq!(staking.bonded(&alice), 60) ;
+        assert_eq!(balances.balance(&alice), 40) ;
+        assert_eq!(balances.reserved_balance(&alice), 60) ;
+
+        // Accounts cannot bond more than their free balance.
+        assert_eq!(staking.bond(alice.clone(), &mut balances, 50), Err(DispatchError::InsufficientFreeBalance)) ;
+        assert_eq!(staking.bonded(&alice), 60) ;
+
+        // Unbonding returns the stake, but not more than was bonded.
+        assert_eq!(staking.unbond(alice.clone(), &mut balances, 70), Err(DispatchError::InsufficientBond)) ;
+        assert_eq!(staking.unbond(alice.clone(), &mut balances, 60), Ok(())) ;
+        assert_eq!(staking.bonded(&alice), 0) ;
+        assert!(staking.bonded.is_empty()) ;
+        assert_eq!(balances.balance(&alice), 100) ;
+
+        assert_eq!(
+            staking.take_events(),
+            vec![
+                super::Event::Bonded { who: alice.clone(), amount: 60 },
+                super::Event::Unbonded { who: alice.clone(), amount: 60 },
+            ]
+        ) ;
+    }
+
+    #[test]
+    fn bonded_funds_cannot_be_transferred() {
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+        let mut staking = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+        let _ = staking.bond(alice.clone(), &mut balances, 80) ;
+
+        // Only the balance which is not bonded can be transferred.
+        assert_eq!(balances.transfer(&alice, &bob, 30), Err(DispatchError::InsufficientFunds)) ;
+        assert_eq!(balances.transfer(&alice, &bob, 20), Ok(())) ;
+        assert_eq!(balances.reserved_balance(&alice), 80) ;
+    }
+
+    #[test]
+    fn reward_stake() {
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+        let mut staking = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+        let _ = staking.bond(alice.clone(), &mut balances, 50) ;
+
+        // Rewards are minted straight into the bonded stake.
+        assert_eq!(staking.reward(&mut balances, &alice, 10), Ok(())) ;
+        assert_eq!(staking.bonded(&alice), 60) ;
+        assert_eq!(balances.balance(&alice), 50) ;
+        assert_eq!(balances.reserved_balance(&alice), 60) ;
+        assert_eq!(balances.total_issuance(), 110) ;
+    }
+}
@@ -1,4 +1,6 @@
 /// The most primitive representation of a Blockchain block.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Block<Header, Extrinsic> {
     /// The block header contains the metadata about the block.
     pub header: Header,
@@ -6,35 +8,1322 @@ pub struct Block<Header, Extrinsic> {
     pub extrinsics: Vec<Extrinsic>,
 }
 
-/// We are using an extremely simplified header which only contains the current block number.
+impl<BlockNumber, Moment, Hash: Encode + Clone, Extrinsic: Encode> Block<Header<BlockNumber, Moment, Hash>, Extrinsic> {
+    /// Create a block of "extrinsics" on top of the block with the hash "parent_hash", whose header
+    /// commits to the extrinsics through its extrinsics root, computed with the hasher "H".
+    pub fn new<H: Hasher<Output = Hash>>(
+        parent_hash: Hash,
+        block_number: BlockNumber,
+        timestamp: Moment,
+        extrinsics: Vec<Extrinsic>
+    ) -> Self {
+        let extrinsics_root = compute_extrinsics_root::<H, _>(&extrinsics) ;
+        Self {
+            header: Header { parent_hash, block_number, timestamp, extrinsics_root },
+            extrinsics,
+        }
+    }
+}
+
+/// We are using an extremely simplified header which only contains the hash of the parent block, the
+/// current block number, the time at which the block was produced, and the root of its extrinsics.
 /// A real blockchain like Polkadot will also have the following :
-/// - parent hash
 /// - state root
-/// - extrinsic root
 /// - consensus digest
 /// - etc..
-pub struct Header<BlockNumber> {
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Header<BlockNumber, Moment, Hash> {
+    /// The hash of the block this block is built on top of, or zero for the first block.
+    pub parent_hash: Hash,
     pub block_number: BlockNumber,
+    /// The timestamp must increase with every block.
+    pub timestamp: Moment,
+    /// The root of the extrinsics of the block, as computed by "compute_extrinsics_root".
+    pub extrinsics_root: Hash,
 } 
 
 /// This is an "extrinsic", which is an external message from outside of the blockchain.
 /// This simplified version of extrinsic tells us who is making the "Call" and which call they are making.
-pub struct Extrinsic<Caller, Call> {
+/// The "nonce" must match the caller's current nonce, which prevents the extrinsic from being replayed.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Extrinsic<Caller, Call, Nonce> {
     pub caller: Caller,
     pub call: Call, 
+    pub nonce: Nonce,
+}
+
+impl<Caller: Encode, Call: Encode, Nonce: Encode> Extrinsic<Caller, Call, Nonce> {
+    /// Sign this extrinsic on behalf of its caller, with the toy "Signature" scheme.
+    pub fn sign(self) -> SignedExtrinsic<Caller, Call, Nonce, Signature> {
+        let signature = Signature::sign(&self.caller, &signing_payload(&self.call, &self.nonce)) ;
+        SignedExtrinsic { caller: self.caller, call: self.call, nonce: self.nonce, signature }
+    }
+}
+
+/// An extrinsic along with the "signature" of its caller, which proves the caller made it.
+/// Blocks are made up of signed extrinsics, so a caller cannot be impersonated.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignedExtrinsic<Caller, Call, Nonce, Signature> {
+    pub caller: Caller,
+    pub call: Call,
+    pub nonce: Nonce,
+    /// The signature of the caller over the call and the nonce.
+    pub signature: Signature,
+}
+
+impl<Caller, Call: Encode, Nonce: Encode, Signature: Verify<Caller>> SignedExtrinsic<Caller, Call, Nonce, Signature> {
+    /// Check that the signature of this extrinsic was made by its caller.
+    pub fn verify(&self) -> bool {
+        self.signature.verify(&signing_payload(&self.call, &self.nonce), &self.caller)
+    }
+}
+
+/// The bytes the caller of an extrinsic signs, which are the encoded call and nonce.
+/// Signing the nonce too means a signature cannot be replayed for another extrinsic.
+pub fn signing_payload<Call: Encode, Nonce: Encode>(call: &Call, nonce: &Nonce) -> Vec<u8> {
+    (call, nonce).encode()
+}
+
+/// A trait for signatures, which can be checked to have been made by some account.
+pub trait Verify<AccountId> {
+    /// Check that this is a signature of the "message" made by the account "signer".
+    fn verify(&self, message: &[u8], signer: &AccountId) -> bool ;
+}
+
+/// A toy signature, which is the hash of the signer along with the signed message.
+/// It is deterministic, which keeps tests reproducible, but anyone can sign on behalf of anyone, so it
+/// only protects against mistakes, not against forgery by someone who knows the scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Signature(pub u64) ;
+
+impl Signature {
+    /// Sign the "message" on behalf of the account "signer".
+    pub fn sign<AccountId: Encode>(signer: &AccountId, message: &[u8]) -> Self {
+        let mut signed = signer.encode() ;
+        signed.extend_from_slice(message) ;
+        Signature(hash(&signed))
+    }
+}
+
+impl<AccountId: Encode> Verify<AccountId> for Signature {
+    fn verify(&self, message: &[u8], signer: &AccountId) -> bool {
+        *self == Signature::sign(signer, message)
+    }
+}
+
+/// A trait for the types which can be encoded into bytes, so they can be hashed.
+/// Different values always encode to different bytes.
+pub trait Encode {
+    /// Append the encoding of this value to "dest".
+    fn encode_to(&self, dest: &mut Vec<u8>) ;
+
+    /// Encode this value into a new vector of bytes.
+    fn encode(&self) -> Vec<u8> {
+        let mut dest = Vec::new() ;
+        self.encode_to(&mut dest) ;
+        dest
+    }
+}
+
+// Integers are encoded as their little endian bytes.
+macro_rules! impl_encode_for_int {
+    ($($int:ty),*) => {
+        $(
+            impl Encode for $int {
+                fn encode_to(&self, dest: &mut Vec<u8>) {
+                    dest.extend_from_slice(&self.to_le_bytes()) ;
+                }
+            }
+        )*
+    } ;
+}
+impl_encode_for_int!(u8, u16, u32, u64, u128) ;
+
+impl Encode for bool {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        dest.push(*self as u8) ;
+    }
+}
+
+// Sequences are encoded as their length, followed by their items.
+impl Encode for str {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        (self.len() as u32).encode_to(dest) ;
+        dest.extend_from_slice(self.as_bytes()) ;
+    }
+}
+
+impl Encode for String {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        self.as_str().encode_to(dest) ;
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        (**self).encode_to(dest) ;
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        (self.len() as u32).encode_to(dest) ;
+        for item in self {
+            item.encode_to(dest) ;
+        }
+    }
+}
+
+// Arrays have a fixed length, so only their items are encoded.
+impl<T: Encode, const N: usize> Encode for [T; N] {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        for item in self {
+            item.encode_to(dest) ;
+        }
+    }
+}
+
+impl<T: Encode> Encode for Box<T> {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        (**self).encode_to(dest) ;
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        match self {
+            None => dest.push(0),
+            Some(value) => {
+                dest.push(1) ;
+                value.encode_to(dest) ;
+            },
+        }
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        self.0.encode_to(dest) ;
+        self.1.encode_to(dest) ;
+    }
+}
+
+// Maps and sets are encoded as their length, followed by their entries ordered by key.
+impl<K: Encode, V: Encode> Encode for std::collections::BTreeMap<K, V> {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        (self.len() as u32).encode_to(dest) ;
+        for entry in self {
+            entry.encode_to(dest) ;
+        }
+    }
+}
+
+impl<T: Encode> Encode for std::collections::BTreeSet<T> {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        (self.len() as u32).encode_to(dest) ;
+        for item in self {
+            item.encode_to(dest) ;
+        }
+    }
+}
+
+// A hash map has no order, so its entries are sorted first, to always encode the same map to the
+// same bytes.
+impl<K: Encode + Ord, V: Encode> Encode for std::collections::HashMap<K, V> {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        let mut entries = self.iter().collect::<Vec<_>>() ;
+        entries.sort_by_key(|(key, _)| *key) ;
+        entries.encode_to(dest) ;
+    }
+}
+
+impl<BlockNumber: Encode, Moment: Encode, Hash: Encode> Header<BlockNumber, Moment, Hash> {
+    /// The hash of the block with this header, hashed with "H", which the next block names as its
+    /// parent. The header commits to the extrinsics through the extrinsics root, so changing any
+    /// extrinsic changes the hash too.
+    pub fn hash_with<H: Hasher>(&self) -> H::Output {
+        H::hash(&self.encode())
+    }
+}
+
+impl<BlockNumber: Encode, Moment: Encode, Hash: Encode> Encode for Header<BlockNumber, Moment, Hash> {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        self.parent_hash.encode_to(dest) ;
+        self.block_number.encode_to(dest) ;
+        self.timestamp.encode_to(dest) ;
+        self.extrinsics_root.encode_to(dest) ;
+    }
+}
+
+impl<Caller: Encode, Call: Encode, Nonce: Encode, Signature: Encode> Encode for SignedExtrinsic<Caller, Call, Nonce, Signature> {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        self.caller.encode_to(dest) ;
+        self.call.encode_to(dest) ;
+        self.nonce.encode_to(dest) ;
+        self.signature.encode_to(dest) ;
+    }
+}
+
+impl Encode for Signature {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        self.0.encode_to(dest) ;
+    }
+}
+
+impl<Header: Encode, Extrinsic: Encode> Encode for Block<Header, Extrinsic> {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        self.header.encode_to(dest) ;
+        self.extrinsics.encode_to(dest) ;
+    }
+}
+
+impl<Caller: Encode, Call: Encode, Nonce: Encode> Encode for Extrinsic<Caller, Call, Nonce> {
+    fn encode_to(&self, dest: &mut Vec<u8>) {
+        self.caller.encode_to(dest) ;
+        self.call.encode_to(dest) ;
+        self.nonce.encode_to(dest) ;
+    }
+}
+
+/// Hash some bytes with the 64 bit FNV-1a hash function.
+/// This is quick and deterministic, but not cryptographically secure.
+pub fn hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// A trait for the hash functions a runtime can be configured with.
+pub trait Hasher {
+    /// The hash of some bytes.
+    type Output ;
+
+    /// Hash the bytes in "data".
+    fn hash(data: &[u8]) -> Self::Output ;
+}
+
+/// The built-in hasher, which hashes with the FNV-1a hash function of "hash".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fnv ;
+
+impl Hasher for Fnv {
+    type Output = u64 ;
+
+    fn hash(data: &[u8]) -> u64 {
+        hash(data)
+    }
+}
+
+/// Hash the account id "id", with the same fixed hash function as "hash".
+/// The hash only depends on the id, so it is the same on every run and every machine.
+pub fn hash_account(id: &str) -> u64 {
+    hash(id.as_bytes())
+}
+
+/// A trait for account ids which can be hashed deterministically, like with "hash_account".
+/// The account ids are generic, so every account id type hashes itself in its own way.
+pub trait Hashable {
+    /// The deterministic hash of this account id.
+    fn hash_id(&self) -> u64 ;
+}
+
+impl Hashable for str {
+    fn hash_id(&self) -> u64 {
+        hash_account(self)
+    }
+}
+
+impl Hashable for String {
+    fn hash_id(&self) -> u64 {
+        hash_account(self)
+    }
+}
+
+/// Compute the root of a Merkle tree over the hashes of the encoded "extrinsics", hashed with "H".
+/// Each level of the tree hashes pairs of hashes from the level below, until a single hash is left.
+/// Changing, adding, removing or reordering any extrinsic changes the root.
+pub fn compute_extrinsics_root<H: Hasher, E: Encode>(extrinsics: &[E]) -> H::Output where H::Output: Encode + Clone {
+    let mut level = extrinsics.iter().map(|extrinsic| H::hash(&extrinsic.encode())).collect::<Vec<_>>() ;
+    if level.is_empty() {
+        return H::hash(&[]) ;
+    }
+    while level.len() > 1 {
+        level = level.chunks(2).map(|pair| H::hash(&pair.to_vec().encode())).collect() ;
+    }
+    level.remove(0)
+}
+
+// The errors are declared through this macro, which lists every one of them in "DispatchError::ALL"
+// too, so that list can never miss an error.
+macro_rules! dispatch_errors {
+    ($( $( #[$doc:meta] )* $error:ident, )*) => {
+        /// The errors which can be returned when dispatching a call, or executing a block.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum DispatchError {
+            $( $( #[$doc] )* $error, )*
+        }
+
+        impl DispatchError {
+            /// Every error, in the order they are declared.
+            pub const ALL: &'static [DispatchError] = &[$( DispatchError::$error, )*] ;
+        }
+    } ;
+}
+dispatch_errors! {
+    /// The block number does not match the block number we expected.
+    InvalidBlockNumber,
+    /// The block number cannot be incremented any further.
+    BlockNumberOverflow,
+    /// The nonce of an extrinsic does not match the caller's current nonce.
+    InvalidNonce,
+    /// The extrinsic does not fit into the block anymore.
+    ExhaustsResources,
+    /// The origin of the call is not allowed to make this call, like a signed origin making a
+    /// privileged call.
+    BadOrigin,
+    /// The account does not have enough balance.
+    InsufficientFunds,
+    /// The account does not have enough free balance.
+    InsufficientFreeBalance,
+    /// The account does not have enough reserved balance.
+    InsufficientReservedBalance,
+    /// A balance would overflow.
+    Overflow,
+    /// The total issuance would overflow.
+    TotalIssuanceOverflow,
+    /// The total issuance would underflow.
+    TotalIssuanceUnderflow,
+    /// A transfer of zero balance was requested.
+    CannotTransferZero,
+    /// A transfer from an account to itself was requested.
+    CannotTransferToSelf,
+    /// A transfer would leave the sender with less than the existential deposit.
+    WouldKillAccount,
+    /// At least one transfer of a batch failed.
+    BatchTransferFailed,
+    /// The content has already been claimed.
+    AlreadyClaimed,
+    /// The claim does not exist.
+    ClaimNotFound,
+    /// The claim is owned by some other account.
+    NotOwner,
+    /// The account already owns the maximum number of claims.
+    TooManyClaims,
+    /// The content is longer than allowed.
+    ContentTooLarge,
+    /// The expiry of a claim is not in the future.
+    ExpiryInPast,
+    /// The account does not have enough bonded balance.
+    InsufficientBond,
+    /// A proposal with this id already exists.
+    ProposalExists,
+    /// The proposal does not exist.
+    ProposalNotFound,
+    /// The treasury does not have enough funds.
+    InsufficientTreasuryFunds,
+    /// The item has already been minted.
+    ItemExists,
+    /// The item does not exist.
+    ItemNotFound,
+    /// The spender is not allowed to spend this much on behalf of the owner.
+    AllowanceExceeded,
+    /// The threshold of a multisig is zero, or larger than its number of signatories.
+    InvalidThreshold,
+    /// The account is not controlled by a multisig.
+    MultisigNotFound,
+    /// The caller is not a signatory of the multisig.
+    NotSignatory,
+    /// The block a call is scheduled for is not in the future.
+    ScheduledInPast,
+    /// The balance is locked, so it cannot be transferred.
+    LiquidityRestrictions,
+    /// The account has no vesting schedule.
+    NotVesting,
+    /// The timestamp of a block is not later than the timestamp of the previous block.
+    TimestampNotIncreasing,
+    /// The caller cannot pay the fee of an extrinsic.
+    CannotPayFee,
+    /// The extrinsics root in the block header does not match the extrinsics of the block.
+    InvalidExtrinsicsRoot,
+    /// The state root after executing the last block does not match the expected state root.
+    StateRootMismatch,
+    /// The parent hash in the block header is not the hash of the current block.
+    ParentHashMismatch,
+    /// The signature of the extrinsic was not made by its caller.
+    InvalidSignature,
+    /// An extrinsic with the same caller and nonce is already waiting in the transaction pool.
+    AlreadyInPool,
+    /// The account sending or receiving a transfer is frozen.
+    AccountFrozen,
+    /// The amount of a transfer is below the minimum transfer amount.
+    BelowMinimumTransfer,
+    /// The caller cannot afford the deposit of a claim.
+    CannotAffordDeposit,
+    /// The caller submitted as many extrinsics as the rate limit allows within its window.
+    RateLimitExceeded,
+    /// The balance of an account cannot be increased any further.
+    BalanceOverflow,
+    /// The timestamp of the next block would overflow.
+    TimestampOverflow,
+}
+
+impl core::fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let message = match self {
+            DispatchError::InvalidBlockNumber => "Invalid block number.",
+            DispatchError::BlockNumberOverflow => "Block number overflow.",
+            DispatchError::InvalidNonce => "Invalid nonce.",
+            DispatchError::ExhaustsResources => "Block weight limit exceeded.",
+            DispatchError::BadOrigin => "Bad origin.",
+            DispatchError::InsufficientFunds => "Insufficient funds.",
+            DispatchError::InsufficientFreeBalance => "Insufficient free balance.",
+            DispatchError::InsufficientReservedBalance => "Insufficient reserved balance.",
+            DispatchError::Overflow => "Overflow.",
+            DispatchError::TotalIssuanceOverflow => "Total issuance overflow.",
+            DispatchError::TotalIssuanceUnderflow => "Total issuance underflow.",
+            DispatchError::CannotTransferZero => "Cannot transfer zero.",
+            DispatchError::CannotTransferToSelf => "Cannot transfer to self.",
+            DispatchError::WouldKillAccount => "Transfer would kill account.",
+            DispatchError::BatchTransferFailed => "Batch transfer failed.",
+            DispatchError::AlreadyClaimed => "This content is already been claimed.",
+            DispatchError::ClaimNotFound => "Claim does not exist.",
+            DispatchError::NotOwner => "This content is owned by some other user.",
+            DispatchError::TooManyClaims => "Too many claims.",
+            DispatchError::ContentTooLarge => "Content too large.",
+            DispatchError::ExpiryInPast => "Expiry must be in the future.",
+            DispatchError::InsufficientBond => "Insufficient bonded balance.",
+            DispatchError::ProposalExists => "Proposal already exists.",
+            DispatchError::ProposalNotFound => "Proposal does not exist.",
+            DispatchError::InsufficientTreasuryFunds => "Insufficient treasury funds.",
+            DispatchError::ItemExists => "Item already exists.",
+            DispatchError::ItemNotFound => "Item does not exist.",
+            DispatchError::AllowanceExceeded => "Allowance exceeded.",
+            DispatchError::InvalidThreshold => "Invalid multisig threshold.",
+            DispatchError::MultisigNotFound => "Multisig does not exist.",
+            DispatchError::NotSignatory => "Not a signatory of this multisig.",
+            DispatchError::ScheduledInPast => "Scheduled block must be in the future.",
+            DispatchError::LiquidityRestrictions => "Balance is locked.",
+            DispatchError::NotVesting => "Account has no vesting schedule.",
+            DispatchError::TimestampNotIncreasing => "Timestamp must increase.",
+            DispatchError::CannotPayFee => "Cannot pay fee.",
+            DispatchError::InvalidExtrinsicsRoot => "Invalid extrinsics root.",
+            DispatchError::StateRootMismatch => "State root mismatch.",
+            DispatchError::ParentHashMismatch => "Parent hash mismatch.",
+            DispatchError::InvalidSignature => "Invalid signature.",
+            DispatchError::AlreadyInPool => "Extrinsic already in pool.",
+            DispatchError::AccountFrozen => "Account frozen.",
+            DispatchError::BelowMinimumTransfer => "Below minimum transfer.",
+            DispatchError::CannotAffordDeposit => "Cannot afford claim deposit.",
+            DispatchError::RateLimitExceeded => "Rate limit exceeded.",
+            DispatchError::BalanceOverflow => "Balance overflow.",
+            DispatchError::TimestampOverflow => "Timestamp overflow.",
+        } ;
+        f.write_str(message)
+    }
+}
+
+// Errors can be boxed up with any other error, so the runtime is easy to use from code which returns
+// "Box<dyn Error>".
+impl std::error::Error for DispatchError {}
+
+impl From<DispatchError> for String {
+    fn from(error: DispatchError) -> Self {
+        error.to_string()
+    }
+}
+
+impl core::str::FromStr for DispatchError {
+    type Err = String ;
+
+    /// Get the error which is displayed as "message", which is the opposite of "to_string".
+    fn from_str(message: &str) -> Result<Self, Self::Err> {
+        DispatchError::ALL.iter()
+            .find(|error| error.to_string() == message)
+            .copied()
+            .ok_or_else(|| format!("Unknown error \"{message}\"."))
+    }
 }
 
 /// The "Result" type for our Runtime. When everything completes successfully, we return an "Ok(())", else
-/// we return a static error message.
-pub type DispatchResult = Result<(), &'static str> ;
+/// we return a "DispatchError" describing what went wrong.
+pub type DispatchResult = Result<(), DispatchError> ;
+
+/// The weight of a call, which models how much of a block's capacity executing that call uses up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Weight(pub u64) ;
+
+/// A trait for calls which know the weight declared for them, before they are dispatched.
+pub trait GetWeight {
+    /// The weight declared for this call, which is what the block is charged for it at most.
+    fn weight(&self) -> Weight ;
+}
+
+/// The information about a call which is only known after dispatching it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PostDispatchInfo {
+    /// The weight the call actually used up. This may be less than the weight declared before it was
+    /// dispatched, which has to account for the worst case.
+    pub actual_weight: Weight,
+}
+
+/// The report of executing a block, which tells what happened to its extrinsics.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ExecutionReport {
+    /// The number of extrinsics whose call was dispatched, whether or not the call succeeded.
+    pub included: usize,
+    /// The number of extrinsics which were skipped without dispatching their call, for example
+    /// because they did not fit into the block.
+    pub skipped: usize,
+    /// The weight the block was charged for every included extrinsic.
+    pub total_weight: Weight,
+    /// The index of every extrinsic which failed or was skipped, along with the error, in order.
+    pub errors: Vec<(usize, DispatchError)>,
+}
+
+impl ExecutionReport {
+    /// The result of every extrinsic of the block, in order.
+    pub fn results(&self) -> Vec<DispatchResult> {
+        let mut results = vec![Ok(()) ; self.included + self.skipped] ;
+        for &(index, error) in &self.errors {
+            results[index] = Err(error) ;
+        }
+        results
+    }
+}
+
+/// The "Result" type of dispatching a call. When the call completes successfully, we return the
+/// "PostDispatchInfo" of the call, else we return a "DispatchError" describing what went wrong.
+pub type DispatchResultWithPostInfo = Result<PostDispatchInfo, DispatchError> ;
+
+/// The origin of a call, which tells on whose behalf the call is made.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Origin<AccountId> {
+    /// The call is made by the account which signed it, like the caller of an extrinsic.
+    Signed(AccountId),
+    /// The call is privileged, and is allowed to do things no account can, like "force_transfer".
+    Root,
+    /// The call is not made on behalf of anyone.
+    None,
+}
+
+impl<AccountId> Origin<AccountId> {
+    /// Get the account which signed the call.
+    /// This function fails with "BadOrigin" for any other origin.
+    pub fn ensure_signed(self) -> Result<AccountId, DispatchError> {
+        match self {
+            Origin::Signed(who) => Ok(who),
+            _ => Err(DispatchError::BadOrigin),
+        }
+    }
+
+    /// Check that the call is privileged.
+    /// This function fails with "BadOrigin" for any other origin.
+    pub fn ensure_root(&self) -> DispatchResult {
+        match self {
+            Origin::Root => Ok(()),
+            _ => Err(DispatchError::BadOrigin),
+        }
+    }
+}
 
 /// A trait which allows us to dispatch an incoming extrinsic to the appropriate state transition function call.
 pub trait Dispatch {
-    /// The type to identify the caller of the function.
+    /// The type to identify the caller of the function, usually an "Origin".
     type Caller ;
     /// The state transition function call the caller is trying to access.
     type Call ;
     /// This function takes up a 'caller' and the 'call' he/she is trying to make, and returns a 'Result'
-    /// based on the outcome of that call.
-    fn dispatch(&mut self, caller: Self::Caller, call: Self::Call) -> DispatchResult ;
-}
\ No newline at end of file
+    /// based on the outcome of that call, along with the weight it actually used up.
+    fn dispatch(&mut self, caller: Self::Caller, call: Self::Call) -> DispatchResultWithPostInfo ;
+    /// The weight of executing the 'call'. Unless specified otherwise, every call weighs 1.
+    fn weight(&self, _call: &Self::Call) -> Weight {
+        Weight(1)
+    }
+}
+
+/// Like "Dispatch", but for pallets whose calls need access to some "Context" outside of the pallet,
+/// for example the currency of the runtime. The runtime provides the context when dispatching.
+pub trait DispatchWith {
+    /// The type to identify the caller of the function, usually an "Origin".
+    type Caller ;
+    /// The state transition function call the caller is trying to access.
+    type Call ;
+    /// The context the calls need access to.
+    type Context ;
+    /// This function takes up the 'context', a 'caller' and the 'call' they are trying to make, and returns
+    /// a 'Result' based on the outcome of that call, along with the weight it actually used up.
+    fn dispatch_with(&mut self, context: &mut Self::Context, caller: Self::Caller, call: Self::Call) -> DispatchResultWithPostInfo ;
+    /// The weight of executing the 'call'. Unless specified otherwise, every call weighs 1.
+    fn weight(&self, _call: &Self::Call) -> Weight {
+        Weight(1)
+    }
+}
+
+/// A description of the calls exposed by a runtime, from which tooling can build a call without knowing
+/// the runtime, like a form in a UI.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RuntimeMetadata {
+    /// Every callable pallet, in the order they are declared in the runtime.
+    pub pallets: Vec<PalletMetadata>,
+}
+
+impl RuntimeMetadata {
+    /// Get the description of the call named "call" of the pallet named "pallet", if there is one.
+    pub fn call(&self, pallet: &str, call: &str) -> Option<&CallMetadata> {
+        let pallet = self.pallets.iter().find(|metadata| metadata.name == pallet)? ;
+        pallet.calls.iter().find(|metadata| metadata.name == call)
+    }
+}
+
+/// A description of the calls exposed by a pallet.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PalletMetadata {
+    /// The name of the pallet in the runtime, like "balances".
+    pub name: &'static str,
+    /// Every callable function of the pallet, in the order they are declared.
+    pub calls: Vec<CallMetadata>,
+}
+
+/// A description of a call, generated from its callable function by "#[macros::call]".
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CallMetadata {
+    /// The name of the callable function, like "transfer".
+    pub name: &'static str,
+    /// The parameters of the call, not including the caller or the origin and the context.
+    pub params: Vec<ParamMetadata>,
+}
+
+/// A description of a parameter of a call.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ParamMetadata {
+    /// The name of the parameter, like "amount".
+    pub name: &'static str,
+    /// The type of the parameter as it is written in the callable function, like "T::Balance".
+    pub type_name: &'static str,
+}
+
+/// A trait which allows a pallet to move funds around, without depending on a concrete balances pallet.
+pub trait Currency<AccountId> {
+    /// The type representing the balance of an account.
+    type Balance ;
+
+    /// Transfer some "amount" from the account "from" to the account "to".
+    fn transfer(&mut self, from: &AccountId, to: &AccountId, amount: Self::Balance) -> DispatchResult ;
+
+    /// Create some "amount" of new balance in the account "who".
+    fn deposit(&mut self, who: &AccountId, amount: Self::Balance) -> DispatchResult ;
+
+    /// Destroy some "amount" of the balance of the account "who".
+    fn withdraw(&mut self, who: &AccountId, amount: Self::Balance) -> DispatchResult ;
+
+    /// Get the total balance of the account "who", including any balance which cannot be used.
+    fn total_balance(&self, who: &AccountId) -> Self::Balance ;
+}
+
+/// A "Currency" which can also set some balance of an account aside, so it cannot be used until it is
+/// returned.
+pub trait ReservableCurrency<AccountId>: Currency<AccountId> {
+    /// Set some "amount" of the balance of the account "who" aside.
+    fn reserve(&mut self, who: &AccountId, amount: Self::Balance) -> DispatchResult ;
+
+    /// Return some "amount" of the balance set aside for the account "who".
+    fn unreserve(&mut self, who: &AccountId, amount: Self::Balance) -> DispatchResult ;
+
+    /// Get the balance set aside for the account "who".
+    fn reserved_balance(&self, who: &AccountId) -> Self::Balance ;
+}
+
+/// The identifier of a lock, which tells apart the locks different pallets put on the same account.
+pub type LockId = [u8; 8] ;
+
+/// A "Currency" which can also lock some balance of an account, so it cannot be transferred away while it
+/// still counts as free balance.
+/// Every lock has an identifier, and the locks of an account overlap rather than add up, so the
+/// locked balance is the largest of them.
+pub trait LockableCurrency<AccountId>: Currency<AccountId> {
+    /// Lock some "amount" of the balance of the account "who" under "id", replacing any amount locked
+    /// under "id" before.
+    fn set_lock(&mut self, id: LockId, who: &AccountId, amount: Self::Balance) ;
+
+    /// Remove the lock on the balance of the account "who" under "id", if there is one.
+    fn remove_lock(&mut self, id: LockId, who: &AccountId) ;
+
+    /// Get the locked balance of the account "who", which is the largest amount locked under any id.
+    fn locked_balance(&self, who: &AccountId) -> Self::Balance ;
+}
+
+/// A trait for pallets which queue up calls for the runtime to dispatch on behalf of some caller, for
+/// example once enough accounts approved them.
+pub trait QueuedCalls<Caller, Call> {
+    /// Take all the calls queued since they were last taken, along with the caller of each call.
+    fn take_queued_calls(&mut self) -> Vec<(Caller, Call)> ;
+}
+
+/// How the calls of a batch are dispatched, and what happens once one of them fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchMode {
+    /// The batch stops at the first call which fails, keeping the effects of the calls before it.
+    Interruptible,
+    /// The batch fails as a whole once any of its calls fails, undoing the effects of all of them.
+    Atomic,
+    /// Every call of the batch is dispatched, even after some of them fail.
+    Forced,
+}
+
+/// Some calls to be dispatched by the runtime one after the other, all on behalf of the same "origin".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batch<Origin, Call> {
+    pub origin: Origin,
+    pub calls: Vec<Call>,
+    pub mode: BatchMode,
+}
+
+/// A trait for pallets which queue up batches of calls for the runtime to dispatch, like "QueuedCalls"
+/// but with control over what happens once a call fails.
+pub trait BatchCalls<Origin, Call> {
+    /// Take all the batches queued since they were last taken.
+    fn take_batches(&mut self) -> Vec<Batch<Origin, Call>> ;
+    /// Report the "results" of the calls of a batch made by "origin" with some "mode", in order.
+    /// There are no results for the calls after the one interrupting the batch. An atomic batch which
+    /// failed is not reported, since all of its effects are undone.
+    fn batch_dispatched(&mut self, origin: Origin, mode: BatchMode, results: &[DispatchResult]) ;
+}
+
+/// A trait for pallets which charge a fee for every extrinsic, before its call is dispatched.
+pub trait ChargeExtrinsicFee<AccountId> {
+    /// The context the fee is paid from, like the currency of the runtime.
+    type Context ;
+    /// Charge the account "who" the fee of an extrinsic, paying it from the "context".
+    /// This function fails with "CannotPayFee" if "who" cannot afford the fee, in which case nothing is paid.
+    fn charge_extrinsic_fee(&mut self, context: &mut Self::Context, who: &AccountId) -> DispatchResult ;
+}
+
+/// A change to the providers of an account, which keep the account in existence.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProviderChange<AccountId> {
+    /// The account gained a provider, for example because it was funded for the first time.
+    Added(AccountId),
+    /// The account lost a provider, for example because it was reaped.
+    Removed(AccountId),
+}
+
+/// A trait for pallets which provide for the existence of accounts, like a currency holding their
+/// balance. The runtime reports the changes to the system pallet, which tracks which accounts exist.
+pub trait AccountProvider<AccountId> {
+    /// Take all the provider changes made since they were last taken, in the order they happened.
+    fn take_provider_changes(&mut self) -> Vec<ProviderChange<AccountId>> ;
+}
+
+/// A trait which allows a pallet to execute some logic as part of every block, outside of any extrinsic.
+pub trait Hooks<BlockNumber> {
+    /// The version of the layout of the storage of this pallet.
+    /// It is bumped whenever the layout changes, along with a migration in "on_runtime_upgrade".
+    const STORAGE_VERSION: u32 = 0 ;
+
+    /// This function is called at the start of every block, before any extrinsic is executed.
+    fn on_initialize(&mut self, _block_number: BlockNumber) {}
+
+    /// This function is called at the end of every block, after all extrinsics are executed.
+    fn on_finalize(&mut self, _block_number: BlockNumber) {}
+
+    /// This function is called once by the runtime when the storage of this pallet was stored with a
+    /// different version than "STORAGE_VERSION", like after loading the state of an older runtime.
+    /// It migrates the storage to the current layout, and returns the weight it used up.
+    fn on_runtime_upgrade(&mut self) -> Weight {
+        Weight(0)
+    }
+}
+
+/// A trait for pallets which journal the mutations of their storage while a block is executed, so the
+/// runtime can undo the block later. Pallets without storage can rely on the default functions, which
+/// journal nothing.
+pub trait Journaled {
+    /// Start journaling every mutation, forgetting the mutations journaled before.
+    fn start_journal(&mut self) {}
+
+    /// Stop journaling, keeping the mutations journaled so far.
+    fn stop_journal(&mut self) {}
+
+    /// Undo every journaled mutation, the latest one first, leaving nothing journaled behind.
+    fn undo_journal(&mut self) {}
+
+    /// Start journaling the mutations of something which may have to be rolled back on its own, like
+    /// an atomic batch of calls. The checkpoint must be given to "commit_journal" or "rollback_journal".
+    fn checkpoint_journal(&mut self) -> JournalCheckpoint {
+        JournalCheckpoint { len: 0, recording: false }
+    }
+
+    /// Keep the mutations journaled since "checkpoint".
+    fn commit_journal(&mut self, _checkpoint: JournalCheckpoint) {}
+
+    /// Undo the mutations journaled since "checkpoint", the latest one first.
+    fn rollback_journal(&mut self, _checkpoint: JournalCheckpoint) {}
+}
+
+/// The journal of a pallet, which records the "Undo" operation reversing every mutation of its storage.
+/// Mutations are only recorded between "start" and "stop", so nothing is recorded outside of a block.
+#[derive(Debug, Clone)]
+pub struct Journal<Undo> {
+    undos: Vec<Undo>,
+    recording: bool,
+}
+
+impl<Undo> Journal<Undo> {
+    /// Create a new journal, which records nothing until it is started.
+    pub fn new() -> Self {
+        Self { undos: Vec::new(), recording: false }
+    }
+
+    /// Start recording, forgetting everything recorded before.
+    pub fn start(&mut self) {
+        self.undos.clear() ;
+        self.recording = true ;
+    }
+
+    /// Stop recording, keeping everything recorded so far.
+    pub fn stop(&mut self) {
+        self.recording = false ;
+    }
+
+    /// Record the operation made by "undo", which reverses the mutation being made.
+    /// Nothing is made unless the journal is recording, so mutations outside of a block cost nothing.
+    pub fn record(&mut self, undo: impl FnOnce() -> Undo) {
+        if self.recording {
+            self.undos.push(undo()) ;
+        }
+    }
+
+    /// Start recording the mutations of something which may have to be rolled back on its own, like
+    /// one call of a batch, even when no block is being executed.
+    /// The returned checkpoint must be given to either "commit" or "rollback" afterwards.
+    pub fn checkpoint(&mut self) -> JournalCheckpoint {
+        let checkpoint = JournalCheckpoint { len: self.undos.len(), recording: self.recording } ;
+        self.recording = true ;
+        checkpoint
+    }
+
+    /// Keep the mutations recorded since "checkpoint". They stay recorded only if the journal was
+    /// recording before the checkpoint, so they can still be undone with the rest of the block.
+    pub fn commit(&mut self, checkpoint: JournalCheckpoint) {
+        if !checkpoint.recording {
+            self.undos.truncate(checkpoint.len) ;
+        }
+        self.recording = checkpoint.recording ;
+    }
+
+    /// Take the operations recorded since "checkpoint" in the order they must be applied, the latest
+    /// one first, to roll back the mutations they reverse.
+    pub fn rollback(&mut self, checkpoint: JournalCheckpoint) -> impl Iterator<Item = Undo> {
+        self.recording = checkpoint.recording ;
+        self.undos.split_off(checkpoint.len).into_iter().rev()
+    }
+
+    /// Stop recording, and take the recorded operations in the order they must be applied, the latest
+    /// one first.
+    pub fn take(&mut self) -> impl Iterator<Item = Undo> {
+        self.recording = false ;
+        core::mem::take(&mut self.undos).into_iter().rev()
+    }
+}
+
+/// The point of a journal to roll back to, made by "Journal::checkpoint".
+#[derive(Debug)]
+#[must_use]
+pub struct JournalCheckpoint {
+    len: usize,
+    recording: bool,
+}
+
+impl<Undo> Default for Journal<Undo> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The journal is not part of the state, so it never tells two pallets apart.
+impl<Undo> PartialEq for Journal<Undo> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// Put the "old" value of "key" back into "map", or remove "key" if it had no value, which reverses any
+/// mutation of that key.
+pub fn restore_entry<K, V, M: StorageMap<K, V>>(map: &mut M, key: K, old: Option<V>) {
+    match old {
+        Some(value) => { map.insert(key, value) ; },
+        None => { map.remove(&key) ; },
+    }
+}
+
+/// A trait for the maps a pallet keeps its storage in, so a runtime can choose the backend, like a
+/// "BTreeMap" or a "HashMap".
+pub trait StorageMap<K, V>: Default {
+    /// Get the value stored for "key", if there is one.
+    fn get(&self, key: &K) -> Option<&V> ;
+    /// Get a mutable reference to the value stored for "key", if there is one.
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> ;
+    /// Store "value" for "key", returning the value stored for it before, if there was one.
+    fn insert(&mut self, key: K, value: V) -> Option<V> ;
+    /// Remove the value stored for "key", returning it if there was one.
+    fn remove(&mut self, key: &K) -> Option<V> ;
+    /// Check whether some value is stored for "key".
+    fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+    /// The number of values stored in the map.
+    fn len(&self) -> usize ;
+    /// Check whether the map stores no values at all.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A "StorageMap" which can iterate over its values in an order only depending on their keys.
+pub trait OrderedStorageMap<K, V>: StorageMap<K, V> {
+    /// Iterate over every key and its value, ordered by key, or by the hash of the key for maps
+    /// ordered by hash.
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a V)> where K: 'a, V: 'a ;
+}
+
+impl<K: Ord, V> StorageMap<K, V> for std::collections::BTreeMap<K, V> {
+    fn get(&self, key: &K) -> Option<&V> {
+        std::collections::BTreeMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        std::collections::BTreeMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        std::collections::BTreeMap::insert(self, key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        std::collections::BTreeMap::remove(self, key)
+    }
+
+    fn contains_key(&self, key: &K) -> bool {
+        std::collections::BTreeMap::contains_key(self, key)
+    }
+
+    fn len(&self) -> usize {
+        std::collections::BTreeMap::len(self)
+    }
+}
+
+impl<K: Ord, V> OrderedStorageMap<K, V> for std::collections::BTreeMap<K, V> {
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a V)> where K: 'a, V: 'a {
+        std::collections::BTreeMap::iter(self)
+    }
+}
+
+impl<K: core::hash::Hash + Eq, V> StorageMap<K, V> for std::collections::HashMap<K, V> {
+    fn get(&self, key: &K) -> Option<&V> {
+        std::collections::HashMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        std::collections::HashMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        std::collections::HashMap::insert(self, key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        std::collections::HashMap::remove(self, key)
+    }
+
+    fn contains_key(&self, key: &K) -> bool {
+        std::collections::HashMap::contains_key(self, key)
+    }
+
+    fn len(&self) -> usize {
+        std::collections::HashMap::len(self)
+    }
+}
+
+// A hash map has no order of its own, so its entries are ordered by the deterministic hash of their
+// keys, and by key for keys with the same hash. This way, the order is the same on every machine.
+impl<K: core::hash::Hash + Hashable + Ord, V> OrderedStorageMap<K, V> for std::collections::HashMap<K, V> {
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a V)> where K: 'a, V: 'a {
+        let mut entries = std::collections::HashMap::iter(self).collect::<Vec<_>>() ;
+        entries.sort_by(|(a, _), (b, _)| a.hash_id().cmp(&b.hash_id()).then_with(|| a.cmp(b))) ;
+        entries.into_iter()
+    }
+}
+
+/// A trait which allows the runtime to report what went wrong while executing a block.
+pub trait Logger {
+    /// This function is called when the extrinsic at "index" in block "block" fails with "err".
+    /// The "call" of the extrinsic is named along with its pallet, like "balances::transfer".
+    fn log_extrinsic_error(&self, block: u32, index: usize, call: &str, err: &str) ;
+}
+
+/// A logger which writes everything to stderr.
+pub struct StderrLogger ;
+
+impl Logger for StderrLogger {
+    fn log_extrinsic_error(&self, block: u32, index: usize, call: &str, err: &str) {
+        eprintln!("Extrinsic Error\n\tBlock Number: {}\n\tExtrinsic Number: {}\n\tCall: {}\n\tError: {}", block, index, call, err) ;
+    }
+}
+
+/// A shared handle to some "Logger", so it can be stored on the runtime.
+/// By default, everything is logged to stderr.
+#[derive(Clone)]
+pub struct SharedLogger(pub std::rc::Rc<dyn Logger>) ;
+
+impl Logger for SharedLogger {
+    fn log_extrinsic_error(&self, block: u32, index: usize, call: &str, err: &str) {
+        self.0.log_extrinsic_error(block, index, call, err)
+    }
+}
+
+impl Default for SharedLogger {
+    fn default() -> Self {
+        Self(std::rc::Rc::new(StderrLogger))
+    }
+}
+
+impl core::fmt::Debug for SharedLogger {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("SharedLogger")
+    }
+}
+
+// The logger is not part of the state of the runtime, so any two loggers are considered equal. This
+// way, comparing two runtimes only compares their state.
+impl PartialEq for SharedLogger {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// Serializes a map as a list of key-value pairs, for maps whose keys are not strings, like tuples.
+/// Use it with #[serde(with = "crate::support::map_as_seq")].
+#[cfg(feature = "serde")]
+pub mod map_as_seq {
+    use std::collections::BTreeMap ;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer} ;
+
+    pub fn serialize<K, V, S>(map: &BTreeMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map.iter())
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<BTreeMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Ord,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(K, V)>::deserialize(deserializer) ?.into_iter().collect())
+    }
+}
+
+/// Put a runtime together from a single declaration of each of its pallets, starting with system.
+/// Every pallet is declared by its field name, the module it lives in, and the items of its "Config",
+/// along with the field attributes "#[macros::runtime]" takes, like "#[dispatch_with(balances)]".
+/// The fields which are not pallets, like a logger, may follow in a "with" block:
+///
+/// ```ignore
+/// construct_runtime! {
+///     #[derive(Debug, Clone, PartialEq)]
+///     pub struct Runtime {
+///         system: system { type AccountId = String ; /* ... */ },
+///         #[account_provider]
+///         balances: balances { type Balance = u64 ; /* ... */ },
+///     }
+///     with {
+///         logger: support::SharedLogger,
+///     }
+/// }
+/// ```
+///
+/// This declares the runtime struct with a "Pallet<Self>" field for every pallet, expands it with
+/// "#[macros::runtime]", and implements the "Config" of every pallet for the runtime.
+macro_rules! construct_runtime {
+    (
+        $( #[$meta:meta] )*
+        $vis:vis struct $runtime:ident {
+            $(
+                $( #[$attr:meta] )*
+                $name:ident: $module:ident { $( $config:tt )* }
+            ),* $(,)?
+        }
+        $(
+            with {
+                $(
+                    $( #[$field_attr:meta] )*
+                    $field:ident: $type:ty
+                ),* $(,)?
+            }
+        )?
+    ) => {
+        #[macros::runtime]
+        $( #[$meta] )*
+        $vis struct $runtime {
+            $(
+                $( #[$attr] )*
+                $name: $module::Pallet<Self>,
+            )*
+            $($(
+                $( #[$field_attr] )*
+                $field: $type,
+            )*)?
+        }
+
+        $(
+            impl $module::Config for $runtime {
+                $( $config )*
+            }
+        )*
+    } ;
+}
+pub(crate) use construct_runtime ;
+
+#[cfg(test)]
+mod tests {
+    use super::{DispatchError, Encode} ;
+
+    #[test]
+    fn error_messages() {
+        // Every error is displayed as its own message, which can be parsed back into it.
+        for &error in DispatchError::ALL {
+            let message = String::from(error) ;
+            assert_eq!(message, error.to_string()) ;
+            assert_eq!(message.parse(), Ok(error)) ;
+        }
+        assert_eq!("Not an error.".parse::<DispatchError>(), Err("Unknown error \"Not an error.\".".to_string())) ;
+
+        // Errors can be returned with "?" from code which returns any error.
+        fn fails() -> Result<(), Box<dyn std::error::Error>> {
+            Err(DispatchError::InsufficientFunds) ?;
+            Ok(())
+        }
+        assert_eq!(fails().unwrap_err().to_string(), "Insufficient funds.") ;
+    }
+
+    #[test]
+    fn stable_encodings() {
+        // Integers are little endian, and sequences are prefixed with their length.
+        assert_eq!(1u32.encode(), vec![1, 0, 0, 0]) ;
+        assert_eq!(258u16.encode(), vec![2, 1]) ;
+        assert_eq!(true.encode(), vec![1]) ;
+        assert_eq!("hi".to_string().encode(), vec![2, 0, 0, 0, b'h', b'i']) ;
+        assert_eq!(vec![1u8, 2].encode(), vec![2, 0, 0, 0, 1, 2]) ;
+        assert_eq!(Some(7u8).encode(), vec![1, 7]) ;
+        assert_eq!(None::<u8>.encode(), vec![0]) ;
+
+        // A block encodes its header, followed by its extrinsics.
+        let extrinsic = super::Extrinsic { caller: 1u8, call: 2u8, nonce: 3u8 } ;
+        assert_eq!(extrinsic.encode(), vec![1, 2, 3]) ;
+        let block = super::Block {
+            header: super::Header { parent_hash: 0u8, block_number: 1u8, timestamp: 2u8, extrinsics_root: 3u8 },
+            extrinsics: vec![extrinsic],
+        } ;
+        assert_eq!(block.encode(), vec![0, 1, 2, 3, 1, 0, 0, 0, 1, 2, 3]) ;
+
+        // The hash of an encoding never changes.
+        assert_eq!(super::hash(&[]), 0xcbf29ce484222325) ;
+        assert_eq!(super::hash(b"a"), 0xaf63dc4c8601ec8c) ;
+    }
+
+    #[test]
+    fn hash_blocks() {
+        use super::{Fnv, Hasher} ;
+
+        // A hasher which only counts the bytes, to check the hasher of the caller is used.
+        struct Length ;
+        impl Hasher for Length {
+            type Output = usize ;
+            fn hash(data: &[u8]) -> usize {
+                data.len()
+            }
+        }
+
+        let extrinsic = |nonce: u8| super::Extrinsic { caller: "alice".to_string(), call: 1u8, nonce } ;
+        let block = |extrinsics| super::Block::new::<Fnv>(0, 1u32, 1u64, extrinsics) ;
+
+        // Identical blocks hash equally, whichever hasher is used.
+        let first = block(vec![extrinsic(0), extrinsic(1)]) ;
+        let second = block(vec![extrinsic(0), extrinsic(1)]) ;
+        assert_eq!(first.header.hash_with::<Fnv>(), second.header.hash_with::<Fnv>()) ;
+        assert_eq!(first.header.hash_with::<Length>(), first.header.encode().len()) ;
+
+        // Changing a single extrinsic changes the hash.
+        let changed = block(vec![extrinsic(0), extrinsic(2)]) ;
+        assert_ne!(changed.header.hash_with::<Fnv>(), first.header.hash_with::<Fnv>()) ;
+
+        // A block built with another hasher commits to its extrinsics with that hasher.
+        struct Sum ;
+        impl Hasher for Sum {
+            type Output = u32 ;
+            fn hash(data: &[u8]) -> u32 {
+                data.iter().map(|byte| *byte as u32).sum()
+            }
+        }
+        let other = super::Block::new::<Sum>(0, 1u32, 1u64, vec![extrinsic(0), extrinsic(1)]) ;
+        assert_eq!(other.header.extrinsics_root, super::compute_extrinsics_root::<Sum, _>(&other.extrinsics)) ;
+        assert_ne!(other.header.extrinsics_root as u64, first.header.extrinsics_root) ;
+        assert_eq!(other.header.hash_with::<Sum>(), Sum::hash(&other.header.encode())) ;
+    }
+
+    #[test]
+    fn hash_accounts() {
+        use super::Hashable ;
+
+        // The hashes of account ids never change.
+        assert_eq!(super::hash_account(""), 0xcbf29ce484222325) ;
+        assert_eq!(super::hash_account("alice"), 0x508b2abb65a03907) ;
+        assert_eq!(super::hash_account("bob"), 0x004d4419134a0a54) ;
+        assert_eq!("alice".to_string().hash_id(), super::hash_account("alice")) ;
+
+        // Distinct account ids hash differently.
+        let ids = ["alice", "bob", "charlie", "alice ", "Alice", "ecila"] ;
+        let hashes = ids.iter().map(|id| id.hash_id()).collect::<std::collections::BTreeSet<_>>() ;
+        assert_eq!(hashes.len(), ids.len()) ;
+    }
+
+    #[test]
+    fn hash_map_order() {
+        use super::{Hashable, OrderedStorageMap} ;
+
+        // A hash map iterates in the order of the hashes of its keys, whatever the order their entries
+        // were inserted in.
+        let ids = ["alice", "bob", "charlie", "dave", "eve"].map(String::from) ;
+        let forward = ids.iter().cloned().zip(0..5).collect::<std::collections::HashMap<_, u32>>() ;
+        let backward = ids.iter().cloned().zip(0..5).rev().collect::<std::collections::HashMap<_, u32>>() ;
+        let order = OrderedStorageMap::iter(&forward).map(|(id, _)| id.clone()).collect::<Vec<_>>() ;
+        assert_eq!(order, OrderedStorageMap::iter(&backward).map(|(id, _)| id.clone()).collect::<Vec<_>>()) ;
+
+        let mut by_hash = ids.to_vec() ;
+        by_hash.sort_by_key(|id| id.hash_id()) ;
+        assert_eq!(order, by_hash) ;
+    }
+
+    #[test]
+    fn maps_encode_identically() {
+        // Equal maps encode to the same bytes, whatever the order their entries were inserted in.
+        let entries = (0..100u32).map(|i| (i, i * 2)).collect::<Vec<_>>() ;
+        let forward = entries.iter().copied().collect::<std::collections::HashMap<_, _>>() ;
+        let backward = entries.iter().rev().copied().collect::<std::collections::HashMap<_, _>>() ;
+        assert_eq!(forward.encode(), backward.encode()) ;
+
+        // And a hash map encodes like the ordered map with the same entries.
+        let ordered = entries.iter().copied().collect::<std::collections::BTreeMap<_, _>>() ;
+        assert_eq!(forward.encode(), ordered.encode()) ;
+    }
+}
@@ -1,27 +1,150 @@
-use num::traits::{Zero, One} ; 
+use num::traits::{CheckedAdd, CheckedSub, Zero, One} ; 
+use core::fmt::Debug ;
 use std::collections::BTreeMap ;
-use core::ops::AddAssign ;
+use crate::support::{DispatchError, DispatchResult, Journal, Weight} ;
+
+/// The map the nonces are stored in.
+/// Nonces are only ever looked up by account and never iterated over, so the "fast-nonce" feature
+/// can store them in a "HashMap" without changing any behavior.
+#[cfg(not(feature = "fast-nonce"))]
+type NonceMap<K, V> = BTreeMap<K, V> ;
+#[cfg(feature = "fast-nonce")]
+type NonceMap<K, V> = std::collections::HashMap<K, V> ;
+
+/// The bounds an account id needs to be a key of the "NonceMap".
+#[cfg(not(feature = "fast-nonce"))]
+pub trait NonceKey: Ord {}
+#[cfg(not(feature = "fast-nonce"))]
+impl<K: Ord> NonceKey for K {}
+#[cfg(feature = "fast-nonce")]
+pub trait NonceKey: Ord + core::hash::Hash {}
+#[cfg(feature = "fast-nonce")]
+impl<K: Ord + core::hash::Hash> NonceKey for K {}
 
 /// The Config trait for the System module.
 /// It contains the types AccountId, BlockNumber and Nonce, which is a BTreeMap from an account to their nonce. 
 pub trait Config {
     /// A type to identify account in our state machine.
     /// On a real blockchain, we would want this to be a cryptgraphic public key.
-    type AccountId: Ord + Clone ;
+    type AccountId: Debug + Ord + Clone + NonceKey ;
     /// A type to identify the current block number.
-    type BlockNumber: Zero + One + Copy + AddAssign ;
+    type BlockNumber: Zero + One + Copy + CheckedAdd + CheckedSub + Ord ;
     /// A type to keep count of the transactions a particular user has done.
-    type Nonce: Zero + One + Copy ;
+    type Nonce: Zero + One + Copy + CheckedAdd + CheckedSub + PartialEq ;
+    /// A type to identify a block, usually the output of some hashing function.
+    type Hash: Ord + Copy ;
+    /// The hash function used to hash blocks and the state, whose hashes are of type "Hash".
+    type Hashing: crate::support::Hasher<Output = Self::Hash> ;
+    /// A type to represent a point in time, like the number of milliseconds since some epoch.
+    type Moment: Zero + Copy + Ord ;
+    /// The number of recent block hashes to keep in storage. Older block hashes get pruned.
+    const BLOCK_HASH_COUNT: Self::BlockNumber ;
+    /// The maximum total weight of the extrinsics executed in a single block.
+    const MAX_BLOCK_WEIGHT: Weight ;
+    /// The events emitted by every pallet of the runtime, collected together by this pallet.
+    type RuntimeEvent ;
+    /// The number of block numbers kept in the extrinsic history of each account. Older block numbers
+    /// get dropped.
+    #[cfg(feature = "history")]
+    const MAX_HISTORY: usize = 100 ;
+    /// The maximum number of extrinsics an account may submit within "RATE_LIMIT_WINDOW" blocks, if
+    /// there is a limit. Extrinsics over the limit are rejected before they are dispatched.
+    const RATE_LIMIT: Option<u32> = None ;
+    /// The number of blocks the rate limit counts the extrinsics of an account over, ending at the
+    /// current block.
+    const RATE_LIMIT_WINDOW: u32 = 1 ;
+}
+
+/// The operations undoing the mutations of the storage of this pallet, as recorded in its journal.
+/// Events are not part of the state, so depositing them is not undone.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Undo<T: Config> {
+    /// Set the block number back to the given one.
+    BlockNumber(T::BlockNumber),
+    /// Set the nonce of an account back to the given one, or remove it if it had none.
+    Nonce(T::AccountId, Option<T::Nonce>),
+    /// Set the providers of an account back to the given number, or remove them if it had none.
+    Providers(T::AccountId, Option<u32>),
+    /// Set the hash of a block back to the given one, or remove it if it had none.
+    BlockHash(T::BlockNumber, Option<T::Hash>),
+    /// Set the timestamp back to the given one.
+    Timestamp(T::Moment),
+    /// Set the state root back to the given one.
+    StateRoot(Option<T::Hash>),
+    /// Set the extrinsic history of an account back to the given one, or remove it if it had none.
+    #[cfg(feature = "history")]
+    History(T::AccountId, Option<Vec<T::BlockNumber>>),
+    /// Set the blocks an account submitted extrinsics in back to the given ones, or remove them if it
+    /// had none.
+    LastBlocks(T::AccountId, Option<Vec<T::BlockNumber>>),
+}
+
+/// The index of the first event which has not been pruned or taken yet.
+/// It only ever increases, and only counts the events deposited so far, which are not part of the
+/// state. So like the journal, it never tells two pallets apart.
+#[derive(Debug, Clone, Copy, Default)]
+struct EventIndex(usize) ;
+
+impl PartialEq for EventIndex {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
 }
 
 /// This is the system Pallet.
 /// It handles low level state needed for our blockchain.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "T::AccountId: serde::Serialize + serde::de::DeserializeOwned, T::BlockNumber: serde::Serialize + serde::de::DeserializeOwned, T::Nonce: serde::Serialize + serde::de::DeserializeOwned, T::Hash: serde::Serialize + serde::de::DeserializeOwned, T::Moment: serde::Serialize + serde::de::DeserializeOwned"))]
 pub struct Pallet<T: Config> {
     /// The current block number.
     block_number: T::BlockNumber,
     /// A map from an account to their "nonce".
-    nonce: BTreeMap<T::AccountId, T::Nonce>,
+    nonce: NonceMap<T::AccountId, T::Nonce>,
+    /// A map from an existing account to the number of providers keeping it in existence.
+    /// Accounts without any provider do not exist, and are not stored. The state of a runtime from
+    /// before providers has none, and its accounts are provided for by the migrations creating them.
+    #[cfg_attr(feature = "serde", serde(default))]
+    providers: BTreeMap<T::AccountId, u32>,
+    /// A map from a recent block number to the hash of that block.
+    block_hash: BTreeMap<T::BlockNumber, T::Hash>,
+    /// A map from the name of a pallet to the version of the layout its storage is stored with.
+    /// Pallets without an entry are stored with version zero, which lets the state of a runtime from
+    /// before storage versions be loaded.
+    #[cfg_attr(feature = "serde", serde(default))]
+    storage_versions: BTreeMap<String, u32>,
+    /// A map from an account to the number of the block of each of its extrinsics within the rate
+    /// limit window, oldest first. Accounts are only tracked if there is a rate limit.
+    #[cfg_attr(feature = "serde", serde(default))]
+    last_blocks: BTreeMap<T::AccountId, Vec<T::BlockNumber>>,
+    /// The timestamp of the current block.
+    timestamp: T::Moment,
+    /// The state root after executing the current block, if any block was executed.
+    /// The state root commits to the state, so it cannot commit to itself.
+    #[encode(skip)]
+    state_root: Option<T::Hash>,
+    /// The events deposited by the runtime since they were last taken, in the order they happened.
+    /// Events are not part of the state, so they are never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    events: Vec<T::RuntimeEvent>,
+    /// The index of the first event in "events". Every event gets the next index when it is deposited,
+    /// and keeps it when the events before it are pruned or taken, so the indices only ever increase.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    first_event: EventIndex,
+    /// A map from an account to the numbers of the recent blocks it submitted extrinsics in, oldest first.
+    /// The history is exported with the state so it survives an import, but the state root does not
+    /// commit to it. It is undone with the nonces it follows.
+    #[cfg(feature = "history")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[encode(skip)]
+    history: BTreeMap<T::AccountId, Vec<T::BlockNumber>>,
+    /// The journal of the mutations made while executing the last block.
+    /// Like events, the journal is not part of the state.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    journal: Journal<Undo<T>>,
 }
 
 impl<T: Config> Pallet<T> {
@@ -29,31 +152,359 @@ impl<T: Config> Pallet<T> {
     pub fn new() -> Self {
         Self {
             block_number: T::BlockNumber::zero(),
-            nonce: BTreeMap::new() ,
+            nonce: NonceMap::new() ,
+            providers: BTreeMap::new(),
+            block_hash: BTreeMap::new(),
+            storage_versions: BTreeMap::new(),
+            last_blocks: BTreeMap::new(),
+            timestamp: T::Moment::zero(),
+            state_root: None,
+            events: Vec::new(),
+            first_event: EventIndex(0),
+            #[cfg(feature = "history")]
+            history: BTreeMap::new(),
+            journal: Journal::new(),
         }
     }
 
+    /// Reset the System pallet back to its initial block number and nonces.
+    /// This sets the block number back to zero and clears all nonces, leaving the rest of the storage
+    /// untouched.
+    pub fn reset(&mut self) {
+        self.block_number = T::BlockNumber::zero() ;
+        self.nonce.clear() ;
+    }
+
     /// Get the current block number.
     pub fn block_number(&self) -> T::BlockNumber {
         self.block_number
     }
 
+    /// Get the number of the block which is executed next.
+    /// This fails if the block number would overflow.
+    pub fn next_block_number(&self) -> Result<T::BlockNumber, DispatchError> {
+        self.block_number.checked_add(&T::BlockNumber::one()).ok_or(DispatchError::BlockNumberOverflow)
+    }
+
+    /// Get the number the header of the next block must carry, which is the current block number plus
+    /// one. No block can follow the largest block number, so this stays at the current block number
+    /// then, and "next_block_number" fails instead.
+    pub fn expected_block_number(&self) -> T::BlockNumber {
+        self.next_block_number().unwrap_or(self.block_number)
+    }
+
     /// Increment the block number by one.
-    pub fn inc_block_number(&mut self) {
-        self.block_number += T::BlockNumber::one() ;
+    /// This fails if the block number would overflow.
+    pub fn inc_block_number(&mut self) -> DispatchResult {
+        let block_number = self.next_block_number() ?;
+        self.journal.record(|| Undo::BlockNumber(self.block_number)) ;
+        self.block_number = block_number ;
+        Ok(())
+    }
+
+    /// Get the timestamp of the current block.
+    pub fn timestamp(&self) -> T::Moment {
+        self.timestamp
+    }
+
+    /// Set the timestamp of the current block.
+    pub fn set_timestamp(&mut self, timestamp: T::Moment) {
+        self.journal.record(|| Undo::Timestamp(self.timestamp)) ;
+        self.timestamp = timestamp ;
+    }
+
+    /// Get the state root after executing the current block, if any block was executed.
+    pub fn state_root(&self) -> Option<T::Hash> {
+        self.state_root
+    }
+
+    /// Set the state root after executing the current block.
+    pub fn set_state_root(&mut self, state_root: T::Hash) {
+        self.journal.record(|| Undo::StateRoot(self.state_root)) ;
+        self.state_root = Some(state_root) ;
+    }
+
+    /// Store the hash of the block with the given "number".
+    /// Only the last "BLOCK_HASH_COUNT" block hashes are kept, older ones are pruned.
+    pub fn set_block_hash(&mut self, number: T::BlockNumber, hash: T::Hash) {
+        self.journal.record(|| Undo::BlockHash(number, self.block_hash.get(&number).copied())) ;
+        self.block_hash.insert(number, hash) ;
+        if let Some(oldest_pruned) = number.checked_sub(&T::BLOCK_HASH_COUNT) {
+            let journal = &mut self.journal ;
+            self.block_hash.retain(|n, hash| {
+                let keep = *n > oldest_pruned ;
+                if !keep {
+                    journal.record(|| Undo::BlockHash(*n, Some(*hash))) ;
+                }
+                keep
+            }) ;
+        }
+    }
+
+    /// Get the hash of the block with the given "number", if it is still stored.
+    pub fn block_hash(&self, number: T::BlockNumber) -> Option<T::Hash> {
+        self.block_hash.get(&number).copied()
+    }
+
+    /// Get the version of the layout the storage of "pallet" is stored with.
+    pub fn storage_version(&self, pallet: &str) -> u32 {
+        self.storage_versions.get(pallet).copied().unwrap_or(0)
+    }
+
+    /// Record that the storage of "pallet" is now stored with "version", once it was migrated.
+    /// Upgrades happen between blocks, so this is not journaled.
+    pub fn set_storage_version(&mut self, pallet: &str, version: u32) {
+        self.storage_versions.insert(pallet.to_string(), version) ;
+    }
+
+    /// Get the nonce of an account "who".
+    /// If the account has no stored nonce, we return zero.
+    pub fn nonce(&self, who: &T::AccountId) -> T::Nonce {
+        *self.nonce.get(who).unwrap_or(&T::Nonce::zero())
     }
 
     /// Increment the nonce of an account. This helps us keep track of how many transactions
     /// each account has made.
+    /// If the nonce has already reached its maximum value, it is left unchanged.
     pub fn inc_nonce(&mut self, who: &T::AccountId) {
-        let nonce = *self.nonce.get(who).unwrap_or(&T::Nonce::zero()) ;
-        let new_nonce = nonce + T::Nonce::one()  ;
-        self.nonce.insert(who.clone(), new_nonce) ;
+        let nonce = self.nonce(who) ;
+        if let Some(new_nonce) = nonce.checked_add(&T::Nonce::one()) {
+            self.journal_nonce(who) ;
+            self.nonce.insert(who.clone(), new_nonce) ;
+            #[cfg(feature = "history")]
+            self.record_history(who) ;
+        }
+    }
+
+    /// Increment the nonce of every account in "callers" once for each time it appears, like calling
+    /// "inc_nonce" for each of them in turn.
+    /// The nonce of each account is only looked up and stored once, however often it appears.
+    pub fn inc_nonces(&mut self, callers: &[T::AccountId]) {
+        let mut counts = BTreeMap::<&T::AccountId, usize>::new() ;
+        for who in callers {
+            *counts.entry(who).or_insert(0) += 1 ;
+        }
+
+        for (who, count) in counts {
+            let mut nonce = self.nonce(who) ;
+            // Like "inc_nonce", the nonce stops at its maximum value.
+            for _ in 0..count {
+                match nonce.checked_add(&T::Nonce::one()) {
+                    Some(new_nonce) => nonce = new_nonce,
+                    None => break,
+                }
+            }
+            self.journal_nonce(who) ;
+            self.nonce.insert(who.clone(), nonce) ;
+            #[cfg(feature = "history")]
+            self.record_history(who) ;
+        }
+    }
+
+    /// Decrement the nonce of an account, undoing an earlier "inc_nonce".
+    /// If the nonce is already zero, it is left unchanged.
+    pub fn dec_nonce(&mut self, who: &T::AccountId) {
+        if let Some(new_nonce) = self.nonce(who).checked_sub(&T::Nonce::one()) {
+            self.journal_nonce(who) ;
+            if new_nonce.is_zero() {
+                self.nonce.remove(who) ;
+            } else {
+                self.nonce.insert(who.clone(), new_nonce) ;
+            }
+        }
+    }
+
+    /// Get the numbers of the recent blocks "who" submitted extrinsics in, oldest first.
+    /// A block is only listed once, however many extrinsics "who" submitted in it.
+    #[cfg(feature = "history")]
+    pub fn extrinsic_blocks(&self, who: &T::AccountId) -> &[T::BlockNumber] {
+        self.history.get(who).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Record that "who" submitted an extrinsic in the current block, since its nonce was incremented.
+    /// Only the last "MAX_HISTORY" block numbers are kept.
+    #[cfg(feature = "history")]
+    fn record_history(&mut self, who: &T::AccountId) {
+        if self.history.get(who).and_then(|history| history.last()) == Some(&self.block_number) {
+            return ;
+        }
+        self.journal.record(|| Undo::History(who.clone(), self.history.get(who).cloned())) ;
+        let history = self.history.entry(who.clone()).or_default() ;
+        history.push(self.block_number) ;
+        if history.len() > T::MAX_HISTORY {
+            history.remove(0) ;
+        }
+    }
+
+    /// Check that "who" may submit another extrinsic in the current block under the rate limit.
+    /// This function fails with "RateLimitExceeded" if the extrinsics of "who" recorded within the
+    /// window already reach the limit.
+    pub fn ensure_rate_limit(&self, who: &T::AccountId) -> DispatchResult {
+        let Some(limit) = T::RATE_LIMIT else {
+            return Ok(()) ;
+        } ;
+        let start = self.window_start() ;
+        let recent = self.last_blocks
+            .get(who)
+            .map_or(0, |blocks| blocks.iter().filter(|block| **block >= start).count()) ;
+        if recent >= limit as usize {
+            return Err(DispatchError::RateLimitExceeded) ;
+        }
+        Ok(())
+    }
+
+    /// Record that "who" submitted an extrinsic in the current block, for the rate limit.
+    /// The blocks which slid out of the window are dropped.
+    pub fn record_extrinsic(&mut self, who: &T::AccountId) {
+        if T::RATE_LIMIT.is_none() {
+            return ;
+        }
+        let start = self.window_start() ;
+        self.journal.record(|| Undo::LastBlocks(who.clone(), self.last_blocks.get(who).cloned())) ;
+        let blocks = self.last_blocks.entry(who.clone()).or_default() ;
+        blocks.retain(|block| *block >= start) ;
+        blocks.push(self.block_number) ;
+    }
+
+    /// The number of the oldest block within the rate limit window, which ends at the current block.
+    fn window_start(&self) -> T::BlockNumber {
+        let mut start = self.block_number ;
+        for _ in 1..T::RATE_LIMIT_WINDOW {
+            match start.checked_sub(&T::BlockNumber::one()) {
+                Some(block) => start = block,
+                None => break,
+            }
+        }
+        start
+    }
+
+    /// Record the nonce of "who" in the journal, before it is mutated.
+    fn journal_nonce(&mut self, who: &T::AccountId) {
+        self.journal.record(|| Undo::Nonce(who.clone(), self.nonce.get(who).copied())) ;
+    }
+
+    /// Check whether the account "who" exists, i.e. whether anything provides for it.
+    pub fn account_exists(&self, who: &T::AccountId) -> bool {
+        self.providers.contains_key(who)
+    }
+
+    /// Add a provider to the account "who", which brings the account into existence if it had none.
+    pub fn inc_providers(&mut self, who: &T::AccountId) {
+        self.journal.record(|| Undo::Providers(who.clone(), self.providers.get(who).copied())) ;
+        let providers = self.providers.entry(who.clone()).or_insert(0) ;
+        *providers = providers.saturating_add(1) ;
+    }
+
+    /// Remove a provider from the account "who". Once the last provider is gone, the account no
+    /// longer exists.
+    /// If the account has no providers, it is left unchanged.
+    pub fn dec_providers(&mut self, who: &T::AccountId) {
+        if let Some(providers) = self.providers.get_mut(who) {
+            self.journal.record(|| Undo::Providers(who.clone(), Some(*providers))) ;
+            *providers -= 1 ;
+            if *providers == 0 {
+                self.providers.remove(who) ;
+            }
+        }
+    }
+
+    /// Deposit an "event" emitted somewhere in the runtime.
+    pub fn deposit_event(&mut self, event: T::RuntimeEvent) {
+        self.events.push(event) ;
+    }
+
+    /// Take all the events deposited since they were last taken, leaving no events behind.
+    /// Like pruning, taking the events keeps the indices going, so the next event deposited gets the
+    /// index after the last event taken.
+    pub fn take_events(&mut self) -> Vec<T::RuntimeEvent> {
+        self.first_event.0 += self.events.len() ;
+        core::mem::take(&mut self.events)
+    }
+
+    /// Get the events deposited since they were last taken, across blocks, without taking them.
+    pub fn events(&self) -> &[T::RuntimeEvent] {
+        &self.events
+    }
+
+    /// Get the index the next event deposited will get, which is one more than the index of the
+    /// last event deposited.
+    pub fn next_event_index(&self) -> usize {
+        self.first_event.0 + self.events.len()
+    }
+
+    /// Get the events deposited with an index of at least "index", oldest first.
+    /// A consumer which has read the events up to "next_event_index" can pass it here later on, to
+    /// read only the events deposited since. The events which were pruned are left out.
+    pub fn events_since(&self, index: usize) -> &[T::RuntimeEvent] {
+        let start = index.saturating_sub(self.first_event.0).min(self.events.len()) ;
+        &self.events[start..]
+    }
+
+    /// Drop the events deposited with an index below "up_to", keeping the indices of the others.
+    pub fn prune_events(&mut self, up_to: usize) {
+        let pruned = up_to.saturating_sub(self.first_event.0).min(self.events.len()) ;
+        self.events.drain(..pruned) ;
+        self.first_event.0 += pruned ;
+    }
+
+    /// Drop the events deposited with an index of at least "index", like the events of calls which
+    /// were rolled back. The next event deposited gets "index" again.
+    pub fn drop_events_since(&mut self, index: usize) {
+        let kept = index.saturating_sub(self.first_event.0).min(self.events.len()) ;
+        self.events.truncate(kept) ;
+    }
+
+    /// Reverse a mutation of the storage, as recorded by "undo" in the journal.
+    fn undo(&mut self, undo: Undo<T>) {
+        match undo {
+            Undo::BlockNumber(block_number) => self.block_number = block_number,
+            Undo::Nonce(who, nonce) => crate::support::restore_entry(&mut self.nonce, who, nonce),
+            Undo::Providers(who, providers) => crate::support::restore_entry(&mut self.providers, who, providers),
+            Undo::BlockHash(number, hash) => crate::support::restore_entry(&mut self.block_hash, number, hash),
+            Undo::Timestamp(timestamp) => self.timestamp = timestamp,
+            Undo::StateRoot(state_root) => self.state_root = state_root,
+            #[cfg(feature = "history")]
+            Undo::History(who, history) => crate::support::restore_entry(&mut self.history, who, history),
+            Undo::LastBlocks(who, blocks) => crate::support::restore_entry(&mut self.last_blocks, who, blocks),
+        }
+    }
+}
+
+// The mutations of this pallet are journaled while a block is executed, so the runtime can undo them.
+impl<T: Config> crate::support::Journaled for Pallet<T> {
+    fn start_journal(&mut self) {
+        self.journal.start() ;
+    }
+
+    fn stop_journal(&mut self) {
+        self.journal.stop() ;
+    }
+
+    fn undo_journal(&mut self) {
+        for undo in self.journal.take() {
+            self.undo(undo) ;
+        }
+    }
+
+    fn checkpoint_journal(&mut self) -> crate::support::JournalCheckpoint {
+        self.journal.checkpoint()
+    }
+
+    fn commit_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        self.journal.commit(checkpoint) ;
+    }
+
+    fn rollback_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        for undo in self.journal.rollback(checkpoint) {
+            self.undo(undo) ;
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::support::DispatchError ;
+
     #[test] 
     fn init_system() {
         struct TestConfig ;
@@ -61,13 +512,19 @@ mod test {
             type AccountId = String ;
             type BlockNumber = u32 ;
             type Nonce = u32 ;
+            type Hash = u64 ;
+            type Hashing = crate::support::Fnv ;
+            type Moment = u64 ;
+            const BLOCK_HASH_COUNT: u32 = 10 ;
+            const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+            type RuntimeEvent = () ;
         } 
 
         // Instantiating a system struct.
         let mut system = crate::system::Pallet::<TestConfig>::new() ;
 
         // Increment the current block number.
-        system.inc_block_number() ;
+        let _ = system.inc_block_number() ;
 
         // Increment nonce of "alice".
         system.inc_nonce(&"alice".to_string()) ;
@@ -76,9 +533,417 @@ mod test {
         assert_eq!(system.block_number, 1) ;
 
         // Assert nonce of "alice" is updated or not.
-        assert_eq!(system.nonce.get(&"alice".to_string()), Some(&1)) ;
+        assert_eq!(system.nonce.get("alice"), Some(&1)) ;
 
         // Assert nonce of "bob" is none.
-        assert_eq!(system.nonce.get(&"bob".to_string()), None) ;
+        assert_eq!(system.nonce.get("bob"), None) ;
+    }
+
+    #[test]
+    fn nonce_does_not_overflow() {
+        struct TestConfig ;
+        impl crate::system::Config for TestConfig {
+            type AccountId = String ;
+            type BlockNumber = u32 ;
+            type Nonce = u8 ;
+            type Hash = u64 ;
+            type Hashing = crate::support::Fnv ;
+            type Moment = u64 ;
+            const BLOCK_HASH_COUNT: u32 = 10 ;
+            const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+            type RuntimeEvent = () ;
+        }
+
+        let mut system = crate::system::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+
+        // Increment the nonce of "alice" up to its maximum value.
+        for _ in 0..255 {
+            system.inc_nonce(&alice) ;
+        }
+        assert_eq!(system.nonce.get(&alice), Some(&255)) ;
+
+        // The next increment does not wrap around to zero.
+        system.inc_nonce(&alice) ;
+        assert_eq!(system.nonce.get(&alice), Some(&255)) ;
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn block_number_does_not_overflow() {
+        struct TestConfig ;
+        impl crate::system::Config for TestConfig {
+            type AccountId = String ;
+            type BlockNumber = u8 ;
+            type Nonce = u32 ;
+            type Hash = u64 ;
+            type Hashing = crate::support::Fnv ;
+            type Moment = u64 ;
+            const BLOCK_HASH_COUNT: u8 = 10 ;
+            const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+            type RuntimeEvent = () ;
+        }
+
+        let mut system = crate::system::Pallet::<TestConfig>::new() ;
+
+        // Advance the block number up to its maximum value.
+        for _ in 0..255 {
+            assert_eq!(system.inc_block_number(), Ok(())) ;
+        }
+        assert_eq!(system.block_number(), 255) ;
+
+        // The next increment is rejected rather than wrapping around to zero.
+        assert_eq!(system.next_block_number(), Err(DispatchError::BlockNumberOverflow)) ;
+        assert_eq!(system.expected_block_number(), 255) ;
+        assert_eq!(system.inc_block_number(), Err(DispatchError::BlockNumberOverflow)) ;
+        assert_eq!(system.block_number(), 255) ;
+    }
+
+    #[test]
+    fn read_nonce() {
+        struct TestConfig ;
+        impl crate::system::Config for TestConfig {
+            type AccountId = String ;
+            type BlockNumber = u32 ;
+            type Nonce = u32 ;
+            type Hash = u64 ;
+            type Hashing = crate::support::Fnv ;
+            type Moment = u64 ;
+            const BLOCK_HASH_COUNT: u32 = 10 ;
+            const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+            type RuntimeEvent = () ;
+        }
+
+        let mut system = crate::system::Pallet::<TestConfig>::new() ;
+
+        // Increment the nonce of "alice" twice.
+        system.inc_nonce(&"alice".to_string()) ;
+        system.inc_nonce(&"alice".to_string()) ;
+        assert_eq!(system.nonce(&"alice".to_string()), 2) ;
+
+        // The nonce of "bob" was never set, so it reads as zero.
+        assert_eq!(system.nonce(&"bob".to_string()), 0) ;
+    }
+
+    #[test]
+    fn batch_nonce_increments() {
+        struct TestConfig ;
+        impl crate::system::Config for TestConfig {
+            type AccountId = String ;
+            type BlockNumber = u32 ;
+            type Nonce = u8 ;
+            type Hash = u64 ;
+            type Hashing = crate::support::Fnv ;
+            type Moment = u64 ;
+            const BLOCK_HASH_COUNT: u32 = 10 ;
+            const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+            type RuntimeEvent = () ;
+        }
+
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let charlie = "charlie".to_string() ;
+        // A block where alice sends five extrinsics, around one from bob.
+        let callers = vec![alice.clone(), alice.clone(), bob.clone(), alice.clone(), alice.clone(), alice.clone()] ;
+
+        let mut sequential = crate::system::Pallet::<TestConfig>::new() ;
+        let mut batched = crate::system::Pallet::<TestConfig>::new() ;
+        sequential.inc_nonce(&alice) ;
+        batched.inc_nonce(&alice) ;
+
+        for who in &callers {
+            sequential.inc_nonce(who) ;
+        }
+        batched.inc_nonces(&callers) ;
+        assert_eq!(batched.nonce(&alice), 6) ;
+        assert_eq!(batched.nonce(&bob), 1) ;
+        assert_eq!(batched.nonce, sequential.nonce) ;
+
+        // Both stop at the maximum nonce.
+        for _ in 0..254 {
+            sequential.inc_nonce(&charlie) ;
+        }
+        let callers = vec![charlie.clone() ; 254] ;
+        batched.inc_nonces(&callers) ;
+        assert_eq!(batched.nonce(&charlie), 254) ;
+        sequential.inc_nonce(&charlie) ;
+        sequential.inc_nonce(&charlie) ;
+        batched.inc_nonces(&[charlie.clone(), charlie.clone()]) ;
+        assert_eq!(batched.nonce(&charlie), u8::MAX) ;
+        assert_eq!(batched.nonce, sequential.nonce) ;
+    }
+
+    #[cfg(feature = "fast-nonce")]
+    #[test]
+    fn fast_nonce() {
+        struct TestConfig ;
+        impl crate::system::Config for TestConfig {
+            type AccountId = String ;
+            type BlockNumber = u32 ;
+            type Nonce = u32 ;
+            type Hash = u64 ;
+            type Hashing = crate::support::Fnv ;
+            type Moment = u64 ;
+            const BLOCK_HASH_COUNT: u32 = 10 ;
+            const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+            type RuntimeEvent = () ;
+        }
+
+        let mut system = crate::system::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+
+        // The nonces are stored in a hash map, but read and change just the same.
+        let _: &std::collections::HashMap<String, u32> = &system.nonce ;
+        system.inc_nonce(&alice) ;
+        system.inc_nonce(&alice) ;
+        assert_eq!(system.nonce(&alice), 2) ;
+        system.dec_nonce(&alice) ;
+        assert_eq!(system.nonce(&alice), 1) ;
+        system.dec_nonce(&alice) ;
+        assert_eq!(system.nonce.get(&alice), None) ;
+        assert_eq!(system.nonce(&"bob".to_string()), 0) ;
+    }
+
+    #[cfg(feature = "history")]
+    #[test]
+    fn extrinsic_history() {
+        struct TestConfig ;
+        impl crate::system::Config for TestConfig {
+            type AccountId = String ;
+            type BlockNumber = u32 ;
+            type Nonce = u32 ;
+            type Hash = u64 ;
+            type Hashing = crate::support::Fnv ;
+            type Moment = u64 ;
+            const BLOCK_HASH_COUNT: u32 = 10 ;
+            const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+            type RuntimeEvent = () ;
+            const MAX_HISTORY: usize = 2 ;
+        }
+
+        let mut system = crate::system::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+
+        // Every block is listed once, however many times the nonce is incremented in it.
+        for _ in 0..3 {
+            system.inc_block_number().expect("Block number overflow.") ;
+            system.inc_nonce(&alice) ;
+            system.inc_nonces(&[alice.clone(), alice.clone()]) ;
+        }
+        // Only the last "MAX_HISTORY" blocks are kept.
+        assert_eq!(system.extrinsic_blocks(&alice), &[2, 3]) ;
+        assert_eq!(system.extrinsic_blocks(&"bob".to_string()), &[] as &[u32]) ;
+    }
+
+    #[test]
+    fn rate_limit() {
+        struct TestConfig ;
+        impl crate::system::Config for TestConfig {
+            type AccountId = String ;
+            type BlockNumber = u32 ;
+            type Nonce = u32 ;
+            type Hash = u64 ;
+            type Hashing = crate::support::Fnv ;
+            type Moment = u64 ;
+            const BLOCK_HASH_COUNT: u32 = 10 ;
+            const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+            type RuntimeEvent = () ;
+            const RATE_LIMIT: Option<u32> = Some(3) ;
+            const RATE_LIMIT_WINDOW: u32 = 3 ;
+        }
+
+        let mut system = crate::system::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+
+        // Three extrinsics fit into the window, spread over blocks 1 and 2.
+        system.inc_block_number().expect("Block number overflow.") ;
+        for _ in 0..2 {
+            assert_eq!(system.ensure_rate_limit(&alice), Ok(())) ;
+            system.record_extrinsic(&alice) ;
+        }
+        system.inc_block_number().expect("Block number overflow.") ;
+        assert_eq!(system.ensure_rate_limit(&alice), Ok(())) ;
+        system.record_extrinsic(&alice) ;
+
+        // The fourth is rejected until block 1 slides out of the window, while other accounts are not limited.
+        assert_eq!(system.ensure_rate_limit(&alice), Err(DispatchError::RateLimitExceeded)) ;
+        assert_eq!(system.ensure_rate_limit(&bob), Ok(())) ;
+        system.inc_block_number().expect("Block number overflow.") ;
+        assert_eq!(system.ensure_rate_limit(&alice), Err(DispatchError::RateLimitExceeded)) ;
+        system.inc_block_number().expect("Block number overflow.") ;
+        assert_eq!(system.ensure_rate_limit(&alice), Ok(())) ;
+        system.record_extrinsic(&alice) ;
+        assert_eq!(system.last_blocks.get(&alice), Some(&vec![2, 4])) ;
+    }
+
+    #[test]
+    fn store_block_hashes() {
+        struct TestConfig ;
+        impl crate::system::Config for TestConfig {
+            type AccountId = String ;
+            type BlockNumber = u32 ;
+            type Nonce = u32 ;
+            type Hash = u64 ;
+            type Hashing = crate::support::Fnv ;
+            type Moment = u64 ;
+            const BLOCK_HASH_COUNT: u32 = 3 ;
+            const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+            type RuntimeEvent = () ;
+        }
+
+        let mut system = crate::system::Pallet::<TestConfig>::new() ;
+
+        // Store and read back block hashes.
+        system.set_block_hash(1, 100) ;
+        system.set_block_hash(2, 200) ;
+        system.set_block_hash(3, 300) ;
+        assert_eq!(system.block_hash(1), Some(100)) ;
+        assert_eq!(system.block_hash(3), Some(300)) ;
+        assert_eq!(system.block_hash(4), None) ;
+
+        // Once a block falls out of the retention window, its hash is pruned.
+        system.set_block_hash(4, 400) ;
+        assert_eq!(system.block_hash(1), None) ;
+        assert_eq!(system.block_hash(2), Some(200)) ;
+        assert_eq!(system.block_hash(4), Some(400)) ;
+        assert_eq!(system.block_hash.len(), 3) ;
+    }
+
+    #[test]
+    fn reset_system() {
+        struct TestConfig ;
+        impl crate::system::Config for TestConfig {
+            type AccountId = String ;
+            type BlockNumber = u32 ;
+            type Nonce = u32 ;
+            type Hash = u64 ;
+            type Hashing = crate::support::Fnv ;
+            type Moment = u64 ;
+            const BLOCK_HASH_COUNT: u32 = 10 ;
+            const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+            type RuntimeEvent = () ;
+        }
+
+        let mut system = crate::system::Pallet::<TestConfig>::new() ;
+        let _ = system.inc_block_number() ;
+        system.inc_nonce(&"alice".to_string()) ;
+        system.inc_nonce(&"bob".to_string()) ;
+        system.inc_providers(&"alice".to_string()) ;
+        system.set_timestamp(5) ;
+
+        system.reset() ;
+
+        // The block number is back to zero, and all nonces are gone.
+        assert_eq!(system.block_number(), 0) ;
+        assert!(system.nonce.is_empty()) ;
+        assert_eq!(system.nonce(&"alice".to_string()), 0) ;
+
+        // Nothing else is reset.
+        assert!(system.account_exists(&"alice".to_string())) ;
+        assert_eq!(system.timestamp(), 5) ;
+    }
+
+    #[test]
+    fn deposit_events() {
+        struct TestConfig ;
+        impl crate::system::Config for TestConfig {
+            type AccountId = String ;
+            type BlockNumber = u32 ;
+            type Nonce = u32 ;
+            type Hash = u64 ;
+            type Hashing = crate::support::Fnv ;
+            type Moment = u64 ;
+            const BLOCK_HASH_COUNT: u32 = 10 ;
+            const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+            type RuntimeEvent = &'static str ;
+        }
+
+        let mut system = crate::system::Pallet::<TestConfig>::new() ;
+        system.deposit_event("first") ;
+        system.deposit_event("second") ;
+
+        // Events are taken in the order they were deposited, and only once.
+        assert_eq!(system.take_events(), vec!["first", "second"]) ;
+        assert!(system.take_events().is_empty()) ;
+
+        // Taking the events does not start the indices over, so an old cursor skips the taken events.
+        assert_eq!(system.next_event_index(), 2) ;
+        for event in ["third", "fourth", "fifth"] {
+            system.deposit_event(event) ;
+        }
+        assert_eq!(system.events_since(0), &["third", "fourth", "fifth"]) ;
+        assert_eq!(system.events_since(3), &["fourth", "fifth"]) ;
+        assert!(system.events_since(5).is_empty()) ;
+
+        // Events keep their index when the events before them are pruned.
+        system.prune_events(4) ;
+        assert_eq!(system.events(), &["fifth"]) ;
+        assert_eq!(system.events_since(3), &["fifth"]) ;
+        system.prune_events(3) ;
+        assert_eq!(system.events(), &["fifth"]) ;
+        assert_eq!(system.next_event_index(), 5) ;
+    }
+
+    #[test]
+    fn decrement_nonce() {
+        struct TestConfig ;
+        impl crate::system::Config for TestConfig {
+            type AccountId = String ;
+            type BlockNumber = u32 ;
+            type Nonce = u32 ;
+            type Hash = u64 ;
+            type Hashing = crate::support::Fnv ;
+            type Moment = u64 ;
+            const BLOCK_HASH_COUNT: u32 = 10 ;
+            const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+            type RuntimeEvent = () ;
+        }
+
+        let mut system = crate::system::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+
+        system.inc_nonce(&alice) ;
+        system.inc_nonce(&alice) ;
+        system.dec_nonce(&alice) ;
+        assert_eq!(system.nonce(&alice), 1) ;
+
+        // Decrementing back to zero leaves nothing in storage, and decrementing a zero nonce does
+        // nothing at all.
+        system.dec_nonce(&alice) ;
+        assert!(system.nonce.is_empty()) ;
+        system.dec_nonce(&alice) ;
+        assert_eq!(system.nonce(&alice), 0) ;
+    }
+
+    #[test]
+    fn account_providers() {
+        struct TestConfig ;
+        impl crate::system::Config for TestConfig {
+            type AccountId = String ;
+            type BlockNumber = u32 ;
+            type Nonce = u32 ;
+            type Hash = u64 ;
+            type Hashing = crate::support::Fnv ;
+            type Moment = u64 ;
+            const BLOCK_HASH_COUNT: u32 = 10 ;
+            const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+            type RuntimeEvent = () ;
+        }
+
+        let mut system = crate::system::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        assert!(!system.account_exists(&alice)) ;
+
+        // The account exists for as long as it has any provider.
+        system.inc_providers(&alice) ;
+        system.inc_providers(&alice) ;
+        system.dec_providers(&alice) ;
+        assert!(system.account_exists(&alice)) ;
+        system.dec_providers(&alice) ;
+        assert!(!system.account_exists(&alice)) ;
+
+        // Removing a provider from an account without any does nothing.
+        system.dec_providers(&alice) ;
+        assert!(system.providers.is_empty()) ;
+    }
+}
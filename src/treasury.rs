@@ -0,0 +1,275 @@
+use num::traits::{CheckedAdd, CheckedSub, Zero} ;
+use crate::support::{DispatchError, DispatchResult, Currency, Journal, Origin} ;
+
+/// The Config trait for the Treasury module.
+/// It contains the currency in which fees are paid, and in which the treasury spends.
+pub trait Config: crate::balances::Config {
+    /// The currency in which fees are paid into the pot, and spent from it.
+    type Currency: Currency<Self::AccountId, Balance = Self::Balance> ;
+    /// The fee charged for every extrinsic, which is paid into the pot.
+    const FEE_PER_EXTRINSIC: Self::Balance ;
+}
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<T: Config> {
+    /// Some "amount" was paid into the pot by "who".
+    Deposited {
+        who: T::AccountId,
+        amount: T::Balance,
+    },
+    /// Some "amount" was spent from the pot, and paid out to "to".
+    Spent {
+        to: T::AccountId,
+        amount: T::Balance,
+    },
+}
+
+/// The operations undoing the mutations of the storage of this pallet, as recorded in its journal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Undo<T: Config> {
+    /// Set the balance held by the treasury back to the given one.
+    Pot(T::Balance),
+}
+
+/// This is the Treasury module.
+/// It holds a pot of balance, which accumulates the fees paid by accounts.
+#[derive(Debug, Clone, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "T::Balance: serde::Serialize + serde::de::DeserializeOwned"))]
+pub struct Pallet<T: Config> {
+    /// The balance held by the treasury.
+    /// Fees are withdrawn from the currency when they are paid into the pot, so the pot is not part of
+    /// the total issuance of the currency until it is spent.
+    pot: T::Balance,
+    /// The events emitted since they were last taken.
+    /// Events are not part of the state, so they are never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    events: Vec<Event<T>>,
+    /// The journal of the mutations made while executing the last block.
+    /// Like events, the journal is not part of the state.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    journal: Journal<Undo<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+    /// Create a new instance of the treasury module.
+    pub fn new() -> Self {
+        Self {
+            pot: T::Balance::zero(),
+            events: Vec::new(),
+            journal: Journal::new(),
+        }
+    }
+
+    /// Get the balance held by the treasury.
+    pub fn pot(&self) -> T::Balance {
+        self.pot
+    }
+
+    /// Charge "who" a fee of "amount", which is paid into the pot rather than burned.
+    /// This function fails if "who" cannot afford the fee, in which case nothing is paid.
+    pub fn charge_fee(&mut self, currency: &mut T::Currency, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+        let pot = self.pot.checked_add(&amount).ok_or(DispatchError::Overflow) ?;
+        currency.withdraw(who, amount) ?;
+        self.set_pot(pot) ;
+
+        self.events.push(Event::Deposited { who: who.clone(), amount }) ;
+        Ok(())
+    }
+
+    /// Take all the events emitted since they were last taken, leaving no events behind.
+    pub fn take_events(&mut self) -> Vec<Event<T>> {
+        core::mem::take(&mut self.events)
+    }
+
+    /// Set the balance held by the treasury to "pot".
+    fn set_pot(&mut self, pot: T::Balance) {
+        self.journal.record(|| Undo::Pot(self.pot)) ;
+        self.pot = pot ;
+    }
+
+    /// Reverse a mutation of the storage, as recorded by "undo" in the journal.
+    fn undo(&mut self, undo: Undo<T>) {
+        match undo {
+            Undo::Pot(pot) => self.pot = pot,
+        }
+    }
+}
+
+// The mutations of this pallet are journaled while a block is executed, so the runtime can undo them.
+impl<T: Config> crate::support::Journaled for Pallet<T> {
+    fn start_journal(&mut self) {
+        self.journal.start() ;
+    }
+
+    fn stop_journal(&mut self) {
+        self.journal.stop() ;
+    }
+
+    fn undo_journal(&mut self) {
+        for undo in self.journal.take() {
+            self.undo(undo) ;
+        }
+    }
+
+    fn checkpoint_journal(&mut self) -> crate::support::JournalCheckpoint {
+        self.journal.checkpoint()
+    }
+
+    fn commit_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        self.journal.commit(checkpoint) ;
+    }
+
+    fn rollback_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        for undo in self.journal.rollback(checkpoint) {
+            self.undo(undo) ;
+        }
+    }
+}
+
+// The fee of every extrinsic is paid into the pot. No fee is charged, and no event emitted, if the fee
+// is zero.
+impl<T: Config> crate::support::ChargeExtrinsicFee<T::AccountId> for Pallet<T> {
+    type Context = T::Currency ;
+
+    fn charge_extrinsic_fee(&mut self, currency: &mut T::Currency, who: &T::AccountId) -> DispatchResult {
+        if T::FEE_PER_EXTRINSIC.is_zero() {
+            return Ok(()) ;
+        }
+        self.charge_fee(currency, who, T::FEE_PER_EXTRINSIC).map_err(|_| DispatchError::CannotPayFee)
+    }
+}
+
+// The treasury pallet does not need to execute any logic as part of a block.
+impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {}
+
+// Only these functions will be called by the user from this pallet. They are given the currency of
+// the runtime, so the treasury can pay out from its pot.
+#[macros::call(context = T::Currency)]
+impl<T: Config> Pallet<T> {
+    /// Spend some "amount" from the pot, paying it out to "to".
+    /// This is a privileged call, which can only be made by the root origin. It fails if the pot
+    /// holds less than "amount".
+    pub fn spend(
+        &mut self,
+        origin: Origin<T::AccountId>,
+        currency: &mut T::Currency,
+        to: T::AccountId,
+        amount: T::Balance
+    ) -> DispatchResult {
+        origin.ensure_root() ?;
+        let pot = self.pot.checked_sub(&amount).ok_or(DispatchError::InsufficientTreasuryFunds) ?;
+        currency.deposit(&to, amount) ?;
+        self.set_pot(pot) ;
+
+        self.events.push(Event::Spent { to, amount }) ;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::support::{DispatchError, Origin} ;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestConfig ;
+    impl crate::system::Config for TestConfig {
+        type AccountId = String ;
+        type BlockNumber = u32 ;
+        type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
+    }
+
+    impl crate::balances::Config for TestConfig {
+        type Balance = u128 ;
+        const EXISTENTIAL_DEPOSIT: u128 = 1 ;
+        const TRANSFER_FEE: u128 = 0 ;
+        const MINIMUM_TRANSFER: u128 = 0 ;
+        fn fee_account() -> String {
+            "fees".to_string()
+        }
+        type AccountStore = std::collections::BTreeMap<String, crate::balances::AccountData<u128>> ;
+    }
+
+    impl super::Config for TestConfig {
+        type Currency = crate::balances::Pallet<TestConfig> ;
+        const FEE_PER_EXTRINSIC: u128 = 2 ;
+    }
+
+    #[test]
+    fn fees_accumulate() {
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+        let mut treasury = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+        let _ = balances.mint(&bob, 5) ;
+
+        assert_eq!(treasury.charge_fee(&mut balances, &alice, 10), Ok(())) ;
+        assert_eq!(treasury.charge_fee(&mut balances, &bob, 5), Ok(())) ;
+        assert_eq!(treasury.pot(), 15) ;
+        assert_eq!(balances.balance(&alice), 90) ;
+        assert_eq!(balances.balance(&bob), 0) ;
+
+        // A fee which cannot be afforded is not paid at all.
+        assert_eq!(treasury.charge_fee(&mut balances, &bob, 1), Err(DispatchError::InsufficientFunds)) ;
+        assert_eq!(treasury.pot(), 15) ;
+
+        assert_eq!(
+            treasury.take_events(),
+            vec![
+                super::Event::Deposited { who: alice.clone(), amount: 10 },
+                super::Event::Deposited { who: bob.clone(), amount: 5 },
+            ]
+        ) ;
+    }
+
+    #[test]
+    fn spend_from_pot() {
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+        let mut treasury = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+        let _ = treasury.charge_fee(&mut balances, &alice, 50) ;
+
+        // Only root can spend from the pot.
+        assert_eq!(treasury.spend(Origin::Signed(alice.clone()), &mut balances, bob.clone(), 10), Err(DispatchError::BadOrigin)) ;
+        assert_eq!(treasury.spend(Origin::Root, &mut balances, bob.clone(), 60), Err(DispatchError::InsufficientTreasuryFunds)) ;
+        assert_eq!(treasury.pot(), 50) ;
+
+        assert_eq!(treasury.spend(Origin::Root, &mut balances, bob.clone(), 30), Ok(())) ;
+        assert_eq!(treasury.pot(), 20) ;
+        assert_eq!(balances.balance(&bob), 30) ;
+    }
+
+    #[test]
+    fn charge_extrinsic_fee() {
+        use crate::support::ChargeExtrinsicFee ;
+
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+        let mut treasury = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+        let _ = balances.mint(&bob, 1) ;
+
+        // Every extrinsic is charged the same fee.
+        assert_eq!(treasury.charge_extrinsic_fee(&mut balances, &alice), Ok(())) ;
+        assert_eq!(treasury.charge_extrinsic_fee(&mut balances, &alice), Ok(())) ;
+        assert_eq!(balances.balance(&alice), 96) ;
+        assert_eq!(treasury.pot(), 4) ;
+
+        assert_eq!(treasury.charge_extrinsic_fee(&mut balances, &bob), Err(DispatchError::CannotPayFee)) ;
+        assert_eq!(balances.balance(&bob), 1) ;
+        assert_eq!(treasury.pot(), 4) ;
+    }
+}
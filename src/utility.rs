@@ -0,0 +1,202 @@
+use core::fmt::Debug ;
+use crate::support::{BatchMode, DispatchError, DispatchResult, GetWeight, Origin} ;
+
+/// The Config trait for the Utility pallet.
+/// It contains the calls which can be batched.
+pub trait Config: crate::system::Config {
+    /// The calls of the runtime, which can be dispatched together as a batch.
+    /// A batch declares the weight of its calls, plus a base weight of its own.
+    type RuntimeCall: Debug + Clone + PartialEq + GetWeight ;
+}
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<T: Config> {
+    /// Every call of a batch made by "origin" succeeded, of which there were "succeeded".
+    Completed {
+        origin: Origin<T::AccountId>,
+        succeeded: u32,
+    },
+    /// A batch made by "origin" was interrupted by a call failing with "error", after the "succeeded"
+    /// calls before it.
+    Interrupted {
+        origin: Origin<T::AccountId>,
+        succeeded: u32,
+        error: DispatchError,
+    },
+    /// Every call of a forced batch made by "origin" was dispatched, of which "succeeded" succeeded and
+    /// "failed" failed.
+    CompletedWithErrors {
+        origin: Origin<T::AccountId>,
+        succeeded: u32,
+        failed: u32,
+    },
+}
+
+/// This is the Utility pallet.
+/// It allows making many calls at once, which the runtime dispatches one after the other on behalf of
+/// the origin of the batch.
+#[derive(Debug, Clone, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
+pub struct Pallet<T: Config> {
+    /// The batches waiting to be taken by the runtime.
+    /// They are taken right after the call queuing them, so they are never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    queued: Vec<crate::support::Batch<Origin<T::AccountId>, T::RuntimeCall>>,
+    /// The events emitted since they were last taken.
+    /// Events are not part of the state, so they are never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    events: Vec<Event<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+    /// Create a new instance of the utility pallet.
+    pub fn new() -> Self {
+        Self { queued: Vec::new(), events: Vec::new() }
+    }
+
+    /// Take all the events emitted since they were last taken, leaving no events behind.
+    pub fn take_events(&mut self) -> Vec<Event<T>> {
+        core::mem::take(&mut self.events)
+    }
+
+    /// Queue up the "calls" as a batch made by "origin", to be dispatched by the runtime with "mode".
+    fn queue_batch(&mut self, origin: Origin<T::AccountId>, calls: Vec<T::RuntimeCall>, mode: BatchMode) {
+        self.queued.push(crate::support::Batch { origin, calls, mode }) ;
+    }
+
+    /// The weight declared for a batch of "calls", which is the weight of every one of the calls plus
+    /// the base weight of queuing the batch. The runtime refunds the weight the calls did not use up.
+    fn batch_weight(calls: &[T::RuntimeCall]) -> u64 {
+        calls.iter().fold(1, |weight: u64, call| weight.saturating_add(call.weight().0))
+    }
+}
+
+// Batches are dispatched by the runtime, which reports how many of their calls succeeded.
+impl<T: Config> crate::support::BatchCalls<Origin<T::AccountId>, T::RuntimeCall> for Pallet<T> {
+    fn take_batches(&mut self) -> Vec<crate::support::Batch<Origin<T::AccountId>, T::RuntimeCall>> {
+        core::mem::take(&mut self.queued)
+    }
+
+    fn batch_dispatched(&mut self, origin: Origin<T::AccountId>, mode: BatchMode, results: &[DispatchResult]) {
+        let succeeded = results.iter().filter(|res| res.is_ok()).count() as u32 ;
+        let failed = results.len() as u32 - succeeded ;
+        let event = match (mode, results.iter().find_map(|res| res.err())) {
+            (_, None) => Event::Completed { origin, succeeded },
+            (BatchMode::Forced, Some(_)) => Event::CompletedWithErrors { origin, succeeded, failed },
+            (_, Some(error)) => Event::Interrupted { origin, succeeded, error },
+        } ;
+        self.events.push(event) ;
+    }
+}
+
+// The utility pallet has no storage, so it has no mutations to journal.
+impl<T: Config> crate::support::Journaled for Pallet<T> {}
+
+// The utility pallet does not need to execute any logic as part of a block.
+impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {}
+
+// Only these functions will be called by the user from this pallet.
+// Every batch is made by the origin of the call queuing it, so a root batch can make privileged calls.
+#[macros::call]
+impl<T: Config> Pallet<T> {
+    /// Make the "calls" one after the other, stopping at the first call which fails.
+    /// The calls before it keep their effects, and the batch itself does not fail.
+    #[weight(Pallet::<T>::batch_weight(calls))]
+    pub fn batch(&mut self, origin: Origin<T::AccountId>, calls: Vec<T::RuntimeCall>) -> DispatchResult {
+        self.queue_batch(origin, calls, BatchMode::Interruptible) ;
+        Ok(())
+    }
+
+    /// Make the "calls" one after the other, failing as a whole if any of them fails.
+    /// In that case the effects of every call of the batch are undone.
+    #[weight(Pallet::<T>::batch_weight(calls))]
+    pub fn batch_all(&mut self, origin: Origin<T::AccountId>, calls: Vec<T::RuntimeCall>) -> DispatchResult {
+        self.queue_batch(origin, calls, BatchMode::Atomic) ;
+        Ok(())
+    }
+
+    /// Make every one of the "calls", even after some of them fail.
+    #[weight(Pallet::<T>::batch_weight(calls))]
+    pub fn force_batch(&mut self, origin: Origin<T::AccountId>, calls: Vec<T::RuntimeCall>) -> DispatchResult {
+        self.queue_batch(origin, calls, BatchMode::Forced) ;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::support::{Batch, BatchCalls, BatchMode, DispatchError, Origin, Weight} ;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestConfig ;
+    impl crate::system::Config for TestConfig {
+        type AccountId = String ;
+        type BlockNumber = u32 ;
+        type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
+    }
+
+    // A call which weighs as much as its name is long.
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestCall(&'static str) ;
+    impl crate::support::GetWeight for TestCall {
+        fn weight(&self) -> crate::support::Weight {
+            crate::support::Weight(self.0.len() as u64)
+        }
+    }
+
+    impl super::Config for TestConfig {
+        type RuntimeCall = TestCall ;
+    }
+
+    #[test]
+    fn queue_batches() {
+        let mut utility = super::Pallet::<TestConfig>::new() ;
+        let alice = Origin::Signed("alice".to_string()) ;
+
+        // Every batch is queued up with its origin and mode, in the order they were made.
+        assert_eq!(utility.batch(alice.clone(), vec![TestCall("first"), TestCall("second")]), Ok(())) ;
+        assert_eq!(utility.batch_all(Origin::Root, vec![TestCall("third")]), Ok(())) ;
+        assert_eq!(utility.force_batch(alice.clone(), vec![]), Ok(())) ;
+        assert_eq!(utility.take_batches(), vec![
+            Batch { origin: alice.clone(), calls: vec![TestCall("first"), TestCall("second")], mode: BatchMode::Interruptible },
+            Batch { origin: Origin::Root, calls: vec![TestCall("third")], mode: BatchMode::Atomic },
+            Batch { origin: alice.clone(), calls: vec![], mode: BatchMode::Forced },
+        ]) ;
+        assert_eq!(utility.take_batches(), vec![]) ;
+    }
+
+    #[test]
+    fn weigh_batches() {
+        // A batch weighs as much as its calls together, plus 1 for queuing it.
+        let calls = vec![TestCall("first"), TestCall("second")] ;
+        assert_eq!(super::Call::<TestConfig>::batch { calls: calls.clone() }.weight(), Weight(12)) ;
+        assert_eq!(super::Call::<TestConfig>::batch_all { calls: calls.clone() }.weight(), Weight(12)) ;
+        assert_eq!(super::Call::<TestConfig>::force_batch { calls: vec![] }.weight(), Weight(1)) ;
+    }
+
+    #[test]
+    fn report_batches() {
+        let mut utility = super::Pallet::<TestConfig>::new() ;
+        let alice = Origin::Signed("alice".to_string()) ;
+        let error = DispatchError::InsufficientFunds ;
+
+        utility.batch_dispatched(alice.clone(), BatchMode::Interruptible, &[Ok(()), Ok(())]) ;
+        utility.batch_dispatched(alice.clone(), BatchMode::Interruptible, &[Ok(()), Err(error)]) ;
+        utility.batch_dispatched(alice.clone(), BatchMode::Forced, &[Err(error), Ok(()), Err(error)]) ;
+        assert_eq!(utility.take_events(), vec![
+            super::Event::Completed { origin: alice.clone(), succeeded: 2 },
+            super::Event::Interrupted { origin: alice.clone(), succeeded: 1, error },
+            super::Event::CompletedWithErrors { origin: alice.clone(), succeeded: 1, failed: 2 },
+        ]) ;
+    }
+}
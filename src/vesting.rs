@@ -0,0 +1,359 @@
+use std::collections::BTreeMap ;
+use num::traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Saturating, Zero} ;
+use crate::support::{DispatchError, DispatchResult, Currency, Journal, LockId, LockableCurrency} ;
+
+/// The identifier of the lock this pallet puts on the balance which has not vested yet.
+const VESTING_ID: LockId = *b"vesting " ;
+
+/// The Config trait for the Vesting module.
+/// It contains the currency in which balance vests, and how block numbers relate to balances.
+pub trait Config: crate::balances::Config {
+    /// The currency in which balance vests. Balance which has not vested yet is locked in this currency,
+    /// so it cannot be transferred.
+    type Currency: LockableCurrency<Self::AccountId, Balance = Self::Balance> ;
+    /// Convert a number of blocks into a balance, so the vested balance can grow with every block.
+    fn block_number_to_balance(number: Self::BlockNumber) -> Self::Balance ;
+}
+
+/// The schedule along which the balance of an account vests.
+/// Nothing is vested before the "start" block, and everything is vested "duration" blocks later. In
+/// between, the vested balance grows linearly.
+#[derive(Debug, Clone, Copy, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VestingSchedule<Balance, BlockNumber> {
+    /// The balance which vests along this schedule.
+    pub total: Balance,
+    /// The block at which the balance starts to vest.
+    pub start: BlockNumber,
+    /// The number of blocks it takes for all of the balance to vest.
+    pub duration: BlockNumber,
+}
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<T: Config> {
+    /// Some "total" was granted to "who", vesting over time.
+    Created {
+        who: T::AccountId,
+        total: T::Balance,
+    },
+    /// The vested balance of "who" was unlocked, leaving "locked" balance which has not vested yet.
+    Unlocked {
+        who: T::AccountId,
+        locked: T::Balance,
+    },
+}
+
+/// The operations undoing the mutations of the storage of this pallet, as recorded in its journal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Undo<T: Config> {
+    /// Put the vesting schedules of an account back, or remove them if there were none.
+    Schedules(T::AccountId, Option<Vec<VestingSchedule<T::Balance, T::BlockNumber>>>),
+    /// Set the current block number back to the given one.
+    BlockNumber(T::BlockNumber),
+}
+
+/// This is the Vesting module.
+/// It keeps track of the schedules along which the balance granted to each account vests.
+#[derive(Debug, Clone, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "T::AccountId: serde::Serialize + serde::de::DeserializeOwned, T::Balance: serde::Serialize + serde::de::DeserializeOwned, T::BlockNumber: serde::Serialize + serde::de::DeserializeOwned"))]
+pub struct Pallet<T: Config> {
+    /// A map from an account to its vesting schedules, in the order they were created.
+    #[allow(clippy::type_complexity)]
+    schedules: BTreeMap<T::AccountId, Vec<VestingSchedule<T::Balance, T::BlockNumber>>>,
+    /// The current block number, as last seen through "on_initialize".
+    block_number: T::BlockNumber,
+    /// The events emitted since they were last taken.
+    /// Events are not part of the state, so they are never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    events: Vec<Event<T>>,
+    /// The journal of the mutations made while executing the last block.
+    /// Like events, the journal is not part of the state.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    journal: Journal<Undo<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+    /// Create a new instance of the vesting module.
+    pub fn new() -> Self {
+        Self {
+            schedules: BTreeMap::new(),
+            block_number: T::BlockNumber::zero(),
+            events: Vec::new(),
+            journal: Journal::new(),
+        }
+    }
+
+    /// Get the vesting schedules of "who", in the order they were created.
+    /// If the account has no vesting schedule, we return none.
+    pub fn schedules(&self, who: &T::AccountId) -> &[VestingSchedule<T::Balance, T::BlockNumber>] {
+        self.schedules.get(who).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Get the balance of "who" which has vested by block "now", along all of its schedules.
+    /// If the account has no vesting schedule, we return zero.
+    pub fn vested_balance(&self, who: &T::AccountId, now: T::BlockNumber) -> T::Balance {
+        self.schedules(who)
+            .iter()
+            .fold(T::Balance::zero(), |vested, schedule| vested.saturating_add(Self::vested(schedule, now)))
+    }
+
+    /// Get the balance which has vested along "schedule" by block "now".
+    fn vested(schedule: &VestingSchedule<T::Balance, T::BlockNumber>, now: T::BlockNumber) -> T::Balance {
+        if now < schedule.start {
+            return T::Balance::zero() ;
+        }
+        // A schedule ending beyond the largest block number never fully vests.
+        let fully_vested = schedule.start.checked_add(&schedule.duration).is_some_and(|end| now >= end) ;
+        if fully_vested {
+            return schedule.total ;
+        }
+
+        let elapsed = T::block_number_to_balance(now.checked_sub(&schedule.start).unwrap_or(now)) ;
+        let duration = T::block_number_to_balance(schedule.duration) ;
+        // If the product overflows, we divide first. This rounds down, so we never vest too much.
+        match schedule.total.checked_mul(&elapsed) {
+            Some(product) => product.checked_div(&duration),
+            None => schedule.total.checked_div(&duration).and_then(|per_block| per_block.checked_mul(&elapsed)),
+        }
+        .unwrap_or(T::Balance::zero())
+    }
+
+    /// Get the balance which has not vested along "schedule" by block "now", and must stay locked.
+    fn unvested(schedule: &VestingSchedule<T::Balance, T::BlockNumber>, now: T::BlockNumber) -> T::Balance {
+        schedule.total.checked_sub(&Self::vested(schedule, now)).unwrap_or(T::Balance::zero())
+    }
+
+    /// Get the balance of "who" which has not vested by block "now" along any of its schedules.
+    fn unvested_balance(&self, who: &T::AccountId, now: T::BlockNumber) -> T::Balance {
+        self.schedules(who)
+            .iter()
+            .fold(T::Balance::zero(), |unvested, schedule| unvested.saturating_add(Self::unvested(schedule, now)))
+    }
+
+    /// Store the vesting schedules of "who", removing the account if it has none left.
+    fn set_schedules(&mut self, who: &T::AccountId, schedules: Vec<VestingSchedule<T::Balance, T::BlockNumber>>) {
+        self.journal.record(|| Undo::Schedules(who.clone(), self.schedules.get(who).cloned())) ;
+        crate::support::restore_entry(&mut self.schedules, who.clone(), Some(schedules).filter(|schedules| !schedules.is_empty())) ;
+    }
+
+    /// Take all the events emitted since they were last taken, leaving no events behind.
+    pub fn take_events(&mut self) -> Vec<Event<T>> {
+        core::mem::take(&mut self.events)
+    }
+
+    /// Reverse a mutation of the storage, as recorded by "undo" in the journal.
+    fn undo(&mut self, undo: Undo<T>) {
+        match undo {
+            Undo::Schedules(who, schedules) => crate::support::restore_entry(&mut self.schedules, who, schedules),
+            Undo::BlockNumber(block_number) => self.block_number = block_number,
+        }
+    }
+}
+
+// The mutations of this pallet are journaled while a block is executed, so the runtime can undo them.
+impl<T: Config> crate::support::Journaled for Pallet<T> {
+    fn start_journal(&mut self) {
+        self.journal.start() ;
+    }
+
+    fn stop_journal(&mut self) {
+        self.journal.stop() ;
+    }
+
+    fn undo_journal(&mut self) {
+        for undo in self.journal.take() {
+            self.undo(undo) ;
+        }
+    }
+
+    fn checkpoint_journal(&mut self) -> crate::support::JournalCheckpoint {
+        self.journal.checkpoint()
+    }
+
+    fn commit_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        self.journal.commit(checkpoint) ;
+    }
+
+    fn rollback_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        for undo in self.journal.rollback(checkpoint) {
+            self.undo(undo) ;
+        }
+    }
+}
+
+// The vesting pallet only needs to know the current block number.
+impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {
+    fn on_initialize(&mut self, block_number: T::BlockNumber) {
+        self.journal.record(|| Undo::BlockNumber(self.block_number)) ;
+        self.block_number = block_number ;
+    }
+}
+
+// Only these functions will be called by the user from this pallet. They are given the currency of
+// the runtime, so they can lock the balance which has not vested yet.
+#[macros::call(context = T::Currency)]
+impl<T: Config> Pallet<T> {
+    /// Transfer some "total" from the caller to "who", which vests over "duration" blocks from block
+    /// "start" on.
+    /// The balance which has not vested yet is locked, so "who" cannot transfer it away. If "who"
+    /// already has some vesting schedules, this one vests alongside them, so nobody can keep "who"
+    /// from being granted a schedule.
+    pub fn vest(
+        &mut self,
+        caller: T::AccountId,
+        currency: &mut T::Currency,
+        who: T::AccountId,
+        total: T::Balance,
+        start: T::BlockNumber,
+        duration: T::BlockNumber
+    ) -> DispatchResult {
+        currency.transfer(&caller, &who, total) ?;
+        let mut schedules = self.schedules(&who).to_vec() ;
+        schedules.push(VestingSchedule { total, start, duration }) ;
+        self.set_schedules(&who, schedules) ;
+
+        let locked = self.unvested_balance(&who, self.block_number) ;
+        currency.set_lock(VESTING_ID, &who, locked) ;
+        self.events.push(Event::Created { who, total }) ;
+        Ok(())
+    }
+
+    /// Unlock the balance of the caller which has vested by now.
+    /// The schedules along which everything is vested are removed.
+    pub fn unlock(&mut self, caller: T::AccountId, currency: &mut T::Currency) -> DispatchResult {
+        if !self.schedules.contains_key(&caller) {
+            return Err(DispatchError::NotVesting) ;
+        }
+
+        let locked = self.unvested_balance(&caller, self.block_number) ;
+        currency.set_lock(VESTING_ID, &caller, locked) ;
+        let now = self.block_number ;
+        let schedules = self.schedules(&caller)
+            .iter()
+            .copied()
+            .filter(|schedule| !Self::unvested(schedule, now).is_zero())
+            .collect::<Vec<_>>() ;
+        if schedules.len() < self.schedules(&caller).len() {
+            self.set_schedules(&caller, schedules) ;
+        }
+        self.events.push(Event::Unlocked { who: caller, locked }) ;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::support::{DispatchError, Hooks} ;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestConfig ;
+    impl crate::system::Config for TestConfig {
+        type AccountId = String ;
+        type BlockNumber = u32 ;
+        type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
+    }
+
+    impl crate::balances::Config for TestConfig {
+        type Balance = u128 ;
+        const EXISTENTIAL_DEPOSIT: u128 = 1 ;
+        const TRANSFER_FEE: u128 = 0 ;
+        const MINIMUM_TRANSFER: u128 = 0 ;
+        fn fee_account() -> String {
+            "fees".to_string()
+        }
+        type AccountStore = std::collections::BTreeMap<String, crate::balances::AccountData<u128>> ;
+    }
+
+    impl super::Config for TestConfig {
+        type Currency = crate::balances::Pallet<TestConfig> ;
+        fn block_number_to_balance(number: u32) -> u128 {
+            number.into()
+        }
+    }
+
+    #[test]
+    fn vested_balance() {
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+        let mut vesting = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 1001) ;
+
+        // 1000 vests over the 10 blocks after block 5.
+        assert_eq!(vesting.vest(alice.clone(), &mut balances, bob.clone(), 1000, 5, 10), Ok(())) ;
+        assert_eq!(vesting.vested_balance(&bob, 0), 0) ;
+        assert_eq!(vesting.vested_balance(&bob, 5), 0) ;
+        assert_eq!(vesting.vested_balance(&bob, 6), 100) ;
+        assert_eq!(vesting.vested_balance(&bob, 12), 700) ;
+        assert_eq!(vesting.vested_balance(&bob, 15), 1000) ;
+        assert_eq!(vesting.vested_balance(&bob, 100), 1000) ;
+
+        // Accounts without a schedule have nothing vested.
+        assert_eq!(vesting.vested_balance(&alice, 100), 0) ;
+
+        // Another schedule vests alongside the first one, however small it is.
+        assert_eq!(vesting.vest(alice.clone(), &mut balances, bob.clone(), 1, 5, 10), Ok(())) ;
+        assert_eq!(vesting.schedules(&bob).len(), 2) ;
+        assert_eq!(vesting.vested_balance(&bob, 12), 700) ;
+        assert_eq!(vesting.vested_balance(&bob, 15), 1001) ;
+    }
+
+    #[test]
+    fn unvested_balance_is_locked() {
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+        let mut vesting = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 1000) ;
+        let _ = vesting.vest(alice.clone(), &mut balances, bob.clone(), 100, 0, 10) ;
+
+        // Nothing is vested yet, so nothing can be transferred.
+        assert_eq!(balances.balance(&bob), 100) ;
+        assert_eq!(balances.transfer(&bob, &alice, 1), Err(DispatchError::LiquidityRestrictions)) ;
+
+        // The vested balance can be transferred once it is unlocked.
+        vesting.on_initialize(4) ;
+        assert_eq!(balances.transfer(&bob, &alice, 40), Err(DispatchError::LiquidityRestrictions)) ;
+        assert_eq!(vesting.unlock(bob.clone(), &mut balances), Ok(())) ;
+        assert_eq!(balances.locked_balance(&bob), 60) ;
+        assert_eq!(balances.transfer(&bob, &alice, 41), Err(DispatchError::LiquidityRestrictions)) ;
+        assert_eq!(balances.transfer(&bob, &alice, 40), Ok(())) ;
+
+        // Once everything is vested, the schedule is removed.
+        vesting.on_initialize(10) ;
+        assert_eq!(vesting.unlock(bob.clone(), &mut balances), Ok(())) ;
+        assert_eq!(balances.locked_balance(&bob), 0) ;
+        assert_eq!(vesting.schedules(&bob), &[]) ;
+        assert_eq!(vesting.unlock(bob.clone(), &mut balances), Err(DispatchError::NotVesting)) ;
+    }
+
+    #[test]
+    fn several_schedules() {
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+        let mut vesting = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let _ = balances.mint(&alice, 1000) ;
+        let _ = vesting.vest(alice.clone(), &mut balances, bob.clone(), 100, 0, 10) ;
+        let _ = vesting.vest(alice.clone(), &mut balances, bob.clone(), 50, 0, 4) ;
+
+        // The balance which has not vested along any of the schedules stays locked.
+        assert_eq!(balances.locked_balance(&bob), 150) ;
+
+        // Unlocking only removes the schedules along which everything is vested.
+        vesting.on_initialize(4) ;
+        assert_eq!(vesting.unlock(bob.clone(), &mut balances), Ok(())) ;
+        assert_eq!(balances.locked_balance(&bob), 60) ;
+        assert_eq!(vesting.schedules(&bob), &[super::VestingSchedule { total: 100, start: 0, duration: 10 }]) ;
+    }
+}
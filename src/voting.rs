@@ -0,0 +1,315 @@
+use core::fmt::Debug ;
+use std::collections::BTreeMap ;
+use num::traits::{CheckedAdd, Zero} ;
+use crate::support::{DispatchError, DispatchResult, Currency, Journal} ;
+
+/// The Config trait for the Voting module.
+/// It contains the types of proposals, and the currency which gives weight to votes.
+pub trait Config: crate::balances::Config {
+    /// A type which identifies a proposal.
+    type ProposalId: Debug + Ord + Clone ;
+    /// A type which describes what a proposal is about.
+    type Description: Debug + Clone + PartialEq ;
+    /// The currency whose balance gives weight to votes.
+    type Currency: Currency<Self::AccountId, Balance = Self::Balance> ;
+}
+
+/// The information stored about a proposal.
+#[derive(Debug, Clone, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "T::AccountId: serde::Serialize + serde::de::DeserializeOwned, T::Description: serde::Serialize + serde::de::DeserializeOwned"))]
+pub struct Proposal<T: Config> {
+    /// The account which created the proposal.
+    pub proposer: T::AccountId,
+    /// What the proposal is about.
+    pub description: T::Description,
+}
+
+/// The information stored about a vote.
+#[derive(Debug, Clone, Copy, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vote<Balance> {
+    /// Whether the vote is in favour of the proposal.
+    pub aye: bool,
+    /// The balance of the voter at the time they voted.
+    pub weight: Balance,
+}
+
+/// The events emitted by this pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<T: Config> {
+    /// Some proposal "id" was created by its "proposer".
+    Proposed {
+        id: T::ProposalId,
+        proposer: T::AccountId,
+    },
+    /// Some "voter" voted on the proposal "id", with their balance as "weight".
+    Voted {
+        id: T::ProposalId,
+        voter: T::AccountId,
+        aye: bool,
+        weight: T::Balance,
+    },
+}
+
+/// The operations undoing the mutations of the storage of this pallet, as recorded in its journal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Undo<T: Config> {
+    /// Remove a proposal which was created.
+    Proposal(T::ProposalId),
+    /// Put the previous vote of a voter on a proposal back, or remove the vote if there was none.
+    Vote((T::ProposalId, T::AccountId), Option<Vote<T::Balance>>),
+}
+
+/// This is the Voting module.
+/// It keeps track of proposals, and of the votes cast on them.
+#[derive(Debug, Clone, PartialEq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "T::AccountId: serde::Serialize + serde::de::DeserializeOwned, T::Balance: serde::Serialize + serde::de::DeserializeOwned, T::ProposalId: serde::Serialize + serde::de::DeserializeOwned, T::Description: serde::Serialize + serde::de::DeserializeOwned"))]
+pub struct Pallet<T: Config> {
+    /// A map from a proposal id to the proposal.
+    proposals: BTreeMap<T::ProposalId, Proposal<T>>,
+    /// A map from a proposal id and a voter to their vote.
+    /// The keys are not strings, so they are serialized as a list.
+    #[cfg_attr(feature = "serde", serde(with = "crate::support::map_as_seq"))]
+    #[allow(clippy::type_complexity)]
+    votes: BTreeMap<(T::ProposalId, T::AccountId), Vote<T::Balance>>,
+    /// The events emitted since they were last taken.
+    /// Events are not part of the state, so they are never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    events: Vec<Event<T>>,
+    /// The journal of the mutations made while executing the last block.
+    /// Like events, the journal is not part of the state.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[encode(skip)]
+    journal: Journal<Undo<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+    /// Create a new instance of the voting module.
+    pub fn new() -> Self {
+        Self {
+            proposals: BTreeMap::new(),
+            votes: BTreeMap::new(),
+            events: Vec::new(),
+            journal: Journal::new(),
+        }
+    }
+
+    /// Get the proposal "id", if it exists.
+    pub fn proposal(&self, id: &T::ProposalId) -> Option<&Proposal<T>> {
+        self.proposals.get(id)
+    }
+
+    /// Get the vote of "voter" on the proposal "id", if they voted.
+    pub fn vote_of(&self, id: &T::ProposalId, voter: &T::AccountId) -> Option<&Vote<T::Balance>> {
+        self.votes.get(&(id.clone(), voter.clone()))
+    }
+
+    /// Get the total weight of the votes in favour of, and against, the proposal "id".
+    /// A total which overflows saturates at the largest weight which could be counted.
+    pub fn tally(&self, id: &T::ProposalId) -> (T::Balance, T::Balance) {
+        let mut ayes = T::Balance::zero() ;
+        let mut nays = T::Balance::zero() ;
+        for ((proposal, _), vote) in &self.votes {
+            if proposal != id {
+                continue ;
+            }
+            let total = if vote.aye { &mut ayes } else { &mut nays } ;
+            *total = total.checked_add(&vote.weight).unwrap_or(*total) ;
+        }
+        (ayes, nays)
+    }
+
+    /// Take all the events emitted since they were last taken, leaving no events behind.
+    pub fn take_events(&mut self) -> Vec<Event<T>> {
+        core::mem::take(&mut self.events)
+    }
+
+    /// Reverse a mutation of the storage, as recorded by "undo" in the journal.
+    fn undo(&mut self, undo: Undo<T>) {
+        match undo {
+            Undo::Proposal(id) => { self.proposals.remove(&id) ; },
+            Undo::Vote(key, vote) => crate::support::restore_entry(&mut self.votes, key, vote),
+        }
+    }
+}
+
+// The mutations of this pallet are journaled while a block is executed, so the runtime can undo them.
+impl<T: Config> crate::support::Journaled for Pallet<T> {
+    fn start_journal(&mut self) {
+        self.journal.start() ;
+    }
+
+    fn stop_journal(&mut self) {
+        self.journal.stop() ;
+    }
+
+    fn undo_journal(&mut self) {
+        for undo in self.journal.take() {
+            self.undo(undo) ;
+        }
+    }
+
+    fn checkpoint_journal(&mut self) -> crate::support::JournalCheckpoint {
+        self.journal.checkpoint()
+    }
+
+    fn commit_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        self.journal.commit(checkpoint) ;
+    }
+
+    fn rollback_journal(&mut self, checkpoint: crate::support::JournalCheckpoint) {
+        for undo in self.journal.rollback(checkpoint) {
+            self.undo(undo) ;
+        }
+    }
+}
+
+// The voting pallet does not need to execute any logic as part of a block.
+impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {}
+
+// Only these functions will be called by the user from this pallet. They are given the currency of
+// the runtime, so votes can be weighted by balance.
+#[macros::call(context = T::Currency)]
+impl<T: Config> Pallet<T> {
+    /// Create a new proposal "id", described by "description".
+    /// This function fails if a proposal with the same id already exists.
+    pub fn create_proposal(
+        &mut self,
+        caller: T::AccountId,
+        id: T::ProposalId,
+        description: T::Description
+    ) -> DispatchResult {
+        if self.proposals.contains_key(&id) {
+            return Err(DispatchError::ProposalExists) ;
+        }
+        self.proposals.insert(id.clone(), Proposal { proposer: caller.clone(), description }) ;
+        self.journal.record(|| Undo::Proposal(id.clone())) ;
+
+        self.events.push(Event::Proposed { id, proposer: caller }) ;
+        Ok(())
+    }
+
+    /// Vote in favour of, or against, the proposal "id".
+    /// The vote is weighted by the caller's balance right now. Voting again replaces the previous
+    /// vote of the caller.
+    pub fn vote(
+        &mut self,
+        caller: T::AccountId,
+        currency: &mut T::Currency,
+        id: T::ProposalId,
+        aye: bool
+    ) -> DispatchResult {
+        if !self.proposals.contains_key(&id) {
+            return Err(DispatchError::ProposalNotFound) ;
+        }
+        let weight = currency.total_balance(&caller) ;
+        let previous = self.votes.insert((id.clone(), caller.clone()), Vote { aye, weight }) ;
+        self.journal.record(|| Undo::Vote((id.clone(), caller.clone()), previous)) ;
+
+        self.events.push(Event::Voted { id, voter: caller, aye, weight }) ;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::support::DispatchError ;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestConfig ;
+    impl crate::system::Config for TestConfig {
+        type AccountId = String ;
+        type BlockNumber = u32 ;
+        type Nonce = u32 ;
+        type Hash = u64 ;
+        type Hashing = crate::support::Fnv ;
+        type Moment = u64 ;
+        const BLOCK_HASH_COUNT: u32 = 10 ;
+        const MAX_BLOCK_WEIGHT: crate::support::Weight = crate::support::Weight(100) ;
+        type RuntimeEvent = () ;
+    }
+
+    impl crate::balances::Config for TestConfig {
+        type Balance = u128 ;
+        const EXISTENTIAL_DEPOSIT: u128 = 1 ;
+        const TRANSFER_FEE: u128 = 0 ;
+        const MINIMUM_TRANSFER: u128 = 0 ;
+        fn fee_account() -> String {
+            "fees".to_string()
+        }
+        type AccountStore = std::collections::BTreeMap<String, crate::balances::AccountData<u128>> ;
+    }
+
+    impl super::Config for TestConfig {
+        type ProposalId = u32 ;
+        type Description = &'static str ;
+        type Currency = crate::balances::Pallet<TestConfig> ;
+    }
+
+    #[test]
+    fn create_proposal() {
+        let mut voting = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+
+        assert_eq!(voting.create_proposal(alice.clone(), 1, "Hello"), Ok(())) ;
+        assert_eq!(
+            voting.proposal(&1),
+            Some(&super::Proposal { proposer: alice.clone(), description: "Hello" })
+        ) ;
+
+        // Proposal ids cannot be reused.
+        assert_eq!(voting.create_proposal(alice.clone(), 1, "World"), Err(DispatchError::ProposalExists)) ;
+        assert_eq!(voting.proposal(&1).map(|proposal| proposal.description), Some("Hello")) ;
+        assert_eq!(voting.take_events(), vec![super::Event::Proposed { id: 1, proposer: alice }]) ;
+    }
+
+    #[test]
+    fn vote_and_tally() {
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+        let mut voting = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let bob = "bob".to_string() ;
+        let charlie = "charlie".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+        let _ = balances.mint(&bob, 30) ;
+        let _ = balances.mint(&charlie, 20) ;
+
+        // Proposals must exist to be voted on.
+        assert_eq!(voting.vote(alice.clone(), &mut balances, 1, true), Err(DispatchError::ProposalNotFound)) ;
+        let _ = voting.create_proposal(alice.clone(), 1, "Hello") ;
+
+        assert_eq!(voting.vote(alice.clone(), &mut balances, 1, true), Ok(())) ;
+        assert_eq!(voting.vote(bob.clone(), &mut balances, 1, false), Ok(())) ;
+        assert_eq!(voting.vote(charlie.clone(), &mut balances, 1, false), Ok(())) ;
+        assert_eq!(voting.tally(&1), (100, 50)) ;
+
+        // Votes are weighted by the balance at the time of voting, not by the current balance.
+        let _ = balances.mint(&bob, 1000) ;
+        assert_eq!(voting.tally(&1), (100, 50)) ;
+
+        // Proposals nobody voted on have no weight either way.
+        let _ = voting.create_proposal(bob.clone(), 2, "World") ;
+        assert_eq!(voting.tally(&2), (0, 0)) ;
+    }
+
+    #[test]
+    fn change_vote() {
+        let mut balances = crate::balances::Pallet::<TestConfig>::new() ;
+        let mut voting = super::Pallet::<TestConfig>::new() ;
+        let alice = "alice".to_string() ;
+        let _ = balances.mint(&alice, 100) ;
+        let _ = voting.create_proposal(alice.clone(), 1, "Hello") ;
+
+        let _ = voting.vote(alice.clone(), &mut balances, 1, true) ;
+        assert_eq!(voting.tally(&1), (100, 0)) ;
+
+        // Voting again replaces the first vote, weighted by the new balance.
+        let _ = balances.mint(&alice, 50) ;
+        assert_eq!(voting.vote(alice.clone(), &mut balances, 1, false), Ok(())) ;
+        assert_eq!(voting.tally(&1), (0, 150)) ;
+        assert_eq!(voting.vote_of(&1, &alice), Some(&super::Vote { aye: false, weight: 150 })) ;
+    }
+}